@@ -258,6 +258,16 @@ pub fn expand_test_or_bench(
                                     field("compile_fail", cx.expr_bool(sp, false)),
                                     // no_run: true | false
                                     field("no_run", cx.expr_bool(sp, false)),
+                                    // timeout: None | Some("...")
+                                    field(
+                                        "timeout",
+                                        match test_timeout(cx, &item) {
+                                            None => cx.expr_none(sp),
+                                            Some(timeout) => {
+                                                cx.expr_some(sp, cx.expr_str(sp, timeout))
+                                            }
+                                        },
+                                    ),
                                     // should_panic: ...
                                     field(
                                         "should_panic",
@@ -360,6 +370,12 @@ fn should_fail(sess: &Session, i: &ast::Item) -> bool {
     sess.contains_name(&i.attrs, sym::allow_fail)
 }
 
+/// Returns the time limit set with `#[test_timeout = "..."]`. It is parsed by libtest, like
+/// `--test-timeout`, so a malformed duration fails the test when it is run.
+fn test_timeout(cx: &ExtCtxt<'_>, i: &ast::Item) -> Option<Symbol> {
+    cx.sess.find_by_name(&i.attrs, sym::test_timeout)?.value_str()
+}
+
 fn should_panic(cx: &ExtCtxt<'_>, i: &ast::Item) -> ShouldPanic {
     match cx.sess.find_by_name(&i.attrs, sym::should_panic) {
         Some(attr) => {
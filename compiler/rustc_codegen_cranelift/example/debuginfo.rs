@@ -0,0 +1,15 @@
+// Checked with llvm-dwarfdump by scripts/tests.sh: the arguments of `scale` need a location and the
+// constant `offset` a signed value.
+
+#[no_mangle]
+#[inline(never)]
+pub fn scale(value: i64, factor: i64) -> i64 {
+    let offset = -3i64;
+    let scaled = value * factor;
+    scaled + offset
+}
+
+fn main() {
+    let point = (std::env::args().count() as i64, 2);
+    assert_eq!(scale(point.0, point.1), -1);
+}
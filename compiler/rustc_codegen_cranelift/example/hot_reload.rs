@@ -0,0 +1,24 @@
+// Run by `scripts/tests.sh` in hot reload jit mode. The program edits its own source file and
+// waits for the new version of `answer` to be swapped in, so it must be run from a copy.
+
+use std::time::{Duration, Instant};
+
+fn answer() -> u32 {
+    41
+}
+
+fn main() {
+    assert_eq!(answer(), 41);
+
+    // Built from parts so that the replacement doesn't change `main` itself.
+    let old_body = ["    4", "1\n"].concat();
+    let new_body = ["    4", "2\n"].concat();
+    let source = std::fs::read_to_string(file!()).unwrap();
+    std::fs::write(file!(), source.replacen(&old_body, &new_body, 1)).unwrap();
+
+    let start = Instant::now();
+    while answer() != 42 {
+        assert!(start.elapsed() < Duration::from_secs(60), "`answer` was not reloaded");
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
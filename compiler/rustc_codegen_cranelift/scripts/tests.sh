@@ -47,6 +47,13 @@ function base_sysroot_tests() {
 
         echo "[JIT-lazy] std_example"
         $MY_RUSTC -Cllvm-args=mode=jit-lazy -Cprefer-dynamic example/std_example.rs --cfg lazy_jit --target "$HOST_TRIPLE"
+
+        echo "[JIT-hot-reload] std_example"
+        $MY_RUSTC -Cllvm-args=mode=jit-hot-reload -Cprefer-dynamic -Cincremental=target/out/hot_reload_incr example/std_example.rs --target "$HOST_TRIPLE"
+
+        echo "[JIT-hot-reload] hot_reload"
+        cp example/hot_reload.rs target/out/hot_reload.rs
+        $MY_RUSTC -Cllvm-args=mode=jit-hot-reload -Cprefer-dynamic -Cincremental=target/out/hot_reload_incr target/out/hot_reload.rs --target "$HOST_TRIPLE"
     else
         echo "[JIT] std_example (skipped)"
     fi
@@ -71,6 +78,22 @@ function base_sysroot_tests() {
     echo "[AOT] mod_bench"
     $MY_RUSTC example/mod_bench.rs --crate-type bin --target "$TARGET_TRIPLE"
     $RUN_WRAPPER ./target/out/mod_bench
+
+    echo "[AOT] debuginfo"
+    # ConstDebugInfo turns the debuginfo of `offset` into a constant.
+    $MY_RUSTC example/debuginfo.rs --crate-type bin -Zmir-opt-level=1 -Zunsound-mir-opts --target "$TARGET_TRIPLE"
+    $RUN_WRAPPER ./target/out/debuginfo
+    if [[ "$TARGET_TRIPLE" = x86_64* ]] && command -v llvm-dwarfdump > /dev/null; then
+        llvm-dwarfdump --name=scale --show-children ./target/out/debuginfo > target/out/debuginfo.txt
+        for arg in value factor; do
+            grep -A4 "DW_AT_name.*(\"$arg\")" target/out/debuginfo.txt | grep -q "DW_OP_" \
+                || (echo "missing location of \`$arg\`"; false)
+        done
+        grep -A3 'DW_AT_name.*("offset")' target/out/debuginfo.txt | grep -q 'DW_AT_const_value.*(-3)' \
+            || (echo "missing signed value of \`offset\`"; false)
+    else
+        echo "[DWARF] debuginfo (skipped)"
+    fi
 }
 
 function extended_sysroot_tests() {
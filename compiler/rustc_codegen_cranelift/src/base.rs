@@ -34,7 +34,10 @@ pub(crate) fn codegen_fn<'tcx>(
     let mut func = std::mem::replace(&mut cx.cached_context.func, Function::new());
     func.name = ExternalName::user(0, func_id.as_u32());
     func.signature = sig;
-    func.collect_debug_info();
+    if cx.debug_context.is_some() {
+        // Tracks the values of locals for their debuginfo locations.
+        func.collect_debug_info();
+    }
 
     let mut bcx = FunctionBuilder::new(&mut func, &mut func_ctx);
 
@@ -2,6 +2,7 @@
 
 mod emit;
 mod line_info;
+mod types;
 mod unwind;
 
 use crate::prelude::*;
@@ -19,6 +20,8 @@
 };
 use gimli::{Encoding, Format, LineEncoding, RunTimeEndian, X86_64};
 
+use crate::pointer::PointerBase;
+
 pub(crate) use emit::{DebugReloc, DebugRelocName};
 pub(crate) use unwind::UnwindContext;
 
@@ -109,97 +112,16 @@ pub(crate) fn new(tcx: TyCtxt<'tcx>, isa: &dyn TargetIsa) -> Self {
         }
     }
 
-    fn dwarf_ty(&mut self, ty: Ty<'tcx>) -> UnitEntryId {
-        if let Some(type_id) = self.types.get(ty) {
-            return *type_id;
-        }
-
-        let new_entry = |dwarf: &mut DwarfUnit, tag| dwarf.unit.add(dwarf.unit.root(), tag);
-
-        let primitive = |dwarf: &mut DwarfUnit, ate| {
-            let type_id = new_entry(dwarf, gimli::DW_TAG_base_type);
-            let type_entry = dwarf.unit.get_mut(type_id);
-            type_entry.set(gimli::DW_AT_encoding, AttributeValue::Encoding(ate));
-            type_id
-        };
-
-        let name = format!("{}", ty);
-        let layout = self.tcx.layout_of(ParamEnv::reveal_all().and(ty)).unwrap();
-
-        let type_id = match ty.kind() {
-            ty::Bool => primitive(&mut self.dwarf, gimli::DW_ATE_boolean),
-            ty::Char => primitive(&mut self.dwarf, gimli::DW_ATE_UTF),
-            ty::Uint(_) => primitive(&mut self.dwarf, gimli::DW_ATE_unsigned),
-            ty::Int(_) => primitive(&mut self.dwarf, gimli::DW_ATE_signed),
-            ty::Float(_) => primitive(&mut self.dwarf, gimli::DW_ATE_float),
-            ty::Ref(_, pointee_ty, _mutbl)
-            | ty::RawPtr(ty::TypeAndMut { ty: pointee_ty, mutbl: _mutbl }) => {
-                let type_id = new_entry(&mut self.dwarf, gimli::DW_TAG_pointer_type);
-
-                // Ensure that type is inserted before recursing to avoid duplicates
-                self.types.insert(ty, type_id);
-
-                let pointee = self.dwarf_ty(pointee_ty);
-
-                let type_entry = self.dwarf.unit.get_mut(type_id);
-
-                //type_entry.set(gimli::DW_AT_mutable, AttributeValue::Flag(mutbl == rustc_hir::Mutability::Mut));
-                type_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(pointee));
-
-                type_id
-            }
-            ty::Adt(adt_def, _substs) if adt_def.is_struct() && !layout.is_unsized() => {
-                let type_id = new_entry(&mut self.dwarf, gimli::DW_TAG_structure_type);
-
-                // Ensure that type is inserted before recursing to avoid duplicates
-                self.types.insert(ty, type_id);
-
-                let variant = adt_def.non_enum_variant();
-
-                for (field_idx, field_def) in variant.fields.iter().enumerate() {
-                    let field_offset = layout.fields.offset(field_idx);
-                    let field_layout = layout
-                        .field(
-                            &layout::LayoutCx { tcx: self.tcx, param_env: ParamEnv::reveal_all() },
-                            field_idx,
-                        )
-                        .unwrap();
-
-                    let field_type = self.dwarf_ty(field_layout.ty);
-
-                    let field_id = self.dwarf.unit.add(type_id, gimli::DW_TAG_member);
-                    let field_entry = self.dwarf.unit.get_mut(field_id);
-
-                    field_entry.set(
-                        gimli::DW_AT_name,
-                        AttributeValue::String(field_def.ident.as_str().to_string().into_bytes()),
-                    );
-                    field_entry.set(
-                        gimli::DW_AT_data_member_location,
-                        AttributeValue::Udata(field_offset.bytes()),
-                    );
-                    field_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(field_type));
-                }
-
-                type_id
-            }
-            _ => new_entry(&mut self.dwarf, gimli::DW_TAG_structure_type),
-        };
-
-        let type_entry = self.dwarf.unit.get_mut(type_id);
-
-        type_entry.set(gimli::DW_AT_name, AttributeValue::String(name.into_bytes()));
-        type_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(layout.size.bytes()));
-
-        self.types.insert(ty, type_id);
-
-        type_id
-    }
-
-    fn define_local(&mut self, scope: UnitEntryId, name: String, ty: Ty<'tcx>) -> UnitEntryId {
+    fn define_local(
+        &mut self,
+        scope: UnitEntryId,
+        tag: gimli::DwTag,
+        name: String,
+        ty: Ty<'tcx>,
+    ) -> UnitEntryId {
         let dw_ty = self.dwarf_ty(ty);
 
-        let var_id = self.dwarf.unit.add(scope, gimli::DW_TAG_variable);
+        let var_id = self.dwarf.unit.add(scope, tag);
         let var_entry = self.dwarf.unit.get_mut(var_id);
 
         var_entry.set(gimli::DW_AT_name, AttributeValue::String(name.into_bytes()));
@@ -247,34 +169,78 @@ pub(crate) fn define_function(
         // Using Udata for DW_AT_high_pc requires at least DWARF4
         func_entry.set(gimli::DW_AT_high_pc, AttributeValue::Udata(u64::from(end)));
 
-        // FIXME make it more reliable and implement scopes before re-enabling this.
-        if false {
-            let value_labels_ranges = context.build_value_labels_ranges(isa).unwrap();
-
-            for (local, _local_decl) in mir.local_decls.iter_enumerated() {
-                let ty = self.tcx.subst_and_normalize_erasing_regions(
-                    instance.substs,
-                    ty::ParamEnv::reveal_all(),
-                    mir.local_decls[local].ty,
-                );
-                let var_id = self.define_local(entry_id, format!("{:?}", local), ty);
-
-                let location = place_location(
-                    self,
-                    isa,
-                    symbol,
-                    context,
-                    &local_map,
-                    &value_labels_ranges,
-                    Place { local, projection: ty::List::empty() },
-                );
-
-                let var_entry = self.dwarf.unit.get_mut(var_id);
-                var_entry.set(gimli::DW_AT_location, location);
+        // Without value label ranges the variables get no location at all, which debuggers show
+        // as optimized out.
+        let value_labels_ranges = context.build_value_labels_ranges(isa).ok();
+
+        // FIXME add variables to the lexical block of their scope instead of the function
+        for var_debug_info in &mir.var_debug_info {
+            let name = var_debug_info.name.to_string();
+            match var_debug_info.value {
+                VarDebugInfoContents::Place(place) => {
+                    let ty = self.tcx.subst_and_normalize_erasing_regions(
+                        instance.substs,
+                        ty::ParamEnv::reveal_all(),
+                        place.ty(mir, self.tcx).ty,
+                    );
+                    let tag = if place.projection.is_empty()
+                        && mir.args_iter().any(|arg| arg == place.local)
+                    {
+                        gimli::DW_TAG_formal_parameter
+                    } else {
+                        gimli::DW_TAG_variable
+                    };
+                    let var_id = self.define_local(entry_id, tag, name, ty);
+
+                    if let Some(value_labels_ranges) = &value_labels_ranges {
+                        let location = place_location(
+                            self,
+                            isa,
+                            symbol,
+                            context,
+                            &local_map,
+                            value_labels_ranges,
+                            place,
+                        );
+
+                        let var_entry = self.dwarf.unit.get_mut(var_id);
+                        var_entry.set(gimli::DW_AT_location, location);
+                    }
+                }
+                VarDebugInfoContents::Const(ref constant) => {
+                    let literal = self.tcx.subst_and_normalize_erasing_regions(
+                        instance.substs,
+                        ty::ParamEnv::reveal_all(),
+                        constant.literal,
+                    );
+                    let ty = literal.ty();
+                    let var_id = self.define_local(entry_id, gimli::DW_TAG_variable, name, ty);
+
+                    if ty.is_integral() || ty.is_bool() || ty.is_char() {
+                        let value = literal
+                            .try_eval_bits(self.tcx, ty::ParamEnv::reveal_all(), ty)
+                            .and_then(|bits| {
+                                if ty.is_signed() {
+                                    let size = self
+                                        .tcx
+                                        .layout_of(ParamEnv::reveal_all().and(ty))
+                                        .unwrap()
+                                        .size;
+                                    let value = size.sign_extend(bits) as i128;
+                                    i64::try_from(value).ok().map(AttributeValue::Sdata)
+                                } else {
+                                    u64::try_from(bits).ok().map(AttributeValue::Udata)
+                                }
+                            });
+                        // FIXME emit 128 bit constants that don't fit in 64 bits as a block
+                        if let Some(value) = value {
+                            let var_entry = self.dwarf.unit.get_mut(var_id);
+                            var_entry.set(gimli::DW_AT_const_value, value);
+                        }
+                    }
+                }
             }
         }
-
-        // FIXME create locals for all entries in mir.var_debug_info
     }
 }
 
@@ -290,41 +256,74 @@ fn place_location<'tcx>(
     >,
     place: Place<'tcx>,
 ) -> AttributeValue {
-    assert!(place.projection.is_empty()); // FIXME implement them
-
-    match local_map[place.local].inner() {
-        CPlaceInner::Var(_local, var) => {
-            let value_label = cranelift_codegen::ir::ValueLabel::new(var.index());
-            if let Some(value_loc_ranges) = value_labels_ranges.get(&value_label) {
-                let loc_list = LocationList(
-                    value_loc_ranges
-                        .iter()
-                        .map(|value_loc_range| Location::StartEnd {
-                            begin: Address::Symbol {
-                                symbol,
-                                addend: i64::from(value_loc_range.start),
-                            },
-                            end: Address::Symbol { symbol, addend: i64::from(value_loc_range.end) },
-                            data: translate_loc(
-                                isa,
-                                value_loc_range.loc,
-                                &context.func.stack_slots,
+    let tcx = debug_context.tcx;
+    let cplace = local_map[place.local];
+
+    match *cplace.inner() {
+        CPlaceInner::Var(_local, var) if place.projection.is_empty() => vars_location(
+            debug_context,
+            isa,
+            symbol,
+            context,
+            value_labels_ranges,
+            &[(var, Size::ZERO, cplace.layout().size)],
+        ),
+        CPlaceInner::VarPair(_local, var1, var2) if place.projection.is_empty() => {
+            let (a_scalar, b_scalar) = match cplace.layout().abi {
+                Abi::ScalarPair(ref a, ref b) => (a, b),
+                _ => unreachable!("{:?}", cplace.layout()),
+            };
+            let a_size = a_scalar.value.size(&tcx);
+            let b_offset = a_size.align_to(b_scalar.value.align(&tcx).abi);
+            vars_location(
+                debug_context,
+                isa,
+                symbol,
+                context,
+                value_labels_ranges,
+                &[(var1, Size::ZERO, a_size), (var2, b_offset, b_scalar.value.size(&tcx))],
+            )
+        }
+        CPlaceInner::Addr(ptr, None) => {
+            let (stack_slot, mut offset) = match ptr.debug_base_and_offset() {
+                (PointerBase::Stack(stack_slot), offset) => (stack_slot, i64::from(offset)),
+                // FIXME implement this (used by arguments passed by reference)
+                (PointerBase::Addr(_), _) | (PointerBase::Dangling(_), _) => {
+                    return AttributeValue::Exprloc(Expression::new());
+                }
+            };
+
+            let mut layout = cplace.layout();
+            for elem in place.projection {
+                match elem {
+                    ProjectionElem::Field(field, _) => {
+                        offset +=
+                            i64::try_from(layout.fields.offset(field.index()).bytes()).unwrap();
+                        layout = layout
+                            .field(
+                                &layout::LayoutCx { tcx, param_env: ParamEnv::reveal_all() },
+                                field.index(),
                             )
-                            .unwrap(),
-                        })
-                        .collect(),
-                );
-                let loc_list_id = debug_context.dwarf.unit.locations.add(loc_list);
-
-                AttributeValue::LocationListRef(loc_list_id)
-            } else {
-                // FIXME set value labels for unused locals
-
-                AttributeValue::Exprloc(Expression::new())
+                            .unwrap();
+                    }
+                    // FIXME implement this (used by captured variables of closures)
+                    _ => return AttributeValue::Exprloc(Expression::new()),
+                }
             }
+
+            let (stack_pointer, _frame_pointer) = match stack_registers(isa) {
+                Some(registers) => registers,
+                None => return AttributeValue::Exprloc(Expression::new()),
+            };
+            // Stack slot offsets are relative to the stack pointer after the prologue.
+            let stack_slot_offset =
+                context.mach_compile_result.as_ref().unwrap().stackslot_offsets[stack_slot];
+            let mut expr = Expression::new();
+            expr.op_breg(stack_pointer, i64::from(stack_slot_offset) + offset);
+            AttributeValue::Exprloc(expr)
         }
-        CPlaceInner::VarPair(_, _, _) => {
-            // FIXME implement this
+        CPlaceInner::Var(_, _) | CPlaceInner::VarPair(_, _, _) => {
+            // FIXME implement projections of places stored in variables
 
             AttributeValue::Exprloc(Expression::new())
         }
@@ -333,50 +332,124 @@ fn place_location<'tcx>(
 
             AttributeValue::Exprloc(Expression::new())
         }
-        CPlaceInner::Addr(_, _) => {
-            // FIXME implement this (used by arguments and returns)
+        CPlaceInner::Addr(_, Some(_)) => {
+            // FIXME implement this (used by unsized locals)
 
             AttributeValue::Exprloc(Expression::new())
-
-            // For PointerBase::Stack:
-            //AttributeValue::Exprloc(translate_loc(ValueLoc::Stack(*stack_slot), &context.func.stack_slots).unwrap())
         }
     }
 }
 
+/// Describes a place stored in one or more variables, given with their offset and size within the
+/// place. Multiple variables are combined using `DW_OP_piece`, which requires all of them to be
+/// available, so the location list only covers the code ranges where that is the case.
+fn vars_location(
+    debug_context: &mut DebugContext<'_>,
+    isa: &dyn TargetIsa,
+    symbol: usize,
+    context: &Context,
+    #[allow(rustc::default_hash_types)] value_labels_ranges: &std::collections::HashMap<
+        ValueLabel,
+        Vec<ValueLocRange>,
+    >,
+    vars: &[(Variable, Size, Size)],
+) -> AttributeValue {
+    let mut ranges = vec![(0, u32::MAX, Vec::new())];
+    for &(var, _offset, _size) in vars {
+        let value_loc_ranges = match value_labels_ranges.get(&ValueLabel::new(var.index())) {
+            Some(value_loc_ranges) => value_loc_ranges,
+            None => {
+                // FIXME set value labels for unused locals
+
+                return AttributeValue::Exprloc(Expression::new());
+            }
+        };
+        ranges = ranges
+            .iter()
+            .flat_map(|(start, end, locs)| {
+                value_loc_ranges.iter().filter_map(move |value_loc_range| {
+                    let start = std::cmp::max(*start, value_loc_range.start);
+                    let end = std::cmp::min(*end, value_loc_range.end);
+                    if start < end {
+                        let mut locs: Vec<LabelValueLoc> = locs.clone();
+                        locs.push(value_loc_range.loc);
+                        Some((start, end, locs))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect();
+    }
+
+    let loc_list = LocationList(
+        ranges
+            .into_iter()
+            .filter_map(|(start, end, locs)| {
+                let mut expr = Expression::new();
+                if let [loc] = *locs {
+                    translate_loc(&mut expr, isa, loc, &context.func.stack_slots)?;
+                } else {
+                    let mut described = Size::ZERO;
+                    for (&(_var, offset, size), &loc) in vars.iter().zip(&locs) {
+                        if offset > described {
+                            // Padding between the variables
+                            expr.op_piece((offset - described).bytes());
+                        }
+                        translate_loc(&mut expr, isa, loc, &context.func.stack_slots)?;
+                        expr.op_piece(size.bytes());
+                        described = offset + size;
+                    }
+                }
+                Some(Location::StartEnd {
+                    begin: Address::Symbol { symbol, addend: i64::from(start) },
+                    end: Address::Symbol { symbol, addend: i64::from(end) },
+                    data: expr,
+                })
+            })
+            .collect(),
+    );
+    let loc_list_id = debug_context.dwarf.unit.locations.add(loc_list);
+
+    AttributeValue::LocationListRef(loc_list_id)
+}
+
 // Adapted from https://github.com/CraneStation/wasmtime/blob/5a1845b4caf7a5dba8eda1fef05213a532ed4259/crates/debug/src/transform/expression.rs#L59-L137
 fn translate_loc(
+    expr: &mut Expression,
     isa: &dyn TargetIsa,
     loc: LabelValueLoc,
     stack_slots: &StackSlots,
-) -> Option<Expression> {
+) -> Option<()> {
     match loc {
         LabelValueLoc::ValueLoc(ValueLoc::Reg(reg)) => {
             let machine_reg = isa.map_dwarf_register(reg).unwrap();
-            let mut expr = Expression::new();
             expr.op_reg(gimli::Register(machine_reg));
-            Some(expr)
         }
         LabelValueLoc::ValueLoc(ValueLoc::Stack(ss)) => {
-            if let Some(ss_offset) = stack_slots[ss].offset {
-                let mut expr = Expression::new();
-                expr.op_breg(X86_64::RBP, i64::from(ss_offset) + 16);
-                Some(expr)
-            } else {
-                None
-            }
+            let (_stack_pointer, frame_pointer) = stack_registers(isa)?;
+            let ss_offset = stack_slots[ss].offset?;
+            expr.op_breg(frame_pointer, i64::from(ss_offset) + 16);
         }
         LabelValueLoc::ValueLoc(ValueLoc::Unassigned) => unreachable!(),
         LabelValueLoc::Reg(reg) => {
             let machine_reg = isa.map_regalloc_reg_to_dwarf(reg).unwrap();
-            let mut expr = Expression::new();
             expr.op_reg(gimli::Register(machine_reg));
-            Some(expr)
         }
         LabelValueLoc::SPOffset(offset) => {
-            let mut expr = Expression::new();
-            expr.op_breg(X86_64::RSP, offset);
-            Some(expr)
+            let (stack_pointer, _frame_pointer) = stack_registers(isa)?;
+            expr.op_breg(stack_pointer, offset);
         }
     }
+    Some(())
+}
+
+/// Returns the DWARF registers of the stack pointer and the frame pointer, relative to which values
+/// on the stack are described. The offsets from those registers are only known for x86_64, so no
+/// location is emitted for values on the stack on other targets.
+fn stack_registers(isa: &dyn TargetIsa) -> Option<(gimli::Register, gimli::Register)> {
+    match isa.triple().architecture {
+        target_lexicon::Architecture::X86_64 => Some((X86_64::RSP, X86_64::RBP)),
+        _ => None,
+    }
 }
@@ -0,0 +1,338 @@
+//! Type debuginfo (`DW_TAG_*_type` entries of `.debug_info`)
+//!
+//! The emitted types follow the layout used by `rustc_codegen_llvm::debuginfo::metadata`, so that
+//! debuggers display them the same way as for LLVM-built binaries.
+
+use crate::prelude::*;
+
+use rustc_hir::def::CtorKind;
+use rustc_middle::ty::layout::IntegerExt;
+use rustc_middle::ty::AdtDef;
+use rustc_target::abi::{Integer, Primitive, TagEncoding, Variants};
+
+use gimli::write::{AttributeValue, UnitEntryId};
+
+use super::DebugContext;
+
+impl<'tcx> DebugContext<'tcx> {
+    pub(super) fn dwarf_ty(&mut self, ty: Ty<'tcx>) -> UnitEntryId {
+        if let Some(type_id) = self.types.get(ty) {
+            return *type_id;
+        }
+
+        let name = format!("{}", ty);
+        let layout = self.layout_of(ty);
+
+        let type_id = match ty.kind() {
+            ty::Bool => self.primitive(gimli::DW_ATE_boolean),
+            ty::Char => self.primitive(gimli::DW_ATE_UTF),
+            ty::Uint(_) => self.primitive(gimli::DW_ATE_unsigned),
+            ty::Int(_) => self.primitive(gimli::DW_ATE_signed),
+            ty::Float(_) => self.primitive(gimli::DW_ATE_float),
+            ty::Ref(_, pointee_ty, _mutbl)
+            | ty::RawPtr(ty::TypeAndMut { ty: pointee_ty, mutbl: _mutbl }) => {
+                if let Abi::ScalarPair(_, _) = layout.abi {
+                    self.fat_pointer(ty, pointee_ty)
+                } else {
+                    let type_id = self.new_entry(gimli::DW_TAG_pointer_type);
+
+                    // Ensure that type is inserted before recursing to avoid duplicates
+                    self.types.insert(ty, type_id);
+
+                    let pointee = self.dwarf_ty(pointee_ty);
+
+                    let type_entry = self.dwarf.unit.get_mut(type_id);
+
+                    //type_entry.set(gimli::DW_AT_mutable, AttributeValue::Flag(mutbl == rustc_hir::Mutability::Mut));
+                    type_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(pointee));
+
+                    type_id
+                }
+            }
+            ty::Adt(adt_def, _substs) if adt_def.is_struct() && !layout.is_unsized() => {
+                let type_id = self.new_entry(gimli::DW_TAG_structure_type);
+
+                // Ensure that type is inserted before recursing to avoid duplicates
+                self.types.insert(ty, type_id);
+
+                let field_names = field_names(adt_def.non_enum_variant());
+                self.add_fields(type_id, layout, field_names);
+
+                type_id
+            }
+            ty::Adt(adt_def, _substs) if adt_def.is_enum() && !adt_def.variants.is_empty() => {
+                self.enum_ty(ty, adt_def, layout)
+            }
+            ty::Tuple(_) => {
+                let type_id = self.new_entry(gimli::DW_TAG_structure_type);
+
+                // Ensure that type is inserted before recursing to avoid duplicates
+                self.types.insert(ty, type_id);
+
+                let field_names = (0..layout.fields.count()).map(|i| format!("__{}", i)).collect();
+                self.add_fields(type_id, layout, field_names);
+
+                type_id
+            }
+            ty::Array(elem_ty, _len) => {
+                let type_id = self.new_entry(gimli::DW_TAG_array_type);
+
+                // Ensure that type is inserted before recursing to avoid duplicates
+                self.types.insert(ty, type_id);
+
+                let elem = self.dwarf_ty(elem_ty);
+                self.dwarf
+                    .unit
+                    .get_mut(type_id)
+                    .set(gimli::DW_AT_type, AttributeValue::UnitRef(elem));
+
+                let subrange_id = self.dwarf.unit.add(type_id, gimli::DW_TAG_subrange_type);
+                let subrange_entry = self.dwarf.unit.get_mut(subrange_id);
+                subrange_entry
+                    .set(gimli::DW_AT_count, AttributeValue::Udata(layout.fields.count() as u64));
+
+                type_id
+            }
+            _ => self.new_entry(gimli::DW_TAG_structure_type),
+        };
+
+        let type_entry = self.dwarf.unit.get_mut(type_id);
+
+        type_entry.set(gimli::DW_AT_name, AttributeValue::String(name.into_bytes()));
+        type_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(layout.size.bytes()));
+
+        self.types.insert(ty, type_id);
+
+        type_id
+    }
+
+    fn layout_of(&self, ty: Ty<'tcx>) -> TyAndLayout<'tcx> {
+        self.tcx.layout_of(ParamEnv::reveal_all().and(ty)).unwrap()
+    }
+
+    fn new_entry(&mut self, tag: gimli::DwTag) -> UnitEntryId {
+        self.dwarf.unit.add(self.dwarf.unit.root(), tag)
+    }
+
+    fn primitive(&mut self, ate: gimli::DwAte) -> UnitEntryId {
+        let type_id = self.new_entry(gimli::DW_TAG_base_type);
+        let type_entry = self.dwarf.unit.get_mut(type_id);
+        type_entry.set(gimli::DW_AT_encoding, AttributeValue::Encoding(ate));
+        type_id
+    }
+
+    /// Adds a `DW_TAG_member` to `parent` for every field of `layout`.
+    fn add_fields(
+        &mut self,
+        parent: UnitEntryId,
+        layout: TyAndLayout<'tcx>,
+        field_names: Vec<String>,
+    ) {
+        for (field_idx, field_name) in field_names.into_iter().enumerate() {
+            let field_offset = layout.fields.offset(field_idx);
+            let field_layout = layout
+                .field(
+                    &layout::LayoutCx { tcx: self.tcx, param_env: ParamEnv::reveal_all() },
+                    field_idx,
+                )
+                .unwrap();
+            let field_type = self.dwarf_ty(field_layout.ty);
+            self.add_member(parent, field_name, field_offset, field_type);
+        }
+    }
+
+    fn add_member(
+        &mut self,
+        parent: UnitEntryId,
+        name: String,
+        offset: Size,
+        member_type: UnitEntryId,
+    ) -> UnitEntryId {
+        let member_id = self.dwarf.unit.add(parent, gimli::DW_TAG_member);
+        let member_entry = self.dwarf.unit.get_mut(member_id);
+
+        member_entry.set(gimli::DW_AT_name, AttributeValue::String(name.into_bytes()));
+        member_entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(offset.bytes()));
+        member_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(member_type));
+
+        member_id
+    }
+
+    /// Describes a pointer to an unsized type as a struct of the data pointer and the metadata.
+    fn fat_pointer(&mut self, ty: Ty<'tcx>, pointee_ty: Ty<'tcx>) -> UnitEntryId {
+        let tcx = self.tcx;
+
+        let type_id = self.new_entry(gimli::DW_TAG_structure_type);
+
+        // Ensure that type is inserted before recursing to avoid duplicates
+        self.types.insert(ty, type_id);
+
+        let (data_name, data_ty, meta_name, meta_ty) = match tcx
+            .struct_tail_erasing_lifetimes(pointee_ty, ParamEnv::reveal_all())
+            .kind()
+        {
+            ty::Slice(elem_ty) => ("data_ptr", tcx.mk_imm_ptr(elem_ty), "length", tcx.types.usize),
+            ty::Str => ("data_ptr", tcx.mk_imm_ptr(tcx.types.u8), "length", tcx.types.usize),
+            _ => (
+                "pointer",
+                tcx.mk_mut_ptr(tcx.types.u8),
+                "vtable",
+                tcx.mk_imm_ref(tcx.lifetimes.re_static, tcx.mk_array(tcx.types.usize, 3)),
+            ),
+        };
+
+        let data_type = self.dwarf_ty(data_ty);
+        self.add_member(type_id, data_name.to_owned(), Size::ZERO, data_type);
+        let meta_type = self.dwarf_ty(meta_ty);
+        self.add_member(type_id, meta_name.to_owned(), tcx.data_layout.pointer_size, meta_type);
+
+        type_id
+    }
+
+    /// Describes an enum as a `DW_TAG_enumeration_type` if none of its variants have fields, or
+    /// else as a struct containing a `DW_TAG_variant_part` with a struct for every variant.
+    fn enum_ty(
+        &mut self,
+        ty: Ty<'tcx>,
+        adt_def: &'tcx AdtDef,
+        layout: TyAndLayout<'tcx>,
+    ) -> UnitEntryId {
+        let tcx = self.tcx;
+
+        if let (
+            Abi::Scalar(_),
+            Variants::Multiple { tag_encoding: TagEncoding::Direct, ref tag, .. },
+        ) = (&layout.abi, &layout.variants)
+        {
+            let type_id = self.new_entry(gimli::DW_TAG_enumeration_type);
+            let base_type = self.dwarf_ty(tag_ty(tcx, tag));
+            self.dwarf
+                .unit
+                .get_mut(type_id)
+                .set(gimli::DW_AT_type, AttributeValue::UnitRef(base_type));
+
+            for (variant_idx, discr) in adt_def.discriminants(tcx) {
+                let enumerator_id = self.dwarf.unit.add(type_id, gimli::DW_TAG_enumerator);
+                let enumerator_entry = self.dwarf.unit.get_mut(enumerator_id);
+                enumerator_entry.set(
+                    gimli::DW_AT_name,
+                    AttributeValue::String(
+                        adt_def.variants[variant_idx].ident.as_str().to_string().into_bytes(),
+                    ),
+                );
+                let value = if let Primitive::Int(_, true) = tag.value {
+                    AttributeValue::Sdata(tag.value.size(&tcx).sign_extend(discr.val) as i64)
+                } else {
+                    AttributeValue::Udata(discr.val as u64)
+                };
+                enumerator_entry.set(gimli::DW_AT_const_value, value);
+            }
+
+            return type_id;
+        }
+
+        let type_id = self.new_entry(gimli::DW_TAG_structure_type);
+
+        // Ensure that type is inserted before recursing to avoid duplicates
+        self.types.insert(ty, type_id);
+
+        // The variant part must be wrapped in a struct according to DWARF.
+        let variant_part_id = self.dwarf.unit.add(type_id, gimli::DW_TAG_variant_part);
+
+        let variants: Vec<(VariantIdx, Option<u64>)> = match layout.variants {
+            Variants::Single { index } => vec![(index, None)],
+            Variants::Multiple { ref tag, ref tag_encoding, tag_field, ref variants } => {
+                let tag_type = self.dwarf_ty(tag_ty(tcx, tag));
+                let tag_offset = layout.fields.offset(tag_field);
+                let tag_member_id = self.dwarf.unit.add(variant_part_id, gimli::DW_TAG_member);
+                let tag_member = self.dwarf.unit.get_mut(tag_member_id);
+                tag_member.set(gimli::DW_AT_artificial, AttributeValue::Flag(true));
+                tag_member.set(
+                    gimli::DW_AT_data_member_location,
+                    AttributeValue::Udata(tag_offset.bytes()),
+                );
+                tag_member.set(gimli::DW_AT_type, AttributeValue::UnitRef(tag_type));
+                self.dwarf
+                    .unit
+                    .get_mut(variant_part_id)
+                    .set(gimli::DW_AT_discr, AttributeValue::UnitRef(tag_member_id));
+
+                variants
+                    .indices()
+                    .map(|variant_idx| {
+                        let discr_value = match *tag_encoding {
+                            TagEncoding::Direct => {
+                                let discr = ty.discriminant_for_variant(tcx, variant_idx).unwrap();
+                                Some(tag.value.size(&tcx).truncate(discr.val) as u64)
+                            }
+                            TagEncoding::Niche { dataful_variant, .. }
+                                if variant_idx == dataful_variant =>
+                            {
+                                None
+                            }
+                            TagEncoding::Niche { ref niche_variants, niche_start, .. } => {
+                                let value = (variant_idx.as_u32() as u128)
+                                    .wrapping_sub(niche_variants.start().as_u32() as u128)
+                                    .wrapping_add(niche_start);
+                                Some(tag.value.size(&tcx).truncate(value) as u64)
+                            }
+                        };
+                        (variant_idx, discr_value)
+                    })
+                    .collect()
+            }
+        };
+
+        for (variant_idx, discr_value) in variants {
+            let variant_def = &adt_def.variants[variant_idx];
+            let variant_name = variant_def.ident.as_str().to_string();
+            let variant_layout = layout.for_variant(
+                &layout::LayoutCx { tcx, param_env: ParamEnv::reveal_all() },
+                variant_idx,
+            );
+
+            let variant_type_id = self.dwarf.unit.add(type_id, gimli::DW_TAG_structure_type);
+            let variant_type = self.dwarf.unit.get_mut(variant_type_id);
+            variant_type
+                .set(gimli::DW_AT_name, AttributeValue::String(variant_name.clone().into_bytes()));
+            variant_type.set(gimli::DW_AT_byte_size, AttributeValue::Udata(layout.size.bytes()));
+            self.add_fields(variant_type_id, variant_layout, field_names(variant_def));
+
+            let variant_id = self.dwarf.unit.add(variant_part_id, gimli::DW_TAG_variant);
+            if let Some(discr_value) = discr_value {
+                self.dwarf
+                    .unit
+                    .get_mut(variant_id)
+                    .set(gimli::DW_AT_discr_value, AttributeValue::Udata(discr_value));
+            }
+            self.add_member(variant_id, variant_name, Size::ZERO, variant_type_id);
+        }
+
+        type_id
+    }
+}
+
+fn field_names(variant: &ty::VariantDef) -> Vec<String> {
+    variant
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            if variant.ctor_kind == CtorKind::Fn {
+                format!("__{}", i)
+            } else {
+                field.ident.as_str().to_string()
+            }
+        })
+        .collect()
+}
+
+/// The integer type of an enum tag. Niches in pointers and floats are described as integers too.
+fn tag_ty<'tcx>(tcx: TyCtxt<'tcx>, tag: &Scalar) -> Ty<'tcx> {
+    match tag.value {
+        Primitive::Int(int, signed) => int.to_ty(tcx, signed),
+        Primitive::F32 => Integer::I32.to_ty(tcx, false),
+        Primitive::F64 => Integer::I64.to_ty(tcx, false),
+        Primitive::Pointer => tcx.data_layout.ptr_sized_integer().to_ty(tcx, false),
+    }
+}
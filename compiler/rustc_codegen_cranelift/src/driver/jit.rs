@@ -7,13 +7,15 @@
 
 use cranelift_codegen::binemit::{NullStackMapSink, NullTrapSink};
 use rustc_codegen_ssa::CrateInfo;
-use rustc_middle::mir::mono::MonoItem;
+use rustc_middle::mir::mono::{Linkage as RLinkage, MonoItem, Visibility};
 
 use cranelift_jit::{JITBuilder, JITModule};
 
 use crate::{prelude::*, BackendConfig};
 use crate::{CodegenCx, CodegenMode};
 
+mod hot_reload;
+
 struct JitState {
     backend_config: BackendConfig,
     jit_module: JITModule,
@@ -60,24 +62,26 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, backend_config: BackendConfig) -> ! {
         tcx.sess.fatal("can't jit non-executable crate");
     }
 
+    if let CodegenMode::JitHotReload = backend_config.codegen_mode {
+        if tcx.sess.opts.incremental.is_none() {
+            tcx.sess.fatal("hot reloading requires incremental compilation to be enabled");
+        }
+        if tcx.sess.local_crate_source_file.is_none() {
+            tcx.sess.fatal("hot reloading requires the crate to be read from a file");
+        }
+    }
+
     let (mut jit_module, mut cx) = create_jit_module(
         tcx,
         &backend_config,
-        matches!(backend_config.codegen_mode, CodegenMode::JitLazy),
+        matches!(backend_config.codegen_mode, CodegenMode::JitLazy | CodegenMode::JitHotReload),
     );
 
-    let (_, cgus) = tcx.collect_and_partition_mono_items(());
-    let mono_items = cgus
-        .iter()
-        .map(|cgu| cgu.items_in_deterministic_order(tcx).into_iter())
-        .flatten()
-        .collect::<FxHashMap<_, (_, _)>>()
-        .into_iter()
-        .collect::<Vec<(_, (_, _))>>();
+    let mono_items = collect_mono_items(tcx);
 
     super::time(tcx, backend_config.display_cg_time, "codegen mono items", || {
         super::predefine_mono_items(tcx, &mut jit_module, &mono_items);
-        for (mono_item, _) in mono_items {
+        for &(mono_item, _) in &mono_items {
             match mono_item {
                 MonoItem::Fn(inst) => match backend_config.codegen_mode {
                     CodegenMode::Aot => unreachable!(),
@@ -86,7 +90,16 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, backend_config: BackendConfig) -> ! {
                             crate::base::codegen_fn(&mut cx, &mut jit_module, inst)
                         });
                     }
-                    CodegenMode::JitLazy => codegen_shim(&mut cx, &mut jit_module, inst),
+                    CodegenMode::JitLazy => codegen_shim(
+                        &mut cx,
+                        &mut jit_module,
+                        inst,
+                        "__clif_jit_fn",
+                        Box::into_raw(Box::new(inst)) as *const u8,
+                    ),
+                    CodegenMode::JitHotReload => {
+                        hot_reload::codegen_shim(&mut cx, &mut jit_module, inst)
+                    }
                 },
                 MonoItem::Static(def_id) => {
                     crate::constant::codegen_static(tcx, &mut jit_module, def_id);
@@ -116,11 +129,6 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, backend_config: BackendConfig) -> ! {
         .chain(backend_config.jit_args.iter().map(|arg| &**arg))
         .map(|arg| CString::new(arg).unwrap())
         .collect::<Vec<_>>();
-    let mut argv = args.iter().map(|arg| arg.as_ptr()).collect::<Vec<_>>();
-
-    // Push a null pointer as a terminating argument. This is required by POSIX and
-    // useful as some dynamic linkers use it as a marker to jump over.
-    argv.push(std::ptr::null());
 
     let start_sig = Signature {
         params: vec![
@@ -132,6 +140,12 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, backend_config: BackendConfig) -> ! {
     };
     let start_func_id = jit_module.declare_function("main", Linkage::Import, &start_sig).unwrap();
     let finalized_start: *const u8 = jit_module.get_finalized_function(start_func_id);
+    let f: extern "C" fn(c_int, *const *const c_char) -> c_int =
+        unsafe { ::std::mem::transmute(finalized_start) };
+
+    if let CodegenMode::JitHotReload = backend_config.codegen_mode {
+        hot_reload::run(tcx, backend_config, jit_module, &mono_items, f, args);
+    }
 
     LAZY_JIT_STATE.with(|lazy_jit_state| {
         let mut lazy_jit_state = lazy_jit_state.borrow_mut();
@@ -139,12 +153,30 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, backend_config: BackendConfig) -> ! {
         *lazy_jit_state = Some(JitState { backend_config, jit_module });
     });
 
-    let f: extern "C" fn(c_int, *const *const c_char) -> c_int =
-        unsafe { ::std::mem::transmute(finalized_start) };
-    let ret = f(args.len() as c_int, argv.as_ptr());
+    let ret = run_main(f, &args);
     std::process::exit(ret);
 }
 
+fn collect_mono_items<'tcx>(tcx: TyCtxt<'tcx>) -> Vec<(MonoItem<'tcx>, (RLinkage, Visibility))> {
+    let (_, cgus) = tcx.collect_and_partition_mono_items(());
+    cgus.iter()
+        .map(|cgu| cgu.items_in_deterministic_order(tcx).into_iter())
+        .flatten()
+        .collect::<FxHashMap<_, (_, _)>>()
+        .into_iter()
+        .collect::<Vec<(_, (_, _))>>()
+}
+
+fn run_main(f: extern "C" fn(c_int, *const *const c_char) -> c_int, args: &[CString]) -> c_int {
+    let mut argv = args.iter().map(|arg| arg.as_ptr()).collect::<Vec<_>>();
+
+    // Push a null pointer as a terminating argument. This is required by POSIX and
+    // useful as some dynamic linkers use it as a marker to jump over.
+    argv.push(std::ptr::null());
+
+    f(args.len() as c_int, argv.as_ptr())
+}
+
 #[no_mangle]
 extern "C" fn __clif_jit_fn(instance_ptr: *const Instance<'static>) -> *const u8 {
     rustc_middle::ty::tls::with(|tcx| {
@@ -237,7 +269,15 @@ fn load_imported_symbols_for_jit(tcx: TyCtxt<'_>) -> Vec<(String, *const u8)> {
     imported_symbols
 }
 
-fn codegen_shim<'tcx>(cx: &mut CodegenCx<'tcx>, module: &mut JITModule, inst: Instance<'tcx>) {
+/// Defines `inst` as a trampoline that calls `jit_fn` with `jit_fn_arg` to get the address of the
+/// actual function and then calls it.
+fn codegen_shim<'tcx>(
+    cx: &mut CodegenCx<'tcx>,
+    module: &mut JITModule,
+    inst: Instance<'tcx>,
+    jit_fn: &str,
+    jit_fn_arg: *const u8,
+) {
     let tcx = cx.tcx;
 
     let pointer_type = module.target_config().pointer_type();
@@ -246,11 +286,9 @@ fn codegen_shim<'tcx>(cx: &mut CodegenCx<'tcx>, module: &mut JITModule, inst: In
     let sig = crate::abi::get_function_sig(tcx, module.isa().triple(), inst);
     let func_id = module.declare_function(name, Linkage::Export, &sig).unwrap();
 
-    let instance_ptr = Box::into_raw(Box::new(inst));
-
     let jit_fn = module
         .declare_function(
-            "__clif_jit_fn",
+            jit_fn,
             Linkage::Import,
             &Signature {
                 call_conv: module.target_config().default_call_conv,
@@ -275,8 +313,8 @@ fn codegen_shim<'tcx>(cx: &mut CodegenCx<'tcx>, module: &mut JITModule, inst: In
     let fn_args = trampoline_builder.func.dfg.block_params(entry_block).to_vec();
 
     trampoline_builder.switch_to_block(entry_block);
-    let instance_ptr = trampoline_builder.ins().iconst(pointer_type, instance_ptr as u64 as i64);
-    let jitted_fn = trampoline_builder.ins().call(jit_fn, &[instance_ptr]);
+    let jit_fn_arg = trampoline_builder.ins().iconst(pointer_type, jit_fn_arg as u64 as i64);
+    let jitted_fn = trampoline_builder.ins().call(jit_fn, &[jit_fn_arg]);
     let jitted_fn = trampoline_builder.func.dfg.inst_results(jitted_fn)[0];
     let call_inst = trampoline_builder.ins().call_indirect(sig_ref, jitted_fn, &fn_args);
     let ret_vals = trampoline_builder.func.dfg.inst_results(call_inst).to_vec();
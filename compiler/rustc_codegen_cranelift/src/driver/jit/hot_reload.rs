@@ -0,0 +1,463 @@
+//! Hot code reloading for the lazy JIT mode.
+//!
+//! The JITed program runs on a separate thread. Every function starts out as a shim that asks the
+//! rustc thread to compile it the first time it is called. Between these requests the rustc
+//! thread polls the source files of the crate. Once one of them changes, the crate is analyzed
+//! again in a new incremental session, which only recomputes the queries affected by the change.
+//! Functions whose MIR, or the layouts and constants it uses, changed are then recompiled and
+//! redefined in place when they were already compiled. All other functions keep their code, or
+//! their shim, which compiles them using the latest session once they are called.
+//!
+//! Statics keep their original definition and functions can't change their signature without
+//! restarting the program. Calling a function that was removed reports an error and blocks the
+//! calling thread until the function is added back.
+
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use cranelift_jit::JITModule;
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_interface::{interface, Queries};
+use rustc_middle::mir::mono::{Linkage as RLinkage, MonoItem, Visibility};
+use rustc_middle::mir::visit::{TyContext, Visitor};
+use rustc_middle::ty::ConstKind;
+use rustc_session::config::Options;
+use rustc_span::FileName;
+
+use crate::prelude::*;
+use crate::{BackendConfig, CodegenCx, CraneliftCodegenBackend};
+
+/// How often the source files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The channel the JITed program uses to send messages to the rustc thread. It is set before the
+/// program starts and never freed.
+static MESSAGE_SENDER: AtomicPtr<Mutex<Sender<Message>>> = AtomicPtr::new(std::ptr::null_mut());
+
+enum Message {
+    /// A shim asks for the address of the function with the given symbol name.
+    JitFn { name: String, tx: Sender<usize> },
+    /// The program returned from `main` with the given exit code.
+    Exit(c_int),
+}
+
+enum Event {
+    Message(Message),
+    SourcesChanged,
+}
+
+struct HotReloadState {
+    backend_config: BackendConfig,
+    jit_module: JITModule,
+    /// The command line and options of the initial session, which every reload session reuses.
+    args: Vec<String>,
+    opts: Options,
+    rx: Receiver<Message>,
+    /// Messages received while no session was able to handle them.
+    pending: VecDeque<Message>,
+    /// The fingerprint of every function, by symbol name.
+    fingerprints: FxHashMap<String, Fingerprint>,
+    /// The def path of every function, by symbol name, to report the functions that were removed.
+    def_paths: FxHashMap<String, String>,
+    /// The functions that have been compiled, as opposed to those still defined as a shim.
+    compiled: FxHashSet<String>,
+    /// The statics that have been defined.
+    statics: FxHashSet<String>,
+    /// The modification time of every source file of the crate when it was last read.
+    sources: FxHashMap<PathBuf, Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+// The JIT module is only used by the thread of the session that is currently active, while all
+// other threads of the rustc process wait for it.
+unsafe impl Send for HotReloadState {}
+
+pub(super) fn run<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    backend_config: BackendConfig,
+    jit_module: JITModule,
+    mono_items: &[(MonoItem<'tcx>, (RLinkage, Visibility))],
+    start: extern "C" fn(c_int, *const *const c_char) -> c_int,
+    args: Vec<CString>,
+) -> ! {
+    let (tx, rx) = mpsc::channel();
+    MESSAGE_SENDER.store(Box::into_raw(Box::new(Mutex::new(tx))), Ordering::SeqCst);
+
+    let instances = instances(tcx, mono_items);
+    let mut state = HotReloadState {
+        backend_config,
+        jit_module,
+        args: std::env::args().collect(),
+        opts: tcx.sess.opts.clone(),
+        rx,
+        pending: VecDeque::new(),
+        fingerprints: instances
+            .iter()
+            .map(|(name, &instance)| (name.clone(), fingerprint(tcx, instance)))
+            .collect(),
+        def_paths: instances
+            .iter()
+            .map(|(name, &instance)| (name.clone(), tcx.def_path_str(instance.def_id())))
+            .collect(),
+        compiled: FxHashSet::default(),
+        statics: mono_items
+            .iter()
+            .filter_map(|&(mono_item, _)| match mono_item {
+                MonoItem::Static(_) => Some(mono_item.symbol_name(tcx).name.to_owned()),
+                MonoItem::Fn(_) | MonoItem::GlobalAsm(_) => None,
+            })
+            .collect(),
+        sources: FxHashMap::default(),
+        last_poll: Instant::now(),
+    };
+    state.watch_sources(tcx);
+
+    std::thread::Builder::new()
+        .name("main".to_owned())
+        .spawn(move || {
+            let ret = super::run_main(start, &args);
+            send_message(Message::Exit(ret));
+        })
+        .unwrap();
+
+    state.serve(tcx, &instances);
+    persist_dep_graph(tcx);
+
+    loop {
+        let args = state.args.clone();
+        let backend_config = state.backend_config.clone();
+        let mut callbacks = HotReloadCallbacks { state: &mut state, reloaded: false };
+        let mut run_compiler = rustc_driver::RunCompiler::new(&args, &mut callbacks);
+        run_compiler.set_make_codegen_backend(Some(Box::new(move |_| {
+            Box::new(CraneliftCodegenBackend { config: Some(backend_config) })
+        })));
+        let _ = rustc_driver::catch_fatal_errors(|| run_compiler.run());
+
+        if !callbacks.reloaded {
+            eprintln!("Hot reloading failed, waiting for the next change");
+            state.wait_for_source_change();
+        }
+    }
+}
+
+/// Defines `inst` as a shim that asks the rustc thread to compile it.
+pub(super) fn codegen_shim<'tcx>(
+    cx: &mut CodegenCx<'tcx>,
+    module: &mut JITModule,
+    inst: Instance<'tcx>,
+) {
+    let name = Box::new(cx.tcx.symbol_name(inst).name.to_owned());
+    super::codegen_shim(
+        cx,
+        module,
+        inst,
+        "__clif_jit_hot_reload_fn",
+        Box::into_raw(name) as *const u8,
+    );
+}
+
+#[no_mangle]
+extern "C" fn __clif_jit_hot_reload_fn(name_ptr: *const String) -> *const u8 {
+    let name = unsafe { &*name_ptr }.clone();
+    let (tx, rx) = mpsc::channel();
+    send_message(Message::JitFn { name, tx });
+    rx.recv().unwrap() as *const u8
+}
+
+fn send_message(message: Message) {
+    let sender = unsafe { &*MESSAGE_SENDER.load(Ordering::SeqCst) };
+    sender.lock().unwrap().send(message).unwrap();
+}
+
+struct HotReloadCallbacks<'a> {
+    state: &'a mut HotReloadState,
+    /// Whether the session was analyzed and reloaded without errors.
+    reloaded: bool,
+}
+
+impl rustc_driver::Callbacks for HotReloadCallbacks<'_> {
+    fn config(&mut self, config: &mut interface::Config) {
+        config.opts = self.state.opts.clone();
+    }
+
+    fn after_analysis<'tcx>(
+        &mut self,
+        _compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> rustc_driver::Compilation {
+        queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
+            if let Some(instances) = self.state.reload(tcx) {
+                self.reloaded = true;
+                self.state.serve(tcx, &instances);
+                persist_dep_graph(tcx);
+            }
+        });
+        rustc_driver::Compilation::Stop
+    }
+}
+
+impl HotReloadState {
+    /// Recompiles the functions that changed since the previous session and defines the new mono
+    /// items. Returns the instances of all functions, or `None` if an error occurred.
+    fn reload<'tcx>(&mut self, tcx: TyCtxt<'tcx>) -> Option<FxHashMap<String, Instance<'tcx>>> {
+        let mono_items = super::collect_mono_items(tcx);
+        let instances = instances(tcx, &mono_items);
+        self.watch_sources(tcx);
+
+        for &(mono_item, _) in &mono_items {
+            match mono_item {
+                MonoItem::Fn(_) => {}
+                MonoItem::Static(def_id) => {
+                    if self.statics.insert(mono_item.symbol_name(tcx).name.to_owned()) {
+                        crate::constant::codegen_static(tcx, &mut self.jit_module, def_id);
+                    }
+                }
+                MonoItem::GlobalAsm(item_id) => {
+                    let item = tcx.hir().item(item_id);
+                    tcx.sess.span_err(item.span, "Global asm is not supported in JIT mode");
+                }
+            }
+        }
+
+        let mut cx = CodegenCx::new(tcx, self.backend_config.clone(), self.jit_module.isa(), false);
+        let mut changed = 0;
+        let mut added = 0;
+        for (name, &instance) in &instances {
+            let fingerprint = fingerprint(tcx, instance);
+            let previous = self.fingerprints.get(name).copied();
+            if previous == Some(fingerprint) {
+                continue;
+            }
+
+            let sig = get_function_sig(tcx, self.jit_module.isa().triple(), instance);
+            if self.jit_module.declare_function(name, Linkage::Export, &sig).is_err() {
+                tcx.sess.err(&format!(
+                    "the signature of `{}` changed, which requires restarting the program",
+                    tcx.def_path_str(instance.def_id()),
+                ));
+                continue;
+            }
+            if previous.is_none() {
+                codegen_shim(&mut cx, &mut self.jit_module, instance);
+                added += 1;
+            } else {
+                if self.compiled.contains(name) {
+                    self.define_fn(&mut cx, instance);
+                }
+                changed += 1;
+            }
+            self.fingerprints.insert(name.clone(), fingerprint);
+            self.def_paths.insert(name.clone(), tcx.def_path_str(instance.def_id()));
+        }
+
+        if tcx.sess.has_errors() {
+            return None;
+        }
+        self.finalize_definitions(cx);
+
+        tcx.sess.note_without_error(&format!(
+            "hot reloaded {} changed and {} new functions",
+            changed, added
+        ));
+        Some(instances)
+    }
+
+    /// Handles the requests of the program using the functions of a single session, until the
+    /// source files change.
+    fn serve<'tcx>(&mut self, tcx: TyCtxt<'tcx>, instances: &FxHashMap<String, Instance<'tcx>>) {
+        loop {
+            match self.next_event() {
+                Event::Message(Message::JitFn { name, tx }) => {
+                    let instance = match instances.get(&name) {
+                        Some(&instance) => instance,
+                        None => {
+                            // Keep the program waiting for the function until a later session
+                            // defines it again, instead of killing it.
+                            let def_path = self.def_paths.get(&name).unwrap_or(&name);
+                            tcx.sess.err(&format!(
+                                "`{}` was removed, but is still used by the program",
+                                def_path
+                            ));
+                            self.pending.push_front(Message::JitFn { name, tx });
+                            self.wait_for_source_change();
+                            return;
+                        }
+                    };
+
+                    let mut cx = CodegenCx::new(
+                        tcx,
+                        self.backend_config.clone(),
+                        self.jit_module.isa(),
+                        false,
+                    );
+                    let func_id = self.define_fn(&mut cx, instance);
+                    self.finalize_definitions(cx);
+                    tx.send(self.jit_module.get_finalized_function(func_id) as usize).unwrap();
+                }
+                Event::Message(Message::Exit(ret)) => std::process::exit(ret),
+                Event::SourcesChanged => return,
+            }
+        }
+    }
+
+    /// Waits for the source files to change, while keeping the requests of the program for the
+    /// next session.
+    fn wait_for_source_change(&mut self) {
+        loop {
+            match self.poll() {
+                Event::Message(Message::Exit(ret)) => std::process::exit(ret),
+                Event::Message(message) => self.pending.push_back(message),
+                Event::SourcesChanged => return,
+            }
+        }
+    }
+
+    fn next_event(&mut self) -> Event {
+        if let Some(message) = self.pending.pop_front() {
+            return Event::Message(message);
+        }
+        self.poll()
+    }
+
+    /// Waits for a new message of the program or a change of the source files.
+    fn poll(&mut self) -> Event {
+        loop {
+            // Also poll while the program keeps sending requests.
+            if self.last_poll.elapsed() >= POLL_INTERVAL {
+                self.last_poll = Instant::now();
+                let mut changed = false;
+                for (path, modified) in &mut self.sources {
+                    let now_modified = modified_time(path);
+                    changed |= now_modified != *modified;
+                    *modified = now_modified;
+                }
+                if changed {
+                    return Event::SourcesChanged;
+                }
+            }
+
+            match self.rx.recv_timeout(POLL_INTERVAL) {
+                Ok(message) => return Event::Message(message),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => unreachable!(),
+            }
+        }
+    }
+
+    /// Starts watching the source files of the crate that are not yet watched. Files that were
+    /// read by the session before a change was noticed keep their previous modification time.
+    fn watch_sources(&mut self, tcx: TyCtxt<'_>) {
+        for file in tcx.sess.source_map().files().iter() {
+            if file.cnum != LOCAL_CRATE {
+                continue;
+            }
+            if let FileName::Real(ref name) = file.name {
+                if let Some(path) = name.local_path() {
+                    if !self.sources.contains_key(path) {
+                        self.sources.insert(path.to_owned(), modified_time(path));
+                    }
+                }
+            }
+        }
+    }
+
+    fn define_fn<'tcx>(&mut self, cx: &mut CodegenCx<'tcx>, instance: Instance<'tcx>) -> FuncId {
+        let tcx = cx.tcx;
+        let name = tcx.symbol_name(instance).name;
+        let sig = get_function_sig(tcx, self.jit_module.isa().triple(), instance);
+        let func_id = self.jit_module.declare_function(name, Linkage::Export, &sig).unwrap();
+        self.jit_module.prepare_for_function_redefine(func_id).unwrap();
+
+        tcx.sess.time("codegen fn", || crate::base::codegen_fn(cx, &mut self.jit_module, instance));
+        self.compiled.insert(name.to_owned());
+        func_id
+    }
+
+    fn finalize_definitions(&mut self, cx: CodegenCx<'_>) {
+        assert!(cx.global_asm.is_empty());
+        self.jit_module.finalize_definitions();
+        unsafe { cx.unwind_context.register_jit(&self.jit_module) };
+    }
+}
+
+fn instances<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mono_items: &[(MonoItem<'tcx>, (RLinkage, Visibility))],
+) -> FxHashMap<String, Instance<'tcx>> {
+    mono_items
+        .iter()
+        .filter_map(|&(mono_item, _)| match mono_item {
+            MonoItem::Fn(instance) => Some((tcx.symbol_name(instance).name.to_owned(), instance)),
+            MonoItem::Static(_) | MonoItem::GlobalAsm(_) => None,
+        })
+        .collect()
+}
+
+/// Hashes everything the code of `instance` is generated from: its MIR, its codegen attributes,
+/// and the layouts of the types and the values of the constants it uses, which the MIR only refers
+/// to by path. Unlike the dependency graph this is independent of the session, so it can be
+/// compared with the fingerprint of the previous session.
+fn fingerprint<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> Fingerprint {
+    let mir = tcx.instance_mir(instance.def);
+    let mut hcx = tcx.create_stable_hashing_context();
+    let mut hasher = StableHasher::new();
+    mir.hash_stable(&mut hcx, &mut hasher);
+    tcx.codegen_fn_attrs(instance.def_id()).hash_stable(&mut hcx, &mut hasher);
+
+    let mut collector = TypeCollector { seen: FxHashSet::default(), tys: Vec::new() };
+    collector.visit_body(mir);
+    for ty in collector.tys {
+        let ty = instance.subst_mir_and_normalize_erasing_regions(tcx, ParamEnv::reveal_all(), ty);
+        let layout = tcx.layout_of(ParamEnv::reveal_all().and(ty)).ok().map(|layout| layout.layout);
+        layout.hash_stable(&mut hcx, &mut hasher);
+    }
+
+    for constant in &mir.required_consts {
+        let literal = instance.subst_mir_and_normalize_erasing_regions(
+            tcx,
+            ParamEnv::reveal_all(),
+            constant.literal,
+        );
+        if let ConstantKind::Ty(&ty::Const { val: ConstKind::Unevaluated(unevaluated), .. }) =
+            literal
+        {
+            let value = tcx.const_eval_resolve(ParamEnv::reveal_all(), unevaluated, None).ok();
+            value.hash_stable(&mut hcx, &mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
+
+/// Collects the types used by a body, in the order they first appear.
+struct TypeCollector<'tcx> {
+    seen: FxHashSet<Ty<'tcx>>,
+    tys: Vec<Ty<'tcx>>,
+}
+
+impl<'tcx> Visitor<'tcx> for TypeCollector<'tcx> {
+    fn visit_ty(&mut self, ty: Ty<'tcx>, _: TyContext) {
+        if self.seen.insert(ty) {
+            self.tys.push(ty);
+        }
+    }
+}
+
+/// Saves the results of the session and publishes its incremental session directory, so that the
+/// next session can reuse them.
+fn persist_dep_graph(tcx: TyCtxt<'_>) {
+    tcx.sess.time("serialize_dep_graph", || rustc_incremental::save_dep_graph(tcx));
+    rustc_incremental::finalize_session_directory(tcx.sess, tcx.crate_hash(LOCAL_CRATE));
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -14,6 +14,7 @@
 extern crate rustc_hir;
 extern crate rustc_incremental;
 extern crate rustc_index;
+extern crate rustc_interface;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
@@ -186,7 +187,7 @@ fn codegen_crate(
         };
         match config.codegen_mode {
             CodegenMode::Aot => driver::aot::run_aot(tcx, config, metadata, need_metadata_module),
-            CodegenMode::Jit | CodegenMode::JitLazy => {
+            CodegenMode::Jit | CodegenMode::JitLazy | CodegenMode::JitHotReload => {
                 #[cfg(feature = "jit")]
                 let _: ! = driver::jit::run_jit(tcx, config);
 
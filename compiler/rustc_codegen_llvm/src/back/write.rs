@@ -179,7 +179,9 @@ pub fn target_machine_factory(
     let abi = SmallCStr::new(&sess.target.llvm_abiname);
     let trap_unreachable =
         sess.opts.debugging_opts.trap_unreachable.unwrap_or(sess.target.trap_unreachable);
-    let emit_stack_size_section = sess.opts.debugging_opts.emit_stack_sizes;
+    // The stack usage report reads the frame sizes from this section.
+    let emit_stack_size_section = sess.opts.debugging_opts.emit_stack_sizes
+        || sess.opts.debugging_opts.stack_usage_report.is_some();
 
     let asm_comments = sess.asm_comments();
     let relax_elf_relocations =
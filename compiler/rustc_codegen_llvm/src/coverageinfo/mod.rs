@@ -182,6 +182,29 @@ fn add_coverage_unreachable(&mut self, instance: Instance<'tcx>, region: CodeReg
             false
         }
     }
+
+    fn add_coverage_branch(
+        &mut self,
+        instance: Instance<'tcx>,
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+        region: CodeRegion,
+    ) -> bool {
+        if let Some(coverage_context) = self.coverage_context() {
+            debug!(
+                "adding branch to coverage_map: instance={:?}, true={:?}, false={:?}, at {:?}",
+                instance, true_operand, false_operand, region,
+            );
+            let mut coverage_map = coverage_context.function_coverage_map.borrow_mut();
+            coverage_map
+                .entry(instance)
+                .or_insert_with(|| FunctionCoverage::new(self.tcx, instance))
+                .add_branch_region(true_operand, false_operand, region);
+            true
+        } else {
+            false
+        }
+    }
 }
 
 fn declare_unused_fn(cx: &CodegenCx<'ll, 'tcx>, def_id: &DefId) -> Instance<'tcx> {
@@ -0,0 +1,351 @@
+//! The report written for `-Z stack-usage-report`.
+//!
+//! The frame size of each function is read from the `.stack_sizes` sections
+//! LLVM emits into the object files, and combined with the calls between the
+//! functions to compute the worst-case stack depth of each entry point. An
+//! entry point is a function that no other function of the crate calls
+//! directly, like `main`, interrupt handlers or the public functions of a
+//! library. Calls from within the recursive cycle of a function don't count,
+//! so every function of a cycle that is only called from within the cycle is
+//! an entry point too.
+//!
+//! The depth is only a lower bound if the code reachable from an entry point
+//! is recursive, calls functions through function pointers or vtables, or
+//! calls functions of other crates, whose frame sizes are unknown. All of
+//! these are listed for each entry point.
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::graph::scc::Sccs;
+use rustc_data_structures::graph::vec_graph::VecGraph;
+use rustc_middle::mir::mono::{CallSite, MonoItem};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{Instance, TyCtxt};
+use rustc_serialize::json::{self, Json, ToJson};
+use rustc_serialize::leb128::read_u64_leb128;
+use rustc_session::Session;
+
+use object::{Object, ObjectSection, ObjectSymbol, RelocationTarget, SymbolKind, SymbolSection};
+
+use std::fs;
+use std::path::Path;
+
+use crate::CompiledModule;
+
+/// The calls between the functions codegened for the crate. This is collected
+/// before codegen, as the report is only written once the object files exist.
+#[derive(Debug, Encodable, Decodable)]
+pub struct CallGraph {
+    functions: Vec<Function>,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+struct Function {
+    symbol: String,
+    name: String,
+    /// Whether the function is codegened for this crate. The frame sizes and
+    /// calls of the functions of other crates are unknown.
+    local: bool,
+    /// The functions called by this function, as indices into `functions`.
+    callees: Vec<usize>,
+    /// Whether this function calls functions through function pointers or
+    /// vtables.
+    dynamic_calls: bool,
+}
+
+impl CallGraph {
+    pub fn new(tcx: TyCtxt<'_>) -> CallGraph {
+        let (_, codegen_units) = tcx.collect_and_partition_mono_items(());
+
+        let mut instances: Vec<_> = codegen_units
+            .iter()
+            .flat_map(|cgu| cgu.items().keys())
+            .filter_map(|mono_item| match *mono_item {
+                MonoItem::Fn(instance) => Some(instance),
+                MonoItem::Static(_) | MonoItem::GlobalAsm(_) => None,
+            })
+            .collect();
+        // Items can be placed into several codegen units.
+        instances.sort_by_cached_key(|&instance| tcx.symbol_name(instance).name);
+        instances.dedup();
+
+        let mut call_graph = CallGraph { functions: Vec::with_capacity(instances.len()) };
+        let mut indices = FxHashMap::default();
+        for &instance in &instances {
+            call_graph.function_index(tcx, &mut indices, instance, true);
+        }
+
+        for &instance in &instances {
+            let caller = indices[&instance];
+            for &call_site in tcx.mono_item_call_sites(instance) {
+                match call_site {
+                    CallSite::Direct(callee) => {
+                        let callee = call_graph.function_index(tcx, &mut indices, callee, false);
+                        call_graph.functions[caller].callees.push(callee);
+                    }
+                    CallSite::Dynamic => call_graph.functions[caller].dynamic_calls = true,
+                }
+            }
+        }
+
+        call_graph
+    }
+
+    fn function_index<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        indices: &mut FxHashMap<Instance<'tcx>, usize>,
+        instance: Instance<'tcx>,
+        local: bool,
+    ) -> usize {
+        let functions = &mut self.functions;
+        *indices.entry(instance).or_insert_with(|| {
+            functions.push(Function {
+                symbol: tcx.symbol_name(instance).name.to_string(),
+                name: with_no_trimmed_paths(|| instance.to_string()),
+                local,
+                callees: Vec::new(),
+                dynamic_calls: false,
+            });
+            functions.len() - 1
+        })
+    }
+}
+
+pub fn write_report(
+    sess: &Session,
+    path: &Path,
+    call_graph: &CallGraph,
+    modules: &[CompiledModule],
+) {
+    let mut frame_sizes = FxHashMap::default();
+    for object in modules.iter().filter_map(|module| module.object.as_ref()) {
+        let result = fs::read(object)
+            .map_err(|e| e.to_string())
+            .and_then(|data| read_frame_sizes(&data, &mut frame_sizes));
+        if let Err(e) = result {
+            sess.err(&format!("failed to read stack sizes from `{}`: {}", object.display(), e));
+        }
+    }
+    if frame_sizes.is_empty() && call_graph.functions.iter().any(|function| function.local) {
+        sess.warn(
+            "no stack sizes were found for `-Z stack-usage-report`, \
+             which is only supported for ELF targets",
+        );
+    }
+
+    let report = report_json(call_graph, &frame_sizes);
+    if let Err(e) = fs::write(path, format!("{}\n", report.pretty())) {
+        sess.err(&format!("failed to write stack usage report to `{}`: {}", path.display(), e));
+    }
+}
+
+/// Reads the frame sizes from the `.stack_sizes` section of an object file, in
+/// which each function has an entry made of its address and its frame size as
+/// ULEB128. As the file is relocatable, the address is given by a relocation
+/// against either the symbol of the function or the section it is defined in.
+/// Functions can be in several object files, so we keep the largest size.
+fn read_frame_sizes(data: &[u8], frame_sizes: &mut FxHashMap<String, u64>) -> Result<(), String> {
+    let file = object::File::parse(data).map_err(|e| e.to_string())?;
+    let section = match file.section_by_name(".stack_sizes") {
+        Some(section) => section,
+        None => return Ok(()),
+    };
+    let section_data = section.data().map_err(|e| e.to_string())?;
+
+    let mut functions_by_address = FxHashMap::default();
+    for symbol in file.symbols() {
+        if let (SymbolKind::Text, SymbolSection::Section(index)) = (symbol.kind(), symbol.section())
+        {
+            if let Ok(name) = symbol.name() {
+                functions_by_address.insert((index, symbol.address()), name);
+            }
+        }
+    }
+    let relocations: FxHashMap<_, _> = section.relocations().collect();
+
+    let address_size = if file.is_64() { 8 } else { 4 };
+    let mut offset = 0;
+    while offset + address_size < section_data.len() {
+        let address = &section_data[offset..offset + address_size];
+        let relocation = relocations.get(&(offset as u64));
+        let (frame_size, len) = read_u64_leb128(&section_data[offset + address_size..]);
+        offset += address_size + len;
+
+        let relocation = match relocation {
+            Some(relocation) => relocation,
+            None => continue,
+        };
+        let addend = if relocation.has_implicit_addend() {
+            let mut bytes = [0; 8];
+            if file.is_little_endian() {
+                bytes[..address_size].copy_from_slice(address);
+                u64::from_le_bytes(bytes)
+            } else {
+                bytes[8 - address_size..].copy_from_slice(address);
+                u64::from_be_bytes(bytes)
+            }
+        } else {
+            relocation.addend() as u64
+        };
+
+        let symbol_name = match relocation.target() {
+            RelocationTarget::Symbol(index) => {
+                let symbol = file.symbol_by_index(index).map_err(|e| e.to_string())?;
+                match (symbol.kind(), symbol.section()) {
+                    (SymbolKind::Section, SymbolSection::Section(section)) => {
+                        functions_by_address.get(&(section, addend)).copied()
+                    }
+                    _ => symbol.name().ok(),
+                }
+            }
+            RelocationTarget::Section(section) => {
+                functions_by_address.get(&(section, addend)).copied()
+            }
+            _ => None,
+        };
+        if let Some(symbol_name) = symbol_name {
+            let max_frame_size = frame_sizes.entry(symbol_name.to_string()).or_insert(0);
+            *max_frame_size = frame_size.max(*max_frame_size);
+        }
+    }
+
+    Ok(())
+}
+
+fn report_json(call_graph: &CallGraph, frame_sizes: &FxHashMap<String, u64>) -> Json {
+    let functions = &call_graph.functions;
+    let frame_size = |function: &Function| frame_sizes.get(&function.symbol).copied();
+
+    let graph = VecGraph::new(
+        functions.len(),
+        functions
+            .iter()
+            .enumerate()
+            .flat_map(|(caller, function)| {
+                function.callees.iter().map(move |&callee| (caller, callee))
+            })
+            .collect(),
+    );
+    let sccs: Sccs<usize, usize> = Sccs::new(&graph);
+    let mut scc_members = vec![Vec::new(); sccs.num_sccs()];
+    for index in 0..functions.len() {
+        scc_members[sccs.scc(index)].push(index);
+    }
+    let is_recursive = |index: usize| {
+        scc_members[sccs.scc(index)].len() > 1 || functions[index].callees.contains(&index)
+    };
+
+    // The deepest stack of each function, not following calls within a
+    // recursive cycle, together with the callee it is reached through. The
+    // SCCs are iterated in dependency order, so all callees outside the cycle
+    // of a function are handled before the function itself.
+    let mut max_stack = vec![(0, None); functions.len()];
+    for scc in sccs.all_sccs() {
+        for &index in &scc_members[scc] {
+            // Functions of this crate without a frame size were inlined into
+            // all of their callers by LLVM.
+            let own_frame = frame_size(&functions[index]).unwrap_or(0);
+            let deepest_callee = functions[index]
+                .callees
+                .iter()
+                .filter(|&&callee| sccs.scc(callee) != scc)
+                .map(|&callee| (max_stack[callee].0, callee))
+                .max();
+            max_stack[index] = match deepest_callee {
+                Some((depth, callee)) => (own_frame + depth, Some(callee)),
+                None => (own_frame, None),
+            };
+        }
+    }
+
+    // Whether each SCC is called by a function of the crate outside of it.
+    let mut scc_is_called = vec![false; sccs.num_sccs()];
+    for (caller, function) in functions.iter().enumerate().filter(|(_, function)| function.local) {
+        for &callee in &function.callees {
+            if sccs.scc(callee) != sccs.scc(caller) {
+                scc_is_called[sccs.scc(callee)] = true;
+            }
+        }
+    }
+
+    let entry_points: Vec<_> = (0..functions.len())
+        .filter(|&index| functions[index].local && !scc_is_called[sccs.scc(index)])
+        .map(|entry_point| {
+            let mut recursive = Vec::new();
+            let mut dynamic_calls = Vec::new();
+            let mut unknown = Vec::new();
+            let mut visited = vec![false; functions.len()];
+            let mut stack = vec![entry_point];
+            visited[entry_point] = true;
+            while let Some(index) = stack.pop() {
+                let function = &functions[index];
+                if !function.local {
+                    unknown.push(function.name.to_json());
+                    continue;
+                }
+                if is_recursive(index) {
+                    recursive.push(function.name.to_json());
+                }
+                if function.dynamic_calls {
+                    dynamic_calls.push(function.name.to_json());
+                }
+                for &callee in &function.callees {
+                    if !visited[callee] {
+                        visited[callee] = true;
+                        stack.push(callee);
+                    }
+                }
+            }
+
+            let mut call_chain = vec![functions[entry_point].name.to_json()];
+            let mut next = max_stack[entry_point].1;
+            while let Some(index) = next {
+                call_chain.push(functions[index].name.to_json());
+                next = max_stack[index].1;
+            }
+
+            let mut obj = json::Object::new();
+            obj.insert("name".to_string(), functions[entry_point].name.to_json());
+            obj.insert("symbol".to_string(), functions[entry_point].symbol.to_json());
+            obj.insert("max_stack".to_string(), max_stack[entry_point].0.to_json());
+            obj.insert(
+                "bounded".to_string(),
+                (recursive.is_empty() && dynamic_calls.is_empty() && unknown.is_empty()).to_json(),
+            );
+            obj.insert("call_chain".to_string(), Json::Array(call_chain));
+            obj.insert("recursive_functions".to_string(), Json::Array(recursive));
+            obj.insert("dynamic_calls".to_string(), Json::Array(dynamic_calls));
+            obj.insert("unknown_functions".to_string(), Json::Array(unknown));
+            Json::Object(obj)
+        })
+        .collect();
+
+    let functions_json: Vec<_> = (0..functions.len())
+        .filter(|&index| functions[index].local)
+        .map(|index| {
+            let function = &functions[index];
+            let mut obj = json::Object::new();
+            obj.insert("name".to_string(), function.name.to_json());
+            obj.insert("symbol".to_string(), function.symbol.to_json());
+            obj.insert("frame_size".to_string(), frame_size(function).to_json());
+            obj.insert(
+                "calls".to_string(),
+                Json::Array(
+                    function
+                        .callees
+                        .iter()
+                        .map(|&callee| functions[callee].name.to_json())
+                        .collect(),
+                ),
+            );
+            obj.insert("dynamic_calls".to_string(), function.dynamic_calls.to_json());
+            obj.insert("recursive".to_string(), is_recursive(index).to_json());
+            Json::Object(obj)
+        })
+        .collect();
+
+    let mut report = json::Object::new();
+    report.insert("entry_points".to_string(), Json::Array(entry_points));
+    report.insert("functions".to_string(), Json::Array(functions_json));
+    Json::Object(report)
+}
@@ -1,6 +1,7 @@
 use super::link::{self, ensure_removed};
 use super::linker::LinkerInfo;
 use super::lto::{self, SerializedModule};
+use super::stack_usage;
 use super::symbol_export::symbol_name_for_instance_in_crate;
 
 use crate::{
@@ -1829,6 +1830,12 @@ pub fn join(self, sess: &Session) -> (CodegenResults, FxHashMap<WorkProductId, W
 
         sess.abort_if_errors();
 
+        if let (Some(path), Some(call_graph)) =
+            (&sess.opts.debugging_opts.stack_usage_report, &self.crate_info.call_graph)
+        {
+            stack_usage::write_report(sess, path, call_graph, &compiled_modules.modules);
+        }
+
         let work_products =
             copy_all_cgu_workproducts_to_incr_comp_cache_dir(sess, &compiled_modules);
         produce_final_output_artifacts(sess, &compiled_modules, &self.output_filenames);
@@ -1,3 +1,4 @@
+use crate::back::stack_usage::CallGraph;
 use crate::back::write::{
     compute_per_cgu_lto_type, start_async_codegen, submit_codegened_module_to_llvm,
     submit_post_lto_module_to_llvm, submit_pre_lto_module_to_llvm, ComputedLtoType, OngoingCodegen,
@@ -786,6 +787,13 @@ pub fn new(tcx: TyCtxt<'_>) -> CrateInfo {
             missing_lang_items: Default::default(),
             dependency_formats: tcx.dependency_formats(()),
             windows_subsystem,
+            call_graph: tcx
+                .sess
+                .opts
+                .debugging_opts
+                .stack_usage_report
+                .is_some()
+                .then(|| CallGraph::new(tcx)),
         };
         let lang_items = tcx.lang_items();
 
@@ -16,11 +16,19 @@ pub struct Expression {
     region: Option<CodeRegion>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchRegion {
+    true_operand: ExpressionOperandId,
+    false_operand: ExpressionOperandId,
+    region: CodeRegion,
+}
+
 /// Collects all of the coverage regions associated with (a) injected counters, (b) counter
-/// expressions (additions or subtraction), and (c) unreachable regions (always counted as zero),
-/// for a given Function. Counters and counter expressions have non-overlapping `id`s because they
-/// can both be operands in an expression. This struct also stores the `function_source_hash`,
-/// computed during instrumentation, and forwarded with counters.
+/// expressions (additions or subtraction), (c) unreachable regions (always counted as zero), and
+/// (d) branch regions (with `-Z instrument-coverage=branch`), for a given Function. Counters and
+/// counter expressions have non-overlapping `id`s because they can both be operands in an
+/// expression. This struct also stores the `function_source_hash`, computed during
+/// instrumentation, and forwarded with counters.
 ///
 /// Note, it may be important to understand LLVM's definitions of `unreachable` regions versus "gap
 /// regions" (or "gap areas"). A gap region is a code region within a counted region (either counter
@@ -36,6 +44,7 @@ pub struct FunctionCoverage<'tcx> {
     counters: IndexVec<CounterValueReference, Option<CodeRegion>>,
     expressions: IndexVec<InjectedExpressionIndex, Option<Expression>>,
     unreachable_regions: Vec<CodeRegion>,
+    branch_regions: Vec<BranchRegion>,
 }
 
 impl<'tcx> FunctionCoverage<'tcx> {
@@ -62,6 +71,7 @@ fn create(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>, is_used: bool) -> Self {
             counters: IndexVec::from_elem_n(None, coverageinfo.num_counters as usize),
             expressions: IndexVec::from_elem_n(None, coverageinfo.num_expressions as usize),
             unreachable_regions: Vec::new(),
+            branch_regions: Vec::new(),
         }
     }
 
@@ -141,18 +151,35 @@ pub fn add_unreachable_region(&mut self, region: CodeRegion) {
         self.unreachable_regions.push(region)
     }
 
+    /// Add a branch region, counting how often the branch was taken (`true_operand`) and not taken
+    /// (`false_operand`). Like expression operands, the operands can reference either counters or
+    /// expressions, and are translated only during code generation.
+    pub fn add_branch_region(
+        &mut self,
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+        region: CodeRegion,
+    ) {
+        self.branch_regions.push(BranchRegion { true_operand, false_operand, region })
+    }
+
     /// Return the source hash, generated from the HIR node structure, and used to indicate whether
     /// or not the source code structure changed between different compilations.
     pub fn source_hash(&self) -> u64 {
         self.source_hash
     }
 
-    /// Generate an array of CounterExpressions, and an iterator over all `Counter`s and their
-    /// associated `Regions` (from which the LLVM-specific `CoverageMapGenerator` will create
+    /// Generate an array of CounterExpressions, an iterator over all `Counter`s and their
+    /// associated `Regions`, and an iterator over the true and false `Counter`s of all branch
+    /// regions (from which the LLVM-specific `CoverageMapGenerator` will create
     /// `CounterMappingRegion`s.
     pub fn get_expressions_and_counter_regions<'a>(
         &'a self,
-    ) -> (Vec<CounterExpression>, impl Iterator<Item = (Counter, &'a CodeRegion)>) {
+    ) -> (
+        Vec<CounterExpression>,
+        impl Iterator<Item = (Counter, &'a CodeRegion)>,
+        impl Iterator<Item = (Counter, Counter, &'a CodeRegion)>,
+    ) {
         assert!(
             self.source_hash != 0 || !self.is_used,
             "No counters provided the source_hash for used function: {:?}",
@@ -160,12 +187,13 @@ pub fn get_expressions_and_counter_regions<'a>(
         );
 
         let counter_regions = self.counter_regions();
-        let (counter_expressions, expression_regions) = self.expressions_with_regions();
+        let (counter_expressions, expression_regions, branch_regions) =
+            self.expressions_with_regions();
         let unreachable_regions = self.unreachable_regions();
 
         let counter_regions =
             counter_regions.chain(expression_regions.into_iter().chain(unreachable_regions));
-        (counter_expressions, counter_regions)
+        (counter_expressions, counter_regions, branch_regions)
     }
 
     fn counter_regions<'a>(&'a self) -> impl Iterator<Item = (Counter, &'a CodeRegion)> {
@@ -178,7 +206,11 @@ fn counter_regions<'a>(&'a self) -> impl Iterator<Item = (Counter, &'a CodeRegio
 
     fn expressions_with_regions(
         &'a self,
-    ) -> (Vec<CounterExpression>, impl Iterator<Item = (Counter, &'a CodeRegion)>) {
+    ) -> (
+        Vec<CounterExpression>,
+        impl Iterator<Item = (Counter, &'a CodeRegion)>,
+        impl Iterator<Item = (Counter, Counter, &'a CodeRegion)>,
+    ) {
         let mut counter_expressions = Vec::with_capacity(self.expressions.len());
         let mut expression_regions = Vec::with_capacity(self.expressions.len());
         let mut new_indexes = IndexVec::from_elem_n(None, self.expressions.len());
@@ -333,7 +365,30 @@ fn expressions_with_regions(
                 );
             }
         }
-        (counter_expressions, expression_regions.into_iter())
+
+        // Branch operands are translated after all expressions have been assigned their
+        // `new_index`, so a branch operand can reference any of the expressions.
+        let branch_regions = self
+            .branch_regions
+            .iter()
+            .map(|BranchRegion { true_operand, false_operand, region }| {
+                let true_counter = id_to_counter(&new_indexes, *true_operand);
+                let false_counter = id_to_counter(&new_indexes, *false_operand);
+                match (true_counter, false_counter) {
+                    (Some(true_counter), Some(false_counter)) => {
+                        (true_counter, false_counter, region)
+                    }
+                    _ => bug!(
+                        "branch region has one or more missing operands \
+                        true_operand={:?}, false_operand={:?}, region={:?}",
+                        true_operand,
+                        false_operand,
+                        region,
+                    ),
+                }
+            })
+            .collect::<Vec<_>>();
+        (counter_expressions, expression_regions.into_iter(), branch_regions.into_iter())
     }
 
     fn unreachable_regions<'a>(&'a self) -> impl Iterator<Item = (Counter, &'a CodeRegion)> {
@@ -150,6 +150,8 @@ pub struct CrateInfo {
     pub missing_lang_items: FxHashMap<CrateNum, Vec<LangItem>>,
     pub dependency_formats: Lrc<Dependencies>,
     pub windows_subsystem: Option<String>,
+    /// Only collected for `-Z stack-usage-report`.
+    pub call_graph: Option<back::stack_usage::CallGraph>,
 }
 
 #[derive(Encodable, Decodable)]
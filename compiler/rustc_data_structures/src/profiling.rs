@@ -79,6 +79,13 @@
 //! invocation) and allocate the corresponding strings together with a mapping
 //! for `DepNodeIndex as StringId`.
 //!
+//!
+//! ## Summary Report
+//!
+//! With `-Z self-profile-report`, the events are also summed up per query and
+//! per generic activity while they are recorded, and a summary is printed at
+//! the end of the compilation session. See the `report` module for details.
+//!
 //! [mm]: https://github.com/rust-lang/measureme/
 
 use crate::cold_path;
@@ -97,6 +104,11 @@
 use measureme::{EventId, EventIdBuilder, Profiler, SerializableString, StringId};
 use parking_lot::RwLock;
 
+use self::report::{ReportCollector, ReportEventKind, ReportTimer};
+pub use self::report::ReportFormat;
+
+mod report;
+
 bitflags::bitflags! {
     struct EventFilter: u32 {
         const GENERIC_ACTIVITIES = 1 << 0;
@@ -242,9 +254,10 @@ pub fn extra_verbose_generic_activity<'a, A>(
     #[inline(always)]
     pub fn generic_activity(&self, event_label: &'static str) -> TimingGuard<'_> {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
-            let event_id = EventId::from_label(event_label);
+            let event_label_id = profiler.get_or_alloc_cached_string(event_label);
+            let event_id = EventId::from_label(event_label_id);
             TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+                .with_report(profiler, ReportEventKind::Activity(event_label))
         })
     }
 
@@ -261,14 +274,15 @@ pub fn generic_activity_with_arg<A>(
     {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
             let builder = EventIdBuilder::new(&profiler.profiler);
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
+            let event_label_id = profiler.get_or_alloc_cached_string(event_label);
             let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
                 let event_arg = profiler.get_or_alloc_cached_string(event_arg);
-                builder.from_label_and_arg(event_label, event_arg)
+                builder.from_label_and_arg(event_label_id, event_arg)
             } else {
-                builder.from_label(event_label)
+                builder.from_label(event_label_id)
             };
             TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+                .with_report(profiler, ReportEventKind::Activity(event_label))
         })
     }
 
@@ -280,17 +294,18 @@ pub fn generic_activity_with_args(
     ) -> TimingGuard<'_> {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
             let builder = EventIdBuilder::new(&profiler.profiler);
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
+            let event_label_id = profiler.get_or_alloc_cached_string(event_label);
             let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
                 let event_args: Vec<_> = event_args
                     .iter()
                     .map(|s| profiler.get_or_alloc_cached_string(&s[..]))
                     .collect();
-                builder.from_label_and_args(event_label, &event_args)
+                builder.from_label_and_args(event_label_id, &event_args)
             } else {
-                builder.from_label(event_label)
+                builder.from_label(event_label_id)
             };
             TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+                .with_report(profiler, ReportEventKind::Activity(event_label))
         })
     }
 
@@ -300,6 +315,7 @@ pub fn generic_activity_with_args(
     pub fn query_provider(&self) -> TimingGuard<'_> {
         self.exec(EventFilter::QUERY_PROVIDERS, |profiler| {
             TimingGuard::start(profiler, profiler.query_event_kind, EventId::INVALID)
+                .with_report(profiler, ReportEventKind::QueryProvider)
         })
     }
 
@@ -313,6 +329,31 @@ pub fn query_cache_hit(&self, query_invocation_id: QueryInvocationId) {
         );
     }
 
+    /// Record a query whose dep node from the previous incremental session was
+    /// marked green, so that it did not have to be executed again, or only
+    /// because its result was not cached on disk. This is only counted for the
+    /// summary report.
+    #[inline(always)]
+    pub fn query_incr_cache_hit(&self, query_invocation_id: QueryInvocationId) {
+        self.with_profiler(|profiler| {
+            if let Some(report) = &profiler.report {
+                report.record_incr_cache_hit(query_invocation_id.0);
+            }
+        });
+    }
+
+    /// Record a query that was executed again in an incremental session because
+    /// its dep node from the previous session could not be marked green. This
+    /// is only counted for the summary report.
+    #[inline(always)]
+    pub fn query_incr_cache_miss(&self, query_invocation_id: QueryInvocationId) {
+        self.with_profiler(|profiler| {
+            if let Some(report) = &profiler.report {
+                report.record_incr_cache_miss(query_invocation_id.0);
+            }
+        });
+    }
+
     /// Start profiling a query being blocked on a concurrent execution.
     /// Profiling continues until the TimingGuard returned from this call is
     /// dropped.
@@ -320,6 +361,7 @@ pub fn query_cache_hit(&self, query_invocation_id: QueryInvocationId) {
     pub fn query_blocked(&self) -> TimingGuard<'_> {
         self.exec(EventFilter::QUERY_BLOCKED, |profiler| {
             TimingGuard::start(profiler, profiler.query_blocked_event_kind, EventId::INVALID)
+                .with_report(profiler, ReportEventKind::QueryBlocked)
         })
     }
 
@@ -334,6 +376,7 @@ pub fn incr_cache_loading(&self) -> TimingGuard<'_> {
                 profiler.incremental_load_result_event_kind,
                 EventId::INVALID,
             )
+            .with_report(profiler, ReportEventKind::IncrCacheLoad)
         })
     }
 
@@ -345,6 +388,10 @@ fn instant_query_event(
         event_filter: EventFilter,
     ) {
         drop(self.exec(event_filter, |profiler| {
+            if let Some(report) = &profiler.report {
+                report.record_query_cache_hit(query_invocation_id.0);
+            }
+
             let event_id = StringId::new_virtual(query_invocation_id.0);
             let thread_id = std::thread::current().id().as_u64().get() as u32;
 
@@ -377,6 +424,22 @@ pub fn llvm_recording_enabled(&self) -> bool {
     pub fn get_self_profiler(&self) -> Option<Arc<SelfProfiler>> {
         self.profiler.clone()
     }
+
+    #[inline]
+    pub fn report_enabled(&self) -> bool {
+        self.profiler.as_ref().map_or(false, |profiler| profiler.report_enabled())
+    }
+
+    /// Prints the summary report requested with `-Z self-profile-report`, if
+    /// any. This should be called at the end of the compilation session, after
+    /// the query strings have been allocated.
+    pub fn print_report(&self, incremental: bool) {
+        self.with_profiler(|profiler| {
+            if let Some(report) = &profiler.report {
+                report.print(incremental);
+            }
+        });
+    }
 }
 
 pub struct SelfProfiler {
@@ -390,6 +453,8 @@ pub struct SelfProfiler {
     incremental_load_result_event_kind: StringId,
     query_blocked_event_kind: StringId,
     query_cache_hit_event_kind: StringId,
+
+    report: Option<ReportCollector>,
 }
 
 impl SelfProfiler {
@@ -397,6 +462,7 @@ pub fn new(
         output_directory: &Path,
         crate_name: Option<&str>,
         event_filters: &Option<Vec<String>>,
+        report_format: Option<ReportFormat>,
     ) -> Result<SelfProfiler, Box<dyn Error + Send + Sync>> {
         fs::create_dir_all(output_directory)?;
 
@@ -444,6 +510,11 @@ pub fn new(
             event_filter_mask = EventFilter::DEFAULT;
         }
 
+        // The report counts query cache hits, which are not recorded by default.
+        if report_format.is_some() {
+            event_filter_mask |= EventFilter::QUERY_CACHE_HITS;
+        }
+
         Ok(SelfProfiler {
             profiler,
             event_filter_mask,
@@ -453,6 +524,7 @@ pub fn new(
             incremental_load_result_event_kind,
             query_blocked_event_kind,
             query_cache_hit_event_kind,
+            report: report_format.map(ReportCollector::new),
         })
     }
 
@@ -504,6 +576,21 @@ pub fn bulk_map_query_invocation_id_to_single_string<I>(&self, from: I, to: Stri
         self.profiler.bulk_map_virtual_to_single_concrete_string(from, to);
     }
 
+    /// Records which query an invocation belongs to, for the summary report.
+    pub fn map_query_invocation_id_to_query_name(
+        &self,
+        query_invocation_id: QueryInvocationId,
+        query_name: &'static str,
+    ) {
+        if let Some(report) = &self.report {
+            report.map_query_invocation_id_to_query_name(query_invocation_id.0, query_name);
+        }
+    }
+
+    pub fn report_enabled(&self) -> bool {
+        self.report.is_some()
+    }
+
     pub fn query_key_recording_enabled(&self) -> bool {
         self.event_filter_mask.contains(EventFilter::QUERY_KEYS)
     }
@@ -514,7 +601,10 @@ pub fn event_id_builder(&self) -> EventIdBuilder<'_> {
 }
 
 #[must_use]
-pub struct TimingGuard<'a>(Option<measureme::TimingGuard<'a>>);
+pub struct TimingGuard<'a> {
+    guard: Option<measureme::TimingGuard<'a>>,
+    report: Option<ReportTimer<'a>>,
+}
 
 impl<'a> TimingGuard<'a> {
     #[inline]
@@ -527,23 +617,36 @@ pub fn start(
         let raw_profiler = &profiler.profiler;
         let timing_guard =
             raw_profiler.start_recording_interval_event(event_kind, event_id, thread_id);
-        TimingGuard(Some(timing_guard))
+        TimingGuard { guard: Some(timing_guard), report: None }
+    }
+
+    /// Makes the event count towards the summary report, if one was requested.
+    #[inline]
+    fn with_report(mut self, profiler: &'a SelfProfiler, kind: ReportEventKind) -> TimingGuard<'a> {
+        if let Some(report) = &profiler.report {
+            self.report = Some(ReportTimer::start(report, kind));
+        }
+        self
     }
 
     #[inline]
     pub fn finish_with_query_invocation_id(self, query_invocation_id: QueryInvocationId) {
-        if let Some(guard) = self.0 {
+        let TimingGuard { guard, report } = self;
+        if let Some(guard) = guard {
             cold_path(|| {
                 let event_id = StringId::new_virtual(query_invocation_id.0);
                 let event_id = EventId::from_virtual(event_id);
                 guard.finish_with_override_event_id(event_id);
+                if let Some(mut report) = report {
+                    report.query_invocation_id = Some(query_invocation_id.0);
+                }
             });
         }
     }
 
     #[inline]
     pub fn none() -> TimingGuard<'a> {
-        TimingGuard(None)
+        TimingGuard { guard: None, report: None }
     }
 
     #[inline(always)]
@@ -0,0 +1,387 @@
+//! Aggregation of self-profiling events into a summary report.
+//!
+//! With `-Z self-profile-report`, the `SelfProfiler` does not only record
+//! events to disk, it also sums them up per query and per generic activity
+//! while the compiler runs. At the end of the compilation session the sums are
+//! printed, either as a table or as JSON, so that no separate post-processing
+//! step with the `measureme` tools is needed for a quick overview.
+//!
+//! Query events only know the `QueryInvocationId` of the query they belong to,
+//! so they are summed up per invocation. The invocations are mapped to query
+//! names in bulk, when the query strings are allocated at the end of the
+//! session, just like it is done for the event data on disk.
+//!
+//! The self time of an event is its total time minus the total time of all
+//! the events nested in it. Nesting is tracked with a stack of child times per
+//! thread.
+
+use crate::fx::FxHashMap;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use rustc_serialize::json::{Json, ToJson};
+
+#[cfg(test)]
+mod tests;
+
+/// The format of the report printed for `-Z self-profile-report`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<ReportFormat, ()> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What an interval event is summed up as.
+#[derive(Clone, Copy)]
+pub(super) enum ReportEventKind {
+    Activity(&'static str),
+    QueryProvider,
+    QueryBlocked,
+    IncrCacheLoad,
+}
+
+#[derive(Clone, Copy, Default)]
+struct ItemStats {
+    self_time: Duration,
+    total_time: Duration,
+    /// The number of times a generic activity ran or a query provider was
+    /// executed.
+    invocations: u64,
+    cache_hits: u64,
+    blocked_time: Duration,
+    /// The number of queries whose dep node was marked green, whether their
+    /// result was then loaded from the incremental cache or recomputed. Per
+    /// query invocation this is at most one, no matter how often the node was
+    /// found to be green.
+    incr_hits: u64,
+    /// The number of times a query whose dep node existed in the previous
+    /// incremental session could not be marked green and was executed again.
+    incr_misses: u64,
+    incr_loads: u64,
+    incr_load_time: Duration,
+}
+
+impl ItemStats {
+    fn add(&mut self, other: &ItemStats) {
+        self.self_time += other.self_time;
+        self.total_time += other.total_time;
+        self.invocations += other.invocations;
+        self.cache_hits += other.cache_hits;
+        self.blocked_time += other.blocked_time;
+        self.incr_hits += other.incr_hits;
+        self.incr_misses += other.incr_misses;
+        self.incr_loads += other.incr_loads;
+        self.incr_load_time += other.incr_load_time;
+    }
+}
+
+thread_local! {
+    /// The total time of the events nested in each event that is currently
+    /// being timed on this thread, innermost last.
+    static CHILD_TIMES: RefCell<Vec<Duration>> = RefCell::new(Vec::new());
+}
+
+pub(super) struct ReportCollector {
+    format: ReportFormat,
+    activities: Mutex<FxHashMap<&'static str, ItemStats>>,
+    query_invocations: Mutex<FxHashMap<u32, ItemStats>>,
+    query_names: Mutex<FxHashMap<u32, &'static str>>,
+}
+
+impl ReportCollector {
+    pub(super) fn new(format: ReportFormat) -> ReportCollector {
+        ReportCollector {
+            format,
+            activities: Default::default(),
+            query_invocations: Default::default(),
+            query_names: Default::default(),
+        }
+    }
+
+    pub(super) fn record_query_cache_hit(&self, query_invocation_id: u32) {
+        self.query_invocations.lock().entry(query_invocation_id).or_default().cache_hits += 1;
+    }
+
+    pub(super) fn record_incr_cache_hit(&self, query_invocation_id: u32) {
+        self.query_invocations.lock().entry(query_invocation_id).or_default().incr_hits = 1;
+    }
+
+    pub(super) fn record_incr_cache_miss(&self, query_invocation_id: u32) {
+        self.query_invocations.lock().entry(query_invocation_id).or_default().incr_misses += 1;
+    }
+
+    pub(super) fn map_query_invocation_id_to_query_name(
+        &self,
+        query_invocation_id: u32,
+        query_name: &'static str,
+    ) {
+        self.query_names.lock().insert(query_invocation_id, query_name);
+    }
+
+    fn record(
+        &self,
+        kind: ReportEventKind,
+        query_invocation_id: Option<u32>,
+        self_time: Duration,
+        total_time: Duration,
+    ) {
+        let mut activities;
+        let mut query_invocations;
+        let stats = match (kind, query_invocation_id) {
+            (ReportEventKind::Activity(label), _) => {
+                activities = self.activities.lock();
+                activities.entry(label).or_default()
+            }
+            (_, Some(query_invocation_id)) => {
+                query_invocations = self.query_invocations.lock();
+                query_invocations.entry(query_invocation_id).or_default()
+            }
+            // A query event that was not finished with an invocation id, which
+            // only happens when unwinding. There is nothing to attribute it to.
+            (_, None) => return,
+        };
+
+        match kind {
+            ReportEventKind::Activity(_) | ReportEventKind::QueryProvider => {
+                stats.invocations += 1;
+                stats.self_time += self_time;
+                stats.total_time += total_time;
+            }
+            ReportEventKind::IncrCacheLoad => {
+                stats.incr_loads += 1;
+                stats.incr_load_time += total_time;
+                stats.self_time += self_time;
+                stats.total_time += total_time;
+            }
+            // Time spent waiting on another thread is not part of the query's
+            // own execution time.
+            ReportEventKind::QueryBlocked => stats.blocked_time += total_time,
+        }
+    }
+
+    /// Sums up the statistics per query and per activity, sorted by
+    /// descending self time.
+    fn items(&self) -> Vec<ReportItem> {
+        let mut queries: FxHashMap<&'static str, ItemStats> = FxHashMap::default();
+        {
+            let query_names = self.query_names.lock();
+            for (id, stats) in self.query_invocations.lock().iter() {
+                let name = query_names.get(id).copied().unwrap_or("<unknown>");
+                queries.entry(name).or_default().add(stats);
+            }
+        }
+
+        let queries =
+            queries.into_iter().map(|(name, stats)| ReportItem { name, is_query: true, stats });
+        let activities = self.activities.lock();
+        let activities =
+            activities.iter().map(|(&name, &stats)| ReportItem { name, is_query: false, stats });
+
+        let mut items: Vec<_> = queries.chain(activities).collect();
+        items.sort_by(|a, b| {
+            b.stats.self_time.cmp(&a.stats.self_time).then_with(|| a.name.cmp(b.name))
+        });
+        items
+    }
+
+    /// Prints the report to stderr. Incremental cache hits and misses are only
+    /// reported for `incremental` sessions.
+    pub(super) fn print(&self, incremental: bool) {
+        let items = self.items();
+        match self.format {
+            ReportFormat::Table => print_table(&items, incremental),
+            ReportFormat::Json => eprintln!("{}", report_json(&items, incremental).pretty()),
+        }
+    }
+}
+
+struct ReportItem {
+    name: &'static str,
+    is_query: bool,
+    stats: ItemStats,
+}
+
+impl ReportItem {
+    fn kind(&self) -> &'static str {
+        if self.is_query { "query" } else { "activity" }
+    }
+
+    /// Incremental cache hits and misses only make sense for queries in
+    /// incremental sessions.
+    fn incr_hits_and_misses(&self, incremental: bool) -> Option<(u64, u64)> {
+        if self.is_query && incremental {
+            Some((self.stats.incr_hits, self.stats.incr_misses))
+        } else {
+            None
+        }
+    }
+}
+
+fn print_table(items: &[ReportItem], incremental: bool) {
+    const HEADER: [&str; 12] = [
+        "Item",
+        "Kind",
+        "Self time (ms)",
+        "% of total",
+        "Total time (ms)",
+        "Invocations",
+        "Cache hits",
+        "Incr. hits",
+        "Incr. misses",
+        "Incr. loads",
+        "Incr. load time (ms)",
+        "Blocked time (ms)",
+    ];
+
+    let total_self_time: Duration = items.iter().map(|item| item.stats.self_time).sum();
+    let ms = |d: Duration| format!("{:.3}", d.as_secs_f64() * 1000.0);
+    let rows: Vec<[String; 12]> = items
+        .iter()
+        .map(|item| {
+            let stats = &item.stats;
+            let percent = if total_self_time == Duration::ZERO {
+                0.0
+            } else {
+                stats.self_time.as_secs_f64() * 100.0 / total_self_time.as_secs_f64()
+            };
+            let query_only = |cell: String| if item.is_query { cell } else { "-".to_string() };
+            let (incr_hits, incr_misses) = match item.incr_hits_and_misses(incremental) {
+                Some((hits, misses)) => (hits.to_string(), misses.to_string()),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            [
+                item.name.to_string(),
+                item.kind().to_string(),
+                ms(stats.self_time),
+                format!("{:.2}", percent),
+                ms(stats.total_time),
+                stats.invocations.to_string(),
+                query_only(stats.cache_hits.to_string()),
+                incr_hits,
+                incr_misses,
+                query_only(stats.incr_loads.to_string()),
+                query_only(ms(stats.incr_load_time)),
+                query_only(ms(stats.blocked_time)),
+            ]
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = HEADER.iter().map(|cell| cell.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    print_row(HEADER.iter().copied(), &widths);
+    for row in &rows {
+        print_row(row.iter().map(String::as_str), &widths);
+    }
+    eprintln!("Total self time: {} ms", ms(total_self_time));
+}
+
+fn print_row<'a>(cells: impl Iterator<Item = &'a str>, widths: &[usize]) {
+    let cells: Vec<String> = cells
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, &width))| {
+            // The name and kind columns are left-aligned, the numbers right-aligned.
+            if i < 2 {
+                format!("{:<width$}", cell, width = width)
+            } else {
+                format!("{:>width$}", cell, width = width)
+            }
+        })
+        .collect();
+    eprintln!("{}", cells.join(" | "));
+}
+
+fn report_json(items: &[ReportItem], incremental: bool) -> Json {
+    let nanos = |d: Duration| (d.as_nanos() as u64).to_json();
+    let items = items
+        .iter()
+        .map(|item| {
+            let stats = &item.stats;
+            let mut obj = BTreeMap::new();
+            obj.insert("name".to_string(), item.name.to_json());
+            obj.insert("kind".to_string(), item.kind().to_json());
+            obj.insert("self_time_ns".to_string(), nanos(stats.self_time));
+            obj.insert("total_time_ns".to_string(), nanos(stats.total_time));
+            obj.insert("invocations".to_string(), stats.invocations.to_json());
+            if item.is_query {
+                obj.insert("cache_hits".to_string(), stats.cache_hits.to_json());
+                obj.insert("blocked_time_ns".to_string(), nanos(stats.blocked_time));
+                let incr_hits_and_misses = item.incr_hits_and_misses(incremental);
+                let incr_hits = incr_hits_and_misses.map(|(hits, _)| hits);
+                let incr_misses = incr_hits_and_misses.map(|(_, misses)| misses);
+                obj.insert("incr_hits".to_string(), incr_hits.to_json());
+                obj.insert("incr_misses".to_string(), incr_misses.to_json());
+                obj.insert("incr_loads".to_string(), stats.incr_loads.to_json());
+                obj.insert("incr_load_time_ns".to_string(), nanos(stats.incr_load_time));
+            }
+            Json::Object(obj)
+        })
+        .collect();
+
+    let mut report = BTreeMap::new();
+    report.insert("incremental".to_string(), incremental.to_json());
+    report.insert("items".to_string(), Json::Array(items));
+    Json::Object(report)
+}
+
+/// Times a single interval event for the report. The event is recorded when
+/// the timer is dropped.
+pub(super) struct ReportTimer<'a> {
+    collector: &'a ReportCollector,
+    kind: ReportEventKind,
+    pub(super) query_invocation_id: Option<u32>,
+    start: Instant,
+    depth: usize,
+}
+
+impl<'a> ReportTimer<'a> {
+    pub(super) fn start(collector: &'a ReportCollector, kind: ReportEventKind) -> ReportTimer<'a> {
+        let depth = CHILD_TIMES.with(|child_times| {
+            let mut child_times = child_times.borrow_mut();
+            child_times.push(Duration::ZERO);
+            child_times.len() - 1
+        });
+        ReportTimer { collector, kind, query_invocation_id: None, start: Instant::now(), depth }
+    }
+}
+
+impl Drop for ReportTimer<'_> {
+    fn drop(&mut self) {
+        let total_time = self.start.elapsed();
+        let child_time = CHILD_TIMES.with(|child_times| {
+            let mut child_times = child_times.borrow_mut();
+            // Timers are normally dropped in the reverse order of their
+            // creation. If one isn't, the time of the timers started after it
+            // cannot be attributed correctly anymore, so it is discarded.
+            let child_time = child_times.get(self.depth).copied().unwrap_or(Duration::ZERO);
+            child_times.truncate(self.depth);
+            if let Some(parent_child_time) = child_times.last_mut() {
+                *parent_child_time += total_time;
+            }
+            child_time
+        });
+        let self_time = total_time.saturating_sub(child_time);
+        self.collector.record(self.kind, self.query_invocation_id, self_time, total_time);
+    }
+}
@@ -0,0 +1,74 @@
+use super::*;
+
+use std::thread;
+
+#[test]
+fn test_self_time_excludes_nested_events() {
+    let collector = ReportCollector::new(ReportFormat::Table);
+    {
+        let _outer = ReportTimer::start(&collector, ReportEventKind::Activity("outer"));
+        thread::sleep(Duration::from_millis(5));
+        let mut inner = ReportTimer::start(&collector, ReportEventKind::QueryProvider);
+        inner.query_invocation_id = Some(0);
+        thread::sleep(Duration::from_millis(5));
+    }
+    collector.map_query_invocation_id_to_query_name(0, "type_of");
+
+    let items = collector.items();
+    let outer = items.iter().find(|item| item.name == "outer").unwrap();
+    let inner = items.iter().find(|item| item.name == "type_of").unwrap();
+    assert!(!outer.is_query);
+    assert!(inner.is_query);
+    assert_eq!(outer.stats.self_time + inner.stats.total_time, outer.stats.total_time);
+    assert_eq!(inner.stats.self_time, inner.stats.total_time);
+    assert!(inner.stats.self_time >= Duration::from_millis(5));
+}
+
+#[test]
+fn test_query_invocations_are_summed_up_per_query() {
+    let collector = ReportCollector::new(ReportFormat::Json);
+    let finish = |kind, query_invocation_id| {
+        let mut timer = ReportTimer::start(&collector, kind);
+        timer.query_invocation_id = Some(query_invocation_id);
+    };
+    finish(ReportEventKind::QueryProvider, 1);
+    finish(ReportEventKind::QueryProvider, 2);
+    finish(ReportEventKind::IncrCacheLoad, 3);
+    finish(ReportEventKind::QueryBlocked, 3);
+    finish(ReportEventKind::QueryProvider, 4);
+    collector.record_query_cache_hit(1);
+    collector.record_query_cache_hit(3);
+    collector.record_query_cache_hit(3);
+    // Only one of the executions was a red node from the previous session,
+    // the other one was a new node. The loaded result was marked green, twice.
+    collector.record_incr_cache_miss(2);
+    collector.record_incr_cache_hit(3);
+    collector.record_incr_cache_hit(3);
+    for id in 1..=3 {
+        collector.map_query_invocation_id_to_query_name(id, "mir_built");
+    }
+
+    let items = collector.items();
+    assert_eq!(items.len(), 2);
+    let mir_built = items.iter().find(|item| item.name == "mir_built").unwrap();
+    assert_eq!(mir_built.stats.invocations, 2);
+    assert_eq!(mir_built.stats.incr_loads, 1);
+    assert_eq!(mir_built.stats.cache_hits, 3);
+    assert_eq!(mir_built.incr_hits_and_misses(true), Some((1, 1)));
+    assert_eq!(mir_built.incr_hits_and_misses(false), None);
+    let unknown = items.iter().find(|item| item.name == "<unknown>").unwrap();
+    assert_eq!(unknown.stats.invocations, 1);
+
+    let json = report_json(&items, false);
+    let mir_built = json["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["name"].as_string() == Some("mir_built"))
+        .unwrap();
+    assert_eq!(mir_built["kind"].as_string(), Some("query"));
+    assert_eq!(mir_built["cache_hits"].as_u64(), Some(3));
+    assert_eq!(mir_built["incr_hits"], Json::Null);
+    assert_eq!(mir_built["incr_misses"], Json::Null);
+    assert_eq!(mir_built["incr_loads"].as_u64(), Some(1));
+}
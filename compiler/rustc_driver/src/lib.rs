@@ -413,8 +413,8 @@ fn run_compiler(
 
             queries.ongoing_codegen()?;
 
-            if sess.opts.debugging_opts.print_type_sizes {
-                sess.code_stats.print_type_sizes();
+            if let Some(format) = sess.opts.debugging_opts.print_type_sizes {
+                sess.code_stats.print_type_sizes(format);
             }
 
             let linker = queries.linker()?;
@@ -652,9 +652,10 @@ fn print_crate_info(
         ofile: &Option<PathBuf>,
     ) -> Compilation {
         use rustc_session::config::PrintRequest::*;
-        // PrintRequest::NativeStaticLibs is special - printed during linking
+        // PrintRequest::NativeStaticLibs and PrintRequest::CrateGraph are special -
+        // printed during linking and crate loading respectively
         // (empty iterator returns true)
-        if sess.opts.prints.iter().all(|&p| p == PrintRequest::NativeStaticLibs) {
+        if sess.opts.prints.iter().all(|&p| p.is_printed_during_compilation()) {
             return Compilation::Continue;
         }
 
@@ -743,7 +744,7 @@ fn print_crate_info(
                     codegen_backend.print(*req, sess);
                 }
                 // Any output here interferes with Cargo's parsing of other printed output
-                PrintRequest::NativeStaticLibs => {}
+                PrintRequest::NativeStaticLibs | PrintRequest::CrateGraph => {}
             }
         }
         Compilation::Stop
@@ -1151,9 +1152,11 @@ pub fn catch_fatal_errors<F: FnOnce() -> R, R>(f: F) -> Result<R, ErrorReported>
 }
 
 /// Variant of `catch_fatal_errors` for the `interface::Result` return type
-/// that also computes the exit code.
+/// that also computes the exit code and writes out any diagnostics that were
+/// buffered because they were reported outside of a session.
 pub fn catch_with_exit_code(f: impl FnOnce() -> interface::Result<()>) -> i32 {
     let result = catch_fatal_errors(f).and_then(|result| result);
+    rustc_errors::sarif::flush_early_log();
     match result {
         Ok(()) => EXIT_SUCCESS,
         Err(_) => EXIT_FAILURE,
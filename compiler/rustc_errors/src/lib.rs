@@ -9,6 +9,7 @@
 #![feature(format_args_capture)]
 #![feature(iter_zip)]
 #![feature(nll)]
+#![feature(once_cell)]
 
 #[macro_use]
 extern crate rustc_macros;
@@ -47,6 +48,7 @@
 pub mod json;
 mod lock;
 pub mod registry;
+pub mod sarif;
 mod snippet;
 mod styled_buffer;
 pub use snippet::Style;
@@ -0,0 +1,424 @@
+//! A SARIF emitter for errors.
+//!
+//! [SARIF] (Static Analysis Results Interchange Format) is the format consumed by most
+//! code-scanning services. Unlike the JSON emitter, which prints one object per diagnostic,
+//! a SARIF log describes a whole run of a tool as a single document. Diagnostics are therefore
+//! buffered as they are emitted and the log is written out once the emitter is dropped.
+//!
+//! Each rustc `Diagnostic` is mapped to one SARIF `result`:
+//!
+//! - the level becomes the result `level` (`error`, `warning`, `note` or `none`),
+//! - the error code or lint name becomes the `ruleId`, with a matching entry in the tool's `rules`
+//!   carrying the extended explanation when the registry has one,
+//! - primary spans become `locations`, secondary spans and sub-diagnostics become
+//!   `relatedLocations`,
+//! - every `CodeSuggestion` becomes a `fix` with one `replacement` per substitution part.
+//!
+//! Diagnostics reported before there is a session (see `SarifEmitter::early`) are part of the
+//! same run, so they are buffered separately and written out along with the session's log, or by
+//! `flush_early_log` if there is no session to do so.
+//!
+//! As with the JSON output, the format of this output should be considered *unstable*.
+//!
+//! [SARIF]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use rustc_span::source_map::{FilePathMapping, SourceMap};
+
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::{CodeSuggestion, DiagnosticId, Level, SubDiagnostic};
+
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_data_structures::sync::Lrc;
+use rustc_span::{Span, SpanLabel};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::lazy::SyncLazy;
+use std::mem;
+use std::sync::{Mutex, MutexGuard};
+
+use rustc_serialize::json::{Json, ToJson};
+
+#[cfg(test)]
+mod tests;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// The rules and results of the diagnostics emitted through early emitters so far.
+static EARLY_LOG: SyncLazy<Mutex<EarlyLog>> = SyncLazy::new(Default::default);
+
+#[derive(Default)]
+struct EarlyLog {
+    pretty: bool,
+    rules: FxIndexMap<String, Option<&'static str>>,
+    results: Vec<Json>,
+}
+
+fn early_log() -> MutexGuard<'static, EarlyLog> {
+    // A panic while holding the lock leaves the log consistent, so there's no reason to give up.
+    EARLY_LOG.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Writes the diagnostics of early emitters out as a SARIF log of their own, unless the log
+/// of a session already included them. Drivers call this right before exiting.
+pub fn flush_early_log() {
+    let EarlyLog { pretty, rules, results } = mem::take(&mut *early_log());
+    if results.is_empty() {
+        return;
+    }
+    let log = sarif_log(&rules, results);
+    if let Err(e) = write_log(&mut io::stderr(), &log, pretty) {
+        panic!("failed to print SARIF log: {:?}", e);
+    }
+}
+
+enum Destination {
+    /// The log is written to stderr, along with the results of early emitters.
+    Stderr(io::BufWriter<io::Stderr>),
+    Raw(Box<dyn Write + Send>),
+    /// The rules and results are added to `EARLY_LOG` instead of being written.
+    Early,
+}
+
+pub struct SarifEmitter {
+    dst: Destination,
+    registry: Option<Registry>,
+    sm: Lrc<SourceMap>,
+    pretty: bool,
+    /// Every rule (error code or lint name) referenced by a result so far, in order of first
+    /// appearance, along with its extended explanation if there is one.
+    rules: FxIndexMap<String, Option<&'static str>>,
+    /// The results collected so far; written out on drop.
+    results: Vec<Json>,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>, source_map: Lrc<SourceMap>, pretty: bool) -> Self {
+        let dst = Destination::Stderr(io::BufWriter::new(io::stderr()));
+        SarifEmitter::with_destination(dst, registry, source_map, pretty)
+    }
+
+    /// Creates an emitter for diagnostics that are reported outside of a session, such as
+    /// errors in command-line arguments. Rather than each writing a log of their own, such
+    /// emitters buffer their results until `flush_early_log` or the session's emitter write
+    /// them out.
+    pub fn early(pretty: bool) -> Self {
+        let source_map = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        SarifEmitter::with_destination(Destination::Early, None, source_map, pretty)
+    }
+
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        registry: Option<Registry>,
+        source_map: Lrc<SourceMap>,
+        pretty: bool,
+    ) -> Self {
+        SarifEmitter::with_destination(Destination::Raw(dst), registry, source_map, pretty)
+    }
+
+    fn with_destination(
+        dst: Destination,
+        registry: Option<Registry>,
+        source_map: Lrc<SourceMap>,
+        pretty: bool,
+    ) -> Self {
+        SarifEmitter {
+            dst,
+            registry,
+            sm: source_map,
+            pretty,
+            rules: Default::default(),
+            results: Vec::new(),
+        }
+    }
+
+    fn result_from_diagnostic(&mut self, diag: &crate::Diagnostic) -> Json {
+        let mut result = BTreeMap::new();
+        result.insert("level".to_string(), level_to_sarif(diag.level).to_json());
+        result.insert("message".to_string(), message(diag.message()));
+
+        if let Some(code) = &diag.code {
+            let rule_id = self.rule_id(code);
+            result.insert("ruleId".to_string(), rule_id.to_json());
+        }
+
+        let mut locations = vec![];
+        let mut related_locations = vec![];
+        for span_label in diag.span.span_labels() {
+            if span_label.is_primary {
+                locations.push(self.location_from_span_label(span_label));
+            } else {
+                related_locations.push(self.location_from_span_label(span_label));
+            }
+        }
+        for child in &diag.children {
+            related_locations.extend(self.locations_from_sub_diagnostic(child));
+        }
+        if !locations.is_empty() {
+            result.insert("locations".to_string(), Json::Array(locations));
+        }
+        if !related_locations.is_empty() {
+            result.insert("relatedLocations".to_string(), Json::Array(related_locations));
+        }
+
+        let fixes: Vec<_> = diag.suggestions.iter().flat_map(|sugg| self.fixes(sugg)).collect();
+        if !fixes.is_empty() {
+            result.insert("fixes".to_string(), Json::Array(fixes));
+        }
+
+        Json::Object(result)
+    }
+
+    /// Registers the rule for `code` and returns its id.
+    fn rule_id(&mut self, code: &DiagnosticId) -> String {
+        let (id, explanation) = match code {
+            DiagnosticId::Error(s) => {
+                let explanation = self
+                    .registry
+                    .as_ref()
+                    .and_then(|registry| registry.try_find_description(s).ok())
+                    .flatten();
+                (s.clone(), explanation)
+            }
+            DiagnosticId::Lint { name, has_future_breakage: _ } => (name.clone(), None),
+        };
+        self.rules.entry(id.clone()).or_insert(explanation);
+        id
+    }
+
+    /// Sub-diagnostics have no SARIF equivalent of their own, so each of them is turned into
+    /// related locations whose messages are prefixed with the sub-diagnostic's level.
+    fn locations_from_sub_diagnostic(&self, diag: &SubDiagnostic) -> Vec<Json> {
+        let text = format!("{}: {}", diag.level.to_str(), diag.message());
+        let span = diag.render_span.as_ref().unwrap_or(&diag.span);
+        let primary_spans = span.primary_spans();
+        if primary_spans.is_empty() {
+            let mut location = BTreeMap::new();
+            location.insert("message".to_string(), message(text));
+            return vec![Json::Object(location)];
+        }
+        primary_spans
+            .iter()
+            .map(|&span| {
+                let span_label = SpanLabel { span, is_primary: true, label: Some(text.clone()) };
+                self.location_from_span_label(span_label)
+            })
+            .collect()
+    }
+
+    fn location_from_span_label(&self, span_label: SpanLabel) -> Json {
+        let mut location = BTreeMap::new();
+        location.insert("physicalLocation".to_string(), self.physical_location(span_label.span));
+        if let Some(label) = span_label.label {
+            location.insert("message".to_string(), message(label));
+        }
+        Json::Object(location)
+    }
+
+    fn physical_location(&self, span: Span) -> Json {
+        let mut physical_location = BTreeMap::new();
+        physical_location.insert("artifactLocation".to_string(), self.artifact_location(span));
+        physical_location.insert("region".to_string(), self.region(span));
+        Json::Object(physical_location)
+    }
+
+    fn artifact_location(&self, span: Span) -> Json {
+        artifact_location(self.file_name(span))
+    }
+
+    fn file_name(&self, span: Span) -> String {
+        self.sm.lookup_source_file(span.lo()).name.prefer_local().to_string()
+    }
+
+    /// Lines are 1-based and columns are 1-based character offsets, matching the
+    /// `unicodeCodePoints` column kind declared on the run.
+    fn region(&self, span: Span) -> Json {
+        let start = self.sm.lookup_char_pos(span.lo());
+        let end = self.sm.lookup_char_pos(span.hi());
+        let byte_start = start.file.original_relative_byte_pos(span.lo()).0;
+        let byte_end = start.file.original_relative_byte_pos(span.hi()).0;
+
+        let mut region = BTreeMap::new();
+        region.insert("startLine".to_string(), start.line.to_json());
+        region.insert("startColumn".to_string(), (start.col.0 + 1).to_json());
+        region.insert("endLine".to_string(), end.line.to_json());
+        region.insert("endColumn".to_string(), (end.col.0 + 1).to_json());
+        region.insert("byteOffset".to_string(), byte_start.to_json());
+        region.insert("byteLength".to_string(), (byte_end - byte_start).to_json());
+        if let Ok(snippet) = self.sm.span_to_snippet(span) {
+            region.insert("snippet".to_string(), message(snippet));
+        }
+        Json::Object(region)
+    }
+
+    /// Each substitution of a suggestion is an alternative way of fixing the diagnostic, so
+    /// each one becomes a separate SARIF `fix`.
+    fn fixes(&self, suggestion: &CodeSuggestion) -> Vec<Json> {
+        suggestion
+            .substitutions
+            .iter()
+            .map(|substitution| {
+                // Replacements have to be grouped by the file they apply to.
+                let mut changes: FxIndexMap<String, Vec<Json>> = Default::default();
+                for part in &substitution.parts {
+                    let mut replacement = BTreeMap::new();
+                    replacement.insert("deletedRegion".to_string(), self.region(part.span));
+                    replacement
+                        .insert("insertedContent".to_string(), message(part.snippet.clone()));
+                    changes
+                        .entry(self.file_name(part.span))
+                        .or_default()
+                        .push(Json::Object(replacement));
+                }
+
+                let artifact_changes = changes
+                    .into_iter()
+                    .map(|(file_name, replacements)| {
+                        let mut change = BTreeMap::new();
+                        change.insert("artifactLocation".to_string(), artifact_location(file_name));
+                        change.insert("replacements".to_string(), Json::Array(replacements));
+                        Json::Object(change)
+                    })
+                    .collect();
+
+                let mut properties = BTreeMap::new();
+                properties.insert(
+                    "applicability".to_string(),
+                    format!("{:?}", suggestion.applicability).to_json(),
+                );
+
+                let mut fix = BTreeMap::new();
+                fix.insert("description".to_string(), message(suggestion.msg.clone()));
+                fix.insert("artifactChanges".to_string(), Json::Array(artifact_changes));
+                fix.insert("properties".to_string(), Json::Object(properties));
+                Json::Object(fix)
+            })
+            .collect()
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: &crate::Diagnostic) {
+        // Failure notes only point the user at `rustc --explain`, which the rules already cover.
+        if diag.level.is_failure_note() {
+            return;
+        }
+        let result = self.result_from_diagnostic(diag);
+        self.results.push(result);
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        Some(&self.sm)
+    }
+
+    fn should_show_explain(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        let mut rules = mem::take(&mut self.rules);
+        let mut results = mem::take(&mut self.results);
+        let dst: &mut dyn Write = match &mut self.dst {
+            Destination::Early => {
+                let mut early_log = early_log();
+                early_log.pretty = self.pretty;
+                for (id, explanation) in rules {
+                    early_log.rules.entry(id).or_insert(explanation);
+                }
+                early_log.results.append(&mut results);
+                return;
+            }
+            Destination::Stderr(dst) => {
+                // Early diagnostics are part of this run, so they go in the same log.
+                let EarlyLog { pretty: _, rules: early_rules, results: early_results } =
+                    mem::take(&mut *early_log());
+                for (id, explanation) in mem::replace(&mut rules, early_rules) {
+                    rules.entry(id).or_insert(explanation);
+                }
+                results = early_results.into_iter().chain(results).collect();
+                dst
+            }
+            Destination::Raw(dst) => dst,
+        };
+        let log = sarif_log(&rules, results);
+        if let Err(e) = write_log(dst, &log, self.pretty) {
+            // Don't turn an ICE into an abort by panicking while unwinding.
+            if !std::thread::panicking() {
+                panic!("failed to print SARIF log: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Creates the SARIF log of a single run of rustc.
+fn sarif_log(rules: &FxIndexMap<String, Option<&'static str>>, results: Vec<Json>) -> Json {
+    let rules = rules
+        .iter()
+        .map(|(id, explanation)| {
+            let mut rule = BTreeMap::new();
+            rule.insert("id".to_string(), id.to_json());
+            if let Some(explanation) = explanation {
+                rule.insert("fullDescription".to_string(), message(explanation.to_string()));
+                rule.insert(
+                    "helpUri".to_string(),
+                    format!("https://doc.rust-lang.org/error-index.html#{}", id).to_json(),
+                );
+            }
+            Json::Object(rule)
+        })
+        .collect();
+
+    let mut driver = BTreeMap::new();
+    driver.insert("name".to_string(), "rustc".to_json());
+    driver.insert("informationUri".to_string(), "https://www.rust-lang.org/".to_json());
+    driver.insert("rules".to_string(), Json::Array(rules));
+
+    let mut tool = BTreeMap::new();
+    tool.insert("driver".to_string(), Json::Object(driver));
+
+    let mut run = BTreeMap::new();
+    run.insert("tool".to_string(), Json::Object(tool));
+    run.insert("columnKind".to_string(), "unicodeCodePoints".to_json());
+    run.insert("results".to_string(), Json::Array(results));
+
+    let mut log = BTreeMap::new();
+    log.insert("$schema".to_string(), SARIF_SCHEMA.to_json());
+    log.insert("version".to_string(), SARIF_VERSION.to_json());
+    log.insert("runs".to_string(), Json::Array(vec![Json::Object(run)]));
+    Json::Object(log)
+}
+
+fn write_log(dst: &mut dyn Write, log: &Json, pretty: bool) -> io::Result<()> {
+    if pretty {
+        writeln!(dst, "{}", log.pretty())?;
+    } else {
+        writeln!(dst, "{}", log)?;
+    }
+    dst.flush()
+}
+
+fn level_to_sarif(level: Level) -> &'static str {
+    match level {
+        Level::Bug | Level::Fatal | Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help => "note",
+        Level::FailureNote | Level::Cancelled | Level::Allow => "none",
+    }
+}
+
+/// Creates a SARIF `artifactLocation` object. URIs always use forward slashes.
+fn artifact_location(file_name: String) -> Json {
+    let mut artifact_location = BTreeMap::new();
+    artifact_location.insert("uri".to_string(), file_name.replace('\\', "/").to_json());
+    Json::Object(artifact_location)
+}
+
+/// Creates a SARIF `message` object.
+fn message(text: String) -> Json {
+    let mut message = BTreeMap::new();
+    message.insert("text".to_string(), Json::String(text));
+    Json::Object(message)
+}
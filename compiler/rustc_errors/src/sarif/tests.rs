@@ -0,0 +1,121 @@
+use super::*;
+
+use crate::sarif::SarifEmitter;
+use rustc_span::source_map::{FilePathMapping, SourceMap};
+
+use crate::Handler;
+use rustc_lint_defs::Applicability;
+use rustc_span::{BytePos, Span};
+
+use std::path::Path;
+use std::str;
+use std::sync::{Arc, Mutex};
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+fn with_default_session_globals(f: impl FnOnce()) {
+    let session_globals = rustc_span::SessionGlobals::new(rustc_span::edition::DEFAULT_EDITION);
+    rustc_span::SESSION_GLOBALS.set(&session_globals, f);
+}
+
+/// Runs `f` against a handler backed by a `SarifEmitter` and returns the emitted log.
+fn emit_sarif(code: &str, f: impl FnOnce(&Handler)) -> Json {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    with_default_session_globals(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+
+        let se = SarifEmitter::new(Box::new(Shared { data: output.clone() }), None, sm, false);
+        let handler = Handler::with_emitter(true, None, Box::new(se));
+        f(&handler);
+    });
+
+    let bytes = output.lock().unwrap();
+    str::from_utf8(&bytes).unwrap().parse().unwrap()
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+#[test]
+fn empty_run() {
+    let log = emit_sarif("", |_| {});
+    assert_eq!(log["version"], Json::String("2.1.0".to_string()));
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], Json::String("rustc".to_string()));
+    assert_eq!(log["runs"][0]["results"], Json::Array(vec![]));
+}
+
+#[test]
+fn error_with_location() {
+    let log = emit_sarif("\nmod foo;\nmod bar;\n", |handler| {
+        handler.span_err(span(5, 12), "foo");
+    });
+
+    let result = &log["runs"][0]["results"][0];
+    assert_eq!(result["level"], Json::String("error".to_string()));
+    assert_eq!(result["message"]["text"], Json::String("foo".to_string()));
+
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], Json::String("test.rs".to_string()));
+    let region = &location["region"];
+    assert_eq!(region["startLine"], Json::U64(2));
+    assert_eq!(region["startColumn"], Json::U64(5));
+    assert_eq!(region["endLine"], Json::U64(3));
+    assert_eq!(region["endColumn"], Json::U64(3));
+    assert_eq!(region["byteOffset"], Json::U64(5));
+    assert_eq!(region["byteLength"], Json::U64(7));
+}
+
+#[test]
+fn code_children_and_suggestion() {
+    let log = emit_sarif("let x = 1;", |handler| {
+        handler
+            .struct_span_warn(span(4, 5), "unused variable: `x`")
+            .code(DiagnosticId::Lint {
+                name: "unused_variables".to_string(),
+                has_future_breakage: false,
+            })
+            .note("`#[warn(unused_variables)]` on by default")
+            .span_suggestion(
+                span(4, 5),
+                "if this is intentional, prefix it with an underscore",
+                "_x".to_string(),
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+
+    let run = &log["runs"][0];
+    assert_eq!(
+        run["tool"]["driver"]["rules"][0]["id"],
+        Json::String("unused_variables".to_string())
+    );
+
+    let result = &run["results"][0];
+    assert_eq!(result["level"], Json::String("warning".to_string()));
+    assert_eq!(result["ruleId"], Json::String("unused_variables".to_string()));
+    assert_eq!(
+        result["relatedLocations"][0]["message"]["text"],
+        Json::String("note: `#[warn(unused_variables)]` on by default".to_string())
+    );
+
+    let change = &result["fixes"][0]["artifactChanges"][0];
+    assert_eq!(change["artifactLocation"]["uri"], Json::String("test.rs".to_string()));
+    let replacement = &change["replacements"][0];
+    assert_eq!(replacement["insertedContent"]["text"], Json::String("_x".to_string()));
+    assert_eq!(replacement["deletedRegion"]["byteOffset"], Json::U64(4));
+    assert_eq!(replacement["deletedRegion"]["byteLength"], Json::U64(1));
+}
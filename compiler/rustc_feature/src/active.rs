@@ -663,6 +663,9 @@ pub fn set(&self, features: &mut Features, span: Span) {
     /// Allows unnamed fields of struct and union type
     (active, unnamed_fields, "1.53.0", Some(49804), None),
 
+    /// Allows a test to set a hard time limit with `#[test_timeout = "..."]`.
+    (active, test_timeout, "1.54.0", None, None),
+
     // -------------------------------------------------------------------------
     // feature-group-end: actual feature gates
     // -------------------------------------------------------------------------
@@ -317,6 +317,7 @@ macro_rules! experimental {
 
     // Testing:
     gated!(allow_fail, Normal, template!(Word), experimental!(allow_fail)),
+    gated!(test_timeout, Normal, template!(NameValueStr: "duration"), experimental!(test_timeout)),
     gated!(
         test_runner, CrateLevel, template!(List: "path"), custom_test_frameworks,
         "custom test frameworks are an unstable feature",
@@ -7,6 +7,7 @@
 use rustc_serialize::opaque::Decoder;
 use rustc_serialize::Decodable;
 use rustc_session::Session;
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use super::data::*;
@@ -19,32 +20,57 @@
 pub enum LoadResult<T> {
     Ok { data: T },
     DataOutOfDate,
+    /// The data is out of date because these tracked command-line options changed.
+    OptionsChanged { changed_options: Vec<String> },
     Error { message: String },
 }
 
 impl LoadResult<(SerializedDepGraph, WorkProductMap)> {
-    pub fn open(self, sess: &Session) -> (SerializedDepGraph, WorkProductMap) {
+    /// Also returns the tracked command-line options that changed since the
+    /// previous session, if that is why its data could not be used.
+    pub fn open(self, sess: &Session) -> (SerializedDepGraph, WorkProductMap, Vec<String>) {
         match self {
             LoadResult::Error { message } => {
                 sess.warn(&message);
                 Default::default()
             }
             LoadResult::DataOutOfDate => {
-                if let Err(err) = delete_all_session_dir_contents(sess) {
-                    sess.err(&format!(
-                        "Failed to delete invalidated or incompatible \
-                                      incremental compilation session directory contents `{}`: {}.",
-                        dep_graph_path(sess).display(),
-                        err
-                    ));
-                }
+                delete_out_of_date_session_dir_contents(sess);
                 Default::default()
             }
-            LoadResult::Ok { data } => data,
+            LoadResult::OptionsChanged { changed_options } => {
+                delete_out_of_date_session_dir_contents(sess);
+                (Default::default(), Default::default(), changed_options)
+            }
+            LoadResult::Ok { data: (graph, work_products) } => (graph, work_products, Vec::new()),
         }
     }
 }
 
+fn delete_out_of_date_session_dir_contents(sess: &Session) {
+    if let Err(err) = delete_all_session_dir_contents(sess) {
+        sess.err(&format!(
+            "Failed to delete invalidated or incompatible \
+                              incremental compilation session directory contents `{}`: {}.",
+            dep_graph_path(sess).display(),
+            err
+        ));
+    }
+}
+
+/// Returns the names of the options whose hash differs between `prev` and `current`,
+/// including the ones only present in either of them.
+fn changed_options(prev: &BTreeMap<String, u64>, current: &BTreeMap<String, u64>) -> Vec<String> {
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|&(name, hash)| prev.get(name) != Some(hash))
+        .map(|(name, _)| name.clone())
+        .collect();
+    changed.extend(prev.keys().filter(|name| !current.contains_key(*name)).cloned());
+    changed.sort();
+    changed
+}
+
 fn load_data(
     report_incremental_info: bool,
     path: &Path,
@@ -103,8 +129,12 @@ pub fn load_dep_graph(sess: &Session) -> DepGraphFuture {
     // Calling `sess.incr_comp_session_dir()` will panic if `sess.opts.incremental.is_none()`.
     // Fortunately, we just checked that this isn't the case.
     let path = dep_graph_path_from(&sess.incr_comp_session_dir());
-    let report_incremental_info = sess.opts.debugging_opts.incremental_info;
+    // Why nothing from the previous session could be reused is part of the
+    // explanation requested with `-Z incremental-explain` as well.
+    let report_incremental_info = sess.opts.debugging_opts.incremental_info
+        || sess.opts.debugging_opts.incremental_explain;
     let expected_hash = sess.opts.dep_tracking_hash(false);
+    let expected_option_hashes = sess.opts.dep_tracking_hashes();
 
     let mut prev_work_products = FxHashMap::default();
     let nightly_build = sess.is_nightly_build();
@@ -136,7 +166,7 @@ pub fn load_dep_graph(sess: &Session) -> DepGraphFuture {
                     if !path.exists() {
                         all_files_exist = false;
 
-                        if sess.opts.debugging_opts.incremental_info {
+                        if report_incremental_info {
                             eprintln!(
                                 "incremental: could not find file for work \
                                     product: {}",
@@ -162,11 +192,16 @@ pub fn load_dep_graph(sess: &Session) -> DepGraphFuture {
 
         match load_data(report_incremental_info, &path, nightly_build) {
             LoadResult::DataOutOfDate => LoadResult::DataOutOfDate,
+            LoadResult::OptionsChanged { changed_options } => {
+                LoadResult::OptionsChanged { changed_options }
+            }
             LoadResult::Error { message } => LoadResult::Error { message },
             LoadResult::Ok { data: (bytes, start_pos) } => {
                 let mut decoder = Decoder::new(&bytes, start_pos);
                 let prev_commandline_args_hash = u64::decode(&mut decoder)
                     .expect("Error reading commandline arg hash from cached dep-graph");
+                let prev_option_hashes = BTreeMap::<String, u64>::decode(&mut decoder)
+                    .expect("Error reading commandline option hashes from cached dep-graph");
 
                 if prev_commandline_args_hash != expected_hash {
                     if report_incremental_info {
@@ -179,7 +214,12 @@ pub fn load_dep_graph(sess: &Session) -> DepGraphFuture {
                     debug!("load_dep_graph_new: differing commandline arg hashes");
 
                     // No need to do any further work
-                    return LoadResult::DataOutOfDate;
+                    return LoadResult::OptionsChanged {
+                        changed_options: changed_options(
+                            &prev_option_hashes,
+                            &expected_option_hashes,
+                        ),
+                    };
                 }
 
                 let dep_graph = SerializedDepGraph::decode(&mut decoder)
@@ -41,6 +41,10 @@ pub fn save_dep_graph(tcx: TyCtxt<'_>) {
             tcx.dep_graph.print_incremental_info()
         }
 
+        if sess.opts.debugging_opts.incremental_explain {
+            tcx.dep_graph.print_invalidation_report()
+        }
+
         join(
             move || {
                 sess.time("incr_comp_persist_result_cache", || {
@@ -188,6 +192,7 @@ pub fn build_dep_graph(
     sess: &Session,
     prev_graph: SerializedDepGraph,
     prev_work_products: FxHashMap<WorkProductId, WorkProduct>,
+    changed_options: Vec<String>,
 ) -> Option<DepGraph> {
     if sess.opts.incremental.is_none() {
         // No incremental compilation.
@@ -228,6 +233,17 @@ pub fn build_dep_graph(
         return None;
     }
 
+    // Then the hash of each tracked option, so that the next session can tell
+    // which of them changed if the hash above differs.
+    if let Err(err) = sess.opts.dep_tracking_hashes().encode(&mut encoder) {
+        sess.err(&format!(
+            "failed to write dependency graph option hashes `{}`: {}",
+            path_buf.display(),
+            err
+        ));
+        return None;
+    }
+
     Some(DepGraph::new(
         &sess.prof,
         prev_graph,
@@ -235,5 +251,7 @@ pub fn build_dep_graph(
         encoder,
         sess.opts.debugging_opts.query_dep_graph,
         sess.opts.debugging_opts.incremental_info,
+        sess.opts.debugging_opts.incremental_explain,
+        changed_options,
     ))
 }
@@ -200,6 +200,12 @@ pub fn create_compiler_and_run<R>(config: Config, f: impl FnOnce(&Compiler) -> R
     };
 
     rustc_span::with_source_map(compiler.sess.parse_sess.clone_source_map(), move || {
+        let prof = compiler.sess.prof.clone();
+        let incremental = compiler.sess.opts.incremental.is_some();
+        // The self-profile report is printed last, and also when the session
+        // is aborted due to errors.
+        let _print_report = OnDrop(|| prof.print_report(incremental));
+
         let r = {
             let _sess_abort_error = OnDrop(|| {
                 compiler.sess.finish_diagnostics(registry);
@@ -208,7 +214,6 @@ pub fn create_compiler_and_run<R>(config: Config, f: impl FnOnce(&Compiler) -> R
             f(&compiler)
         };
 
-        let prof = compiler.sess.prof.clone();
         prof.generic_activity("drop_compiler").run(move || drop(compiler));
         r
     })
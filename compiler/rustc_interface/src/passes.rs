@@ -827,6 +827,7 @@ fn analysis(tcx: TyCtxt<'_>, (): ()) -> Result<()> {
                     .downcast_ref::<CStore>()
                     .expect("`tcx.cstore` is not a `CStore`");
                 cstore.report_unused_deps(tcx);
+                cstore.print_crate_graph(tcx);
             },
             {
                 par_iter(&tcx.hir().krate().modules).for_each(|(&module, _)| {
@@ -6,6 +6,7 @@
 use rustc_data_structures::steal::Steal;
 use rustc_data_structures::svh::Svh;
 use rustc_data_structures::sync::{Lrc, OnceCell, WorkerLocal};
+use rustc_data_structures::OnDrop;
 use rustc_errors::ErrorReported;
 use rustc_hir::def_id::LOCAL_CRATE;
 use rustc_hir::Crate;
@@ -198,7 +199,7 @@ pub fn dep_graph(&self) -> Result<&Query<DepGraph>> {
             Ok(match self.dep_graph_future()?.take() {
                 None => DepGraph::new_disabled(),
                 Some(future) => {
-                    let (prev_graph, prev_work_products) =
+                    let (prev_graph, prev_work_products, changed_options) =
                         self.session().time("blocked_on_dep_graph_loading", || {
                             future
                                 .open()
@@ -212,6 +213,7 @@ pub fn dep_graph(&self) -> Result<&Query<DepGraph>> {
                         self.session(),
                         prev_graph,
                         prev_work_products,
+                        changed_options,
                     )
                     .unwrap_or_else(DepGraph::new_disabled)
                 }
@@ -428,7 +430,19 @@ pub fn enter<F, T>(&self, f: F) -> T
     {
         let mut _timer = None;
         let queries = Queries::new(&self);
-        let ret = f(&queries);
+        let ret = {
+            // The self-profile report is also printed when the session is
+            // aborted due to errors, and it needs the query names.
+            let _alloc_query_strings_on_abort = OnDrop(|| {
+                if std::thread::panicking() && self.session().prof.report_enabled() {
+                    if let Some(Ok(gcx)) = &mut *queries.global_ctxt.result.borrow_mut() {
+                        gcx.enter(rustc_query_impl::alloc_self_profile_query_strings);
+                    }
+                }
+            });
+
+            f(&queries)
+        };
 
         // NOTE: intentionally does not compute the global context if it hasn't been built yet,
         // since that likely means there was a parse error.
@@ -1,6 +1,7 @@
 use crate::interface::parse_cfgspecs;
 
 use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::profiling::ReportFormat;
 use rustc_errors::{emitter::HumanReadableErrorType, registry, ColorConfig};
 use rustc_session::config::InstrumentCoverage;
 use rustc_session::config::Strip;
@@ -13,6 +14,7 @@
 use rustc_session::lint::Level;
 use rustc_session::search_paths::SearchPath;
 use rustc_session::utils::{CanonicalizedPath, NativeLib, NativeLibKind};
+use rustc_session::TypeSizesFormat;
 use rustc_session::{build_session, getopts, DiagnosticOutput, Session};
 use rustc_span::edition::{Edition, DEFAULT_EDITION};
 use rustc_span::symbol::sym;
@@ -83,6 +85,7 @@ fn assert_same_clone(x: &Options) {
 fn assert_same_hash(x: &Options, y: &Options) {
     assert_eq!(x.dep_tracking_hash(true), y.dep_tracking_hash(true));
     assert_eq!(x.dep_tracking_hash(false), y.dep_tracking_hash(false));
+    assert_eq!(x.dep_tracking_hashes(), y.dep_tracking_hashes());
     // Check clone
     assert_same_clone(x);
     assert_same_clone(y);
@@ -91,6 +94,7 @@ fn assert_same_hash(x: &Options, y: &Options) {
 fn assert_different_hash(x: &Options, y: &Options) {
     assert_ne!(x.dep_tracking_hash(true), y.dep_tracking_hash(true));
     assert_ne!(x.dep_tracking_hash(false), y.dep_tracking_hash(false));
+    assert_ne!(x.dep_tracking_hashes(), y.dep_tracking_hashes());
     // Check clone
     assert_same_clone(x);
     assert_same_clone(y);
@@ -627,6 +631,7 @@ macro_rules! untracked {
     untracked!(emit_stack_sizes, true);
     untracked!(hir_stats, true);
     untracked!(identify_regions, true);
+    untracked!(incremental_explain, true);
     untracked!(incremental_ignore_spans, true);
     untracked!(incremental_info, true);
     untracked!(incremental_verify_ich, true);
@@ -649,13 +654,14 @@ macro_rules! untracked {
     untracked!(print_link_args, true);
     untracked!(print_llvm_passes, true);
     untracked!(print_mono_items, Some(String::from("abc")));
-    untracked!(print_type_sizes, true);
+    untracked!(print_type_sizes, Some(TypeSizesFormat::Json));
     untracked!(proc_macro_backtrace, true);
     untracked!(query_dep_graph, true);
     untracked!(query_stats, true);
     untracked!(save_analysis, true);
     untracked!(self_profile, SwitchWithOptPath::Enabled(None));
     untracked!(self_profile_events, Some(vec![String::new()]));
+    untracked!(self_profile_report, Some(ReportFormat::Json));
     untracked!(span_debug, true);
     untracked!(span_free_formats, true);
     untracked!(strip, Strip::Debuginfo);
@@ -710,6 +716,7 @@ macro_rules! tracked {
     tracked!(merge_functions, Some(MergeFunctions::Disabled));
     tracked!(mir_emit_retag, true);
     tracked!(mir_opt_level, Some(4));
+    tracked!(mir_sroa, Some(false));
     tracked!(mutable_noalias, Some(true));
     tracked!(new_llvm_pass_manager, Some(true));
     tracked!(no_codegen, true);
@@ -735,6 +742,7 @@ macro_rules! tracked {
     tracked!(share_generics, Some(true));
     tracked!(show_span, Some(String::from("abc")));
     tracked!(src_hash_algorithm, Some(SourceFileHashAlgorithm::Sha1));
+    tracked!(stack_usage_report, Some(PathBuf::from("stack-usage.json")));
     tracked!(symbol_mangling_version, Some(SymbolManglingVersion::V0));
     tracked!(teach, true);
     tracked!(thinlto, Some(true));
@@ -10,6 +10,7 @@ using namespace llvm;
 
 struct LLVMRustCounterMappingRegion {
   coverage::Counter Count;
+  coverage::Counter FalseCount;
   uint32_t FileID;
   uint32_t ExpandedFileID;
   uint32_t LineStart;
@@ -52,10 +53,19 @@ extern "C" void LLVMRustCoverageWriteMappingToBuffer(
   SmallVector<coverage::CounterMappingRegion, 0> MappingRegions;
   MappingRegions.reserve(NumMappingRegions);
   for (const auto &Region : makeArrayRef(RustMappingRegions, NumMappingRegions)) {
+#if LLVM_VERSION_GE(12, 0)
+    MappingRegions.emplace_back(
+        Region.Count, Region.FalseCount, Region.FileID, Region.ExpandedFileID,
+        Region.LineStart, Region.ColumnStart, Region.LineEnd, Region.ColumnEnd,
+        Region.Kind);
+#else
+    // Branch regions are not supported before LLVM 12; `-Z instrument-coverage=branch` is
+    // rejected on the Rust side, so `FalseCount` is never set here.
     MappingRegions.emplace_back(
         Region.Count, Region.FileID, Region.ExpandedFileID,
         Region.LineStart, Region.ColumnStart, Region.LineEnd, Region.ColumnEnd,
         Region.Kind);
+#endif
   }
   auto CoverageMappingWriter = coverage::CoverageMappingWriter(
       makeArrayRef(VirtualFileMappingIDs, NumVirtualFileMappingIDs),
@@ -1,7 +1,7 @@
 //! Validates all used crates and extern libraries and loads their metadata
 
 use crate::dynamic_lib::DynamicLibrary;
-use crate::locator::{CrateError, CrateLocator, CratePaths};
+use crate::locator::{CrateError, CrateFlavor, CrateLocator, CratePaths};
 use crate::rmeta::{CrateDep, CrateMetadata, CrateNumMap, CrateRoot, MetadataBlob};
 
 use rustc_ast::expand::allocator::AllocatorKind;
@@ -15,9 +15,10 @@
 use rustc_index::vec::IndexVec;
 use rustc_middle::middle::cstore::{CrateDepKind, CrateSource, ExternCrate};
 use rustc_middle::middle::cstore::{ExternCrateSource, MetadataLoaderDyn};
+use rustc_middle::middle::dependency_format::{Dependencies, Linkage};
 use rustc_middle::ty::TyCtxt;
-use rustc_serialize::json::ToJson;
-use rustc_session::config::{self, CrateType, ExternLocation};
+use rustc_serialize::json::{Json, ToJson};
+use rustc_session::config::{self, CrateType, ExternLocation, PrintRequest};
 use rustc_session::lint::{self, BuiltinLintDiagnostics, ExternDepSpec};
 use rustc_session::output::validate_crate_name;
 use rustc_session::search_paths::PathKind;
@@ -29,7 +30,7 @@
 
 use proc_macro::bridge::client::ProcMacro;
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{cmp, env};
 use tracing::{debug, info};
 
@@ -69,6 +70,8 @@ pub enum LoadedMacro {
 crate struct Library {
     pub source: CrateSource,
     pub metadata: MetadataBlob,
+    /// The kind of file in `source` that `metadata` was read from.
+    pub metadata_flavor: CrateFlavor,
 }
 
 enum LoadResult {
@@ -194,6 +197,88 @@ fn crate_dependencies_in_reverse_postorder(&self, cnum: CrateNum) -> Vec<CrateNu
         self.has_global_allocator
     }
 
+    /// Describes all loaded crates and the dependencies between them, for
+    /// `--print crate-graph`, along with the crate that failed to load if any.
+    /// `dependency_formats` is the way each crate is linked into each crate type
+    /// being built, which is only known once all crates loaded successfully.
+    fn crate_graph_json(
+        &self,
+        local_crate_name: Symbol,
+        dependency_formats: Option<&Dependencies>,
+        failed_load: Option<Json>,
+    ) -> Json {
+        let path_json = |path: &Option<(PathBuf, PathKind)>| {
+            path.as_ref().map(|(path, _)| path.display().to_string()).to_json()
+        };
+        let mut crates = Vec::new();
+        self.iter_crate_data(|cnum, data| {
+            let CrateSource { dylib, rlib, rmeta } = data.source();
+            let dependencies =
+                data.dependencies().iter().map(|cnum| cnum.as_u32().to_json()).collect();
+
+            let mut source = BTreeMap::new();
+            source.insert("dylib".to_string(), path_json(dylib));
+            source.insert("rlib".to_string(), path_json(rlib));
+            source.insert("rmeta".to_string(), path_json(rmeta));
+
+            let mut krate = BTreeMap::new();
+            krate.insert("name".to_string(), data.name().to_string().to_json());
+            krate.insert("cnum".to_string(), cnum.as_u32().to_json());
+            krate.insert("hash".to_string(), data.hash().to_string().to_json());
+            krate.insert("dep_kind".to_string(), format!("{:?}", data.dep_kind()).to_json());
+            krate.insert(
+                "metadata_flavor".to_string(),
+                data.metadata_flavor().to_string().to_json(),
+            );
+            krate.insert("source".to_string(), Json::Object(source));
+            krate.insert("dependencies".to_string(), Json::Array(dependencies));
+            if let Some(dependency_formats) = dependency_formats {
+                let linkage = dependency_formats
+                    .iter()
+                    .map(|(crate_type, list)| {
+                        // The list is empty for crate types that don't link anything.
+                        let linkage =
+                            list.get(cnum.as_usize() - 1).copied().unwrap_or(Linkage::NotLinked);
+                        let (flavor, path) = match linkage {
+                            Linkage::Static => (Some(CrateFlavor::Rlib), rlib),
+                            Linkage::Dynamic => (Some(CrateFlavor::Dylib), dylib),
+                            Linkage::NotLinked | Linkage::IncludedFromDylib => (None, &None),
+                        };
+                        let mut entry = BTreeMap::new();
+                        entry.insert("linkage".to_string(), format!("{:?}", linkage).to_json());
+                        entry.insert(
+                            "flavor".to_string(),
+                            flavor.map(|flavor| flavor.to_string()).to_json(),
+                        );
+                        entry.insert("path".to_string(), path_json(path));
+                        (crate_type.to_string(), Json::Object(entry))
+                    })
+                    .collect();
+                krate.insert("linkage".to_string(), Json::Object(linkage));
+            }
+            crates.push(Json::Object(krate));
+        });
+
+        let mut graph = BTreeMap::new();
+        graph.insert("local_crate".to_string(), local_crate_name.to_string().to_json());
+        graph.insert("crates".to_string(), Json::Array(crates));
+        if let Some(failed_load) = failed_load {
+            graph.insert("failed_load".to_string(), failed_load);
+        }
+        Json::Object(graph)
+    }
+
+    /// Prints the crate graph if `--print crate-graph` was requested, along with
+    /// the linkage chosen for each crate.
+    pub fn print_crate_graph(&self, tcx: TyCtxt<'_>) {
+        if tcx.sess.opts.prints.contains(&PrintRequest::CrateGraph) {
+            let dependency_formats = tcx.dependency_formats(());
+            let local_crate_name = tcx.crate_name(LOCAL_CRATE);
+            let graph = self.crate_graph_json(local_crate_name, Some(&dependency_formats), None);
+            println!("{}", graph.pretty());
+        }
+    }
+
     pub fn report_unused_deps(&self, tcx: TyCtxt<'_>) {
         // We put the check for the option before the lint_level_at_node call
         // because the call mutates internal state and introducing it
@@ -372,7 +457,7 @@ fn register_crate(
     ) -> Result<CrateNum, CrateError> {
         let _prof_timer = self.sess.prof.generic_activity("metadata_register_crate");
 
-        let Library { source, metadata } = lib;
+        let Library { source, metadata, metadata_flavor } = lib;
         let crate_root = metadata.get_root();
         let host_hash = host_lib.as_ref().map(|lib| lib.metadata.get_root().hash());
 
@@ -433,6 +518,7 @@ fn register_crate(
             cnum_map,
             dep_kind,
             source,
+            metadata_flavor,
             private_dep,
             host_hash,
         );
@@ -514,6 +600,7 @@ fn resolve_crate<'b>(
         self.maybe_resolve_crate(name, dep_kind, dep).unwrap_or_else(|err| {
             let missing_core =
                 self.maybe_resolve_crate(sym::core, CrateDepKind::Explicit, None).is_err();
+            self.print_failed_crate_graph(err.failed_load_json(name));
             err.report(&self.sess, span, missing_core)
         })
     }
@@ -980,6 +1067,17 @@ pub fn postprocess(&mut self, krate: &ast::Crate) {
         info!("{:?}", CrateDump(&self.cstore));
     }
 
+    /// Prints the crate graph if `--print crate-graph` was requested and loading a crate
+    /// failed, so that the candidates can be inspected. Otherwise the graph is printed by
+    /// `CStore::print_crate_graph` once the linkage of the crates is known.
+    fn print_failed_crate_graph(&self, failed_load: Json) {
+        if self.sess.opts.prints.contains(&PrintRequest::CrateGraph) {
+            let graph =
+                self.cstore.crate_graph_json(self.local_crate_name, None, Some(failed_load));
+            println!("{}", graph.pretty());
+        }
+    }
+
     pub fn process_extern_crate(
         &mut self,
         item: &ast::Item,
@@ -222,6 +222,7 @@
 use rustc_data_structures::sync::MetadataRef;
 use rustc_errors::struct_span_err;
 use rustc_middle::middle::cstore::{CrateSource, MetadataLoader};
+use rustc_serialize::json::{Json, ToJson};
 use rustc_session::config::{self, CrateType};
 use rustc_session::filesearch::{FileDoesntMatch, FileMatches, FileSearch};
 use rustc_session::search_paths::PathKind;
@@ -232,6 +233,7 @@
 use rustc_target::spec::{Target, TargetTriple};
 
 use snap::read::FrameDecoder;
+use std::collections::BTreeMap;
 use std::io::{Read, Result as IoResult, Write};
 use std::path::{Path, PathBuf};
 use std::{cmp, fmt, fs};
@@ -476,7 +478,9 @@ fn extract_lib(
             rlib: self.extract_one(rlibs, CrateFlavor::Rlib, &mut slot)?,
             dylib: self.extract_one(dylibs, CrateFlavor::Dylib, &mut slot)?,
         };
-        Ok(slot.map(|(svh, metadata)| (svh, Library { source, metadata })))
+        Ok(slot.map(|(svh, metadata, metadata_flavor)| {
+            (svh, Library { source, metadata, metadata_flavor })
+        }))
     }
 
     fn needs_crate_flavor(&self, flavor: CrateFlavor) -> bool {
@@ -511,7 +515,7 @@ fn extract_one(
         &mut self,
         m: FxHashMap<PathBuf, PathKind>,
         flavor: CrateFlavor,
-        slot: &mut Option<(Svh, MetadataBlob)>,
+        slot: &mut Option<(Svh, MetadataBlob, CrateFlavor)>,
     ) -> Result<Option<(PathBuf, PathKind)>, CrateError> {
         // If we are producing an rlib, and we've already loaded metadata, then
         // we should not attempt to discover further crate sources (unless we're
@@ -597,7 +601,7 @@ fn extract_one(
                     continue;
                 }
             }
-            *slot = Some((hash, metadata));
+            *slot = Some((hash, metadata, flavor));
             ret = Some((lib, kind));
         }
 
@@ -884,6 +888,67 @@ struct CrateMismatch {
 }
 
 impl CrateError {
+    /// Describes the crate that failed to load and the candidate files that were considered for
+    /// it, for `--print crate-graph`. `requested` is the crate that was being resolved, which is
+    /// only used for errors that don't name a crate themselves.
+    crate fn failed_load_json(&self, requested: Symbol) -> Json {
+        fn candidate(path: &Path, rejected: Option<&str>, got: Option<String>) -> Json {
+            let mut candidate = BTreeMap::new();
+            candidate.insert("path".to_string(), path.display().to_string().to_json());
+            candidate.insert("rejected".to_string(), rejected.map(str::to_string).to_json());
+            candidate.insert("got".to_string(), got.to_json());
+            Json::Object(candidate)
+        }
+
+        let mut crate_name = requested;
+        let mut candidates = Vec::new();
+        match self {
+            CrateError::MultipleCandidates(name, _, paths) => {
+                crate_name = *name;
+                candidates.extend(paths.iter().map(|path| candidate(path, None, None)));
+            }
+            CrateError::MultipleMatchingCrates(name, libraries) => {
+                crate_name = *name;
+                for (svh, lib) in libraries {
+                    let CrateSource { dylib, rlib, rmeta } = &lib.source;
+                    for (path, _) in dylib.iter().chain(rlib).chain(rmeta) {
+                        candidates.push(candidate(path, None, Some(svh.to_string())));
+                    }
+                }
+            }
+            CrateError::LocatorCombined(locator) => {
+                crate_name = locator.crate_name;
+                let rejections = [
+                    ("hash", &locator.rejected_via_hash),
+                    ("triple", &locator.rejected_via_triple),
+                    ("kind", &locator.rejected_via_kind),
+                    ("version", &locator.rejected_via_version),
+                    ("filename", &locator.rejected_via_filename),
+                ];
+                for &(reason, mismatches) in &rejections {
+                    for CrateMismatch { path, got } in mismatches {
+                        let got = if got.is_empty() { None } else { Some(got.clone()) };
+                        candidates.push(candidate(path, Some(reason), got));
+                    }
+                }
+            }
+            CrateError::NonAsciiName(name)
+            | CrateError::ExternLocationNotExist(name, _)
+            | CrateError::ExternLocationNotFile(name, _)
+            | CrateError::NonDylibPlugin(name) => crate_name = *name,
+            CrateError::SymbolConflictsCurrent(_)
+            | CrateError::SymbolConflictsOthers(_)
+            | CrateError::StableCrateIdCollision(..)
+            | CrateError::DlOpen(_)
+            | CrateError::DlSym(_) => {}
+        }
+
+        let mut failed_load = BTreeMap::new();
+        failed_load.insert("name".to_string(), crate_name.to_string().to_json());
+        failed_load.insert("candidates".to_string(), Json::Array(candidates));
+        Json::Object(failed_load)
+    }
+
     crate fn report(self, sess: &Session, span: Span, missing_core: bool) -> ! {
         let mut err = match self {
             CrateError::NonAsciiName(crate_name) => sess.struct_span_err(
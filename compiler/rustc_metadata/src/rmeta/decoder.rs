@@ -1,6 +1,7 @@
 // Decoding metadata from a single crate's metadata
 
 use crate::creader::CrateMetadataRef;
+use crate::locator::CrateFlavor;
 use crate::rmeta::table::{FixedSizeEncoding, Table};
 use crate::rmeta::*;
 
@@ -99,6 +100,8 @@
     dep_kind: Lock<CrateDepKind>,
     /// Filesystem location of this crate.
     source: CrateSource,
+    /// The kind of file in `source` the metadata was read from.
+    metadata_flavor: CrateFlavor,
     /// Whether or not this crate should be consider a private dependency
     /// for purposes of the 'exported_private_dependencies' lint
     private_dep: bool,
@@ -1201,6 +1204,14 @@ fn get_unused_generic_params(&self, id: DefIndex) -> FiniteBitSet<u32> {
             .unwrap_or_default()
     }
 
+    fn cross_crate_inlinable(&self, id: DefIndex) -> bool {
+        self.root
+            .tables
+            .cross_crate_inlinable
+            .get(self, id)
+            .map_or(false, |inlinable| inlinable.decode(self))
+    }
+
     fn get_promoted_mir(&self, tcx: TyCtxt<'tcx>, id: DefIndex) -> IndexVec<Promoted, Body<'tcx>> {
         self.root
             .tables
@@ -1829,6 +1840,7 @@ impl CrateMetadata {
         cnum_map: CrateNumMap,
         dep_kind: CrateDepKind,
         source: CrateSource,
+        metadata_flavor: CrateFlavor,
         private_dep: bool,
         host_hash: Option<Svh>,
     ) -> CrateMetadata {
@@ -1853,6 +1865,7 @@ impl CrateMetadata {
             dependencies,
             dep_kind: Lock::new(dep_kind),
             source,
+            metadata_flavor,
             private_dep,
             host_hash,
             extern_crate: Lock::new(None),
@@ -1883,6 +1896,10 @@ impl CrateMetadata {
         &self.source
     }
 
+    crate fn metadata_flavor(&self) -> CrateFlavor {
+        self.metadata_flavor
+    }
+
     crate fn dep_kind(&self) -> CrateDepKind {
         *self.dep_kind.lock()
     }
@@ -833,10 +833,13 @@ fn should_encode_mir(tcx: TyCtxt<'_>, def_id: LocalDefId) -> (bool, bool) {
             let needs_inline = (generics.requires_monomorphization(tcx)
                 || tcx.codegen_fn_attrs(def_id).requests_inline())
                 && tcx.sess.opts.output_types.should_codegen();
+            // Tiny leaf functions are inlinable across crates even without `#[inline]`.
+            let cross_crate_inlinable =
+                !needs_inline && tcx.cross_crate_inlinable(def_id.to_def_id());
             // Only check the presence of the `const` modifier.
             let is_const_fn = tcx.is_const_fn_raw(def_id.to_def_id());
             let always_encode_mir = tcx.sess.opts.debugging_opts.always_encode_mir;
-            (is_const_fn, needs_inline || always_encode_mir)
+            (is_const_fn, needs_inline || cross_crate_inlinable || always_encode_mir)
         }
         // Closures can't be const fn.
         DefKind::Closure => {
@@ -1280,6 +1283,9 @@ fn encode_mir(&mut self) {
             debug!("EntryBuilder::encode_mir({:?})", def_id);
             if encode_opt {
                 record!(self.tables.mir[def_id.to_def_id()] <- self.tcx.optimized_mir(def_id));
+                if self.tcx.cross_crate_inlinable(def_id.to_def_id()) {
+                    record!(self.tables.cross_crate_inlinable[def_id.to_def_id()] <- true);
+                }
             }
             if encode_const {
                 record!(self.tables.mir_for_ctfe[def_id.to_def_id()] <- self.tcx.mir_for_ctfe(def_id));
@@ -308,6 +308,8 @@ fn encode(&self, buf: &mut Encoder) -> LazyTables<'tcx> {
     mir_abstract_consts: Table<DefIndex, Lazy!(&'tcx [mir::abstract_const::Node<'tcx>])>,
     const_defaults: Table<DefIndex, Lazy<rustc_middle::ty::Const<'tcx>>>,
     unused_generic_params: Table<DefIndex, Lazy<FiniteBitSet<u32>>>,
+    // As an optimization, a missing entry indicates `false`.
+    cross_crate_inlinable: Table<DefIndex, Lazy<bool>>,
     // `def_keys` and `def_path_hashes` represent a lazy version of a
     // `DefPathTable`. This allows us to avoid deserializing an entire
     // `DefPathTable` up front, since we may only ever use a few
@@ -113,6 +113,13 @@ pub enum CoverageKind {
         rhs: ExpressionOperandId,
     },
     Unreachable,
+    /// The two outcomes of a branch, for `-Z instrument-coverage=branch`. The `code_region` of a
+    /// `Branch` is the source of the branch condition (or matched value), and the operands count
+    /// how often the branch was taken (`true_operand`) or not taken (`false_operand`).
+    Branch {
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+    },
 }
 
 impl CoverageKind {
@@ -122,6 +129,7 @@ pub fn as_operand_id(&self) -> ExpressionOperandId {
             Counter { id, .. } => ExpressionOperandId::from(id),
             Expression { id, .. } => ExpressionOperandId::from(id),
             Unreachable => bug!("Unreachable coverage cannot be part of an expression"),
+            Branch { .. } => bug!("Branch coverage cannot be part of an expression"),
         }
     }
 
@@ -144,6 +152,12 @@ fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
                 rhs.index(),
             ),
             Unreachable => write!(fmt, "Unreachable"),
+            Branch { true_operand, false_operand } => write!(
+                fmt,
+                "Branch(true: {}, false: {})",
+                true_operand.index(),
+                false_operand.index(),
+            ),
         }
     }
 }
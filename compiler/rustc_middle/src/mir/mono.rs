@@ -266,6 +266,16 @@ pub enum Visibility {
     Protected,
 }
 
+/// A call made by the code generated for a function, see the
+/// `mono_item_call_sites` query.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, HashStable)]
+pub enum CallSite<'tcx> {
+    /// A call to a function that is known at compile time.
+    Direct(Instance<'tcx>),
+    /// A call through a function pointer or a vtable.
+    Dynamic,
+}
+
 impl<'tcx> CodegenUnit<'tcx> {
     #[inline]
     pub fn new(name: Symbol) -> CodegenUnit<'tcx> {
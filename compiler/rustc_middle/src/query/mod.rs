@@ -785,6 +785,15 @@
         }
     }
 
+    /// Whether the optimized MIR of a function that is neither generic nor `#[inline]` is small
+    /// enough to be encoded in the crate metadata for cross-crate inlining.
+    query cross_crate_inlinable(key: DefId) -> bool {
+        desc { |tcx|
+            "computing whether `{}` is inlinable across crates",
+            tcx.def_path_str(key),
+        }
+    }
+
     /// Obtain all the calls into other local functions
     query mir_inliner_callees(key: ty::InstanceDef<'tcx>) -> &'tcx [(DefId, SubstsRef<'tcx>)] {
         fatal_cycle
@@ -1483,6 +1492,12 @@
     query codegen_unit(_: Symbol) -> &'tcx CodegenUnit<'tcx> {
         desc { "codegen_unit" }
     }
+
+    /// The calls made by the code generated for the given function, each
+    /// callee listed once. Used for `-Z stack-usage-report`.
+    query mono_item_call_sites(key: ty::Instance<'tcx>) -> &'tcx [CallSite<'tcx>] {
+        desc { "collecting the calls made by `{}`", key }
+    }
     query unused_generic_params(key: DefId) -> FiniteBitSet<u32> {
         cache_on_disk_if { key.is_local() }
         desc {
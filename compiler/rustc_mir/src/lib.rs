@@ -52,6 +52,7 @@ pub fn provide(providers: &mut Providers) {
     const_eval::provide(providers);
     shim::provide(providers);
     transform::provide(providers);
+    monomorphize::collector::provide(providers);
     monomorphize::partitioning::provide(providers);
     monomorphize::polymorphize::provide(providers);
     providers.eval_to_const_value_raw = const_eval::eval_to_const_value_raw_provider;
@@ -59,6 +60,7 @@ pub fn provide(providers: &mut Providers) {
     providers.const_caller_location = const_eval::const_caller_location;
     providers.mir_callgraph_reachable = transform::inline::cycle::mir_callgraph_reachable;
     providers.mir_inliner_callees = transform::inline::cycle::mir_inliner_callees;
+    providers.cross_crate_inlinable = transform::inline::cross_crate::cross_crate_inlinable;
     providers.destructure_const = |tcx, param_env_and_value| {
         let (param_env, value) = param_env_and_value.into_parts();
         const_eval::destructure_const(tcx, param_env, value)
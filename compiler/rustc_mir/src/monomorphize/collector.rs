@@ -180,7 +180,7 @@
 
 use crate::monomorphize;
 
-use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexSet};
 use rustc_data_structures::sync::{par_iter, MTLock, MTRef, ParallelIterator};
 use rustc_errors::{ErrorReported, FatalError};
 use rustc_hir as hir;
@@ -190,11 +190,12 @@
 use rustc_index::bit_set::GrowableBitSet;
 use rustc_middle::mir::interpret::{AllocId, ConstValue};
 use rustc_middle::mir::interpret::{ErrorHandled, GlobalAlloc, Scalar};
-use rustc_middle::mir::mono::{InstantiationMode, MonoItem};
+use rustc_middle::mir::mono::{CallSite, InstantiationMode, MonoItem};
 use rustc_middle::mir::visit::Visitor as MirVisitor;
 use rustc_middle::mir::{self, Local, Location};
 use rustc_middle::ty::adjustment::{CustomCoerceUnsized, PointerCast};
 use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::subst::{GenericArgKind, InternalSubsts};
 use rustc_middle::ty::{self, GenericParamDefKind, Instance, Ty, TyCtxt, TypeFoldable};
 use rustc_middle::{middle::codegen_fn_attrs::CodegenFnAttrFlags, mir::visit::TyContext};
@@ -1393,3 +1394,57 @@ fn collect_const_value<'tcx>(
         _ => {}
     }
 }
+
+/// Finds the calls made by the code generated for `instance`, resolving the
+/// callees like `MirNeighborCollector` does. Calls of intrinsics and of no-op
+/// drop glue are left out, as they don't result in a call.
+fn mono_item_call_sites<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instance: Instance<'tcx>,
+) -> &'tcx [CallSite<'tcx>] {
+    let body = tcx.instance_mir(instance.def);
+    let monomorphize = |ty: Ty<'tcx>| {
+        instance.subst_mir_and_normalize_erasing_regions(tcx, ty::ParamEnv::reveal_all(), ty)
+    };
+
+    let mut call_sites = FxIndexSet::default();
+    for block in body.basic_blocks() {
+        let call_site = match block.terminator().kind {
+            mir::TerminatorKind::Call { ref func, .. } => {
+                match *monomorphize(func.ty(body, tcx)).kind() {
+                    ty::FnDef(def_id, substs) => {
+                        let callee =
+                            ty::Instance::resolve(tcx, ty::ParamEnv::reveal_all(), def_id, substs)
+                                .unwrap()
+                                .unwrap();
+                        match callee.def {
+                            ty::InstanceDef::Intrinsic(_) => continue,
+                            ty::InstanceDef::Virtual(..) => CallSite::Dynamic,
+                            _ => CallSite::Direct(callee),
+                        }
+                    }
+                    _ => CallSite::Dynamic,
+                }
+            }
+            mir::TerminatorKind::Drop { ref place, .. }
+            | mir::TerminatorKind::DropAndReplace { ref place, .. } => {
+                let ty = monomorphize(place.ty(body, tcx).ty);
+                let callee = Instance::resolve_drop_in_place(tcx, ty);
+                match callee.def {
+                    ty::InstanceDef::DropGlue(_, None) => continue,
+                    // Trait objects are dropped through their vtable.
+                    _ if ty.is_trait() => CallSite::Dynamic,
+                    _ => CallSite::Direct(callee),
+                }
+            }
+            _ => continue,
+        };
+        call_sites.insert(call_site);
+    }
+
+    tcx.arena.alloc_from_iter(call_sites)
+}
+
+pub fn provide(providers: &mut Providers) {
+    providers.mono_item_call_sites = mono_item_call_sites;
+}
@@ -0,0 +1,348 @@
+//! A partitioning strategy for incremental compilation that tries to keep the
+//! number of codegen units invalidated by an edit low, by placing mono items
+//! that are invalidated together into the same codegen units.
+//!
+//! Which items are invalidated together is taken from the dep-graph of the
+//! previous incremental session: the inputs of a function are the HIR bodies
+//! its optimized MIR was built from, which includes the bodies of all the
+//! functions that were inlined into it. Edits to function bodies are by far the
+//! most common kind of edit, so we only look at those.
+//!
+//! Within each codegen unit of the default partitioning, the functions that
+//! inlined some of the same bodies from elsewhere are grouped together, and the
+//! functions that were only built from their own body form one more group. So
+//! an edit to a function only invalidates the group it is in and the groups of
+//! the functions that inlined it, while the other items of its module are
+//! reused. When merging, the smallest codegen unit is merged into the one it
+//! shares the largest fraction of inputs with.
+//!
+//! Mono items without inputs, like statics, shims and items that did not exist
+//! in the previous session, stay where the default partitioning places them.
+//! In particular, this strategy behaves like the default one for the first
+//! session of an incremental build.
+
+use std::cmp::{self, Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::mem;
+
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::dep_graph::{DepKind, DepNode, DepNodeExt};
+use rustc_middle::mir::mono::{CodegenUnit, MonoItem};
+use rustc_middle::ty::{Instance, InstanceDef, TyCtxt};
+use rustc_span::symbol::{Symbol, SymbolStr};
+
+use super::PartitioningCx;
+use crate::monomorphize::partitioning::default::DefaultPartitioning;
+use crate::monomorphize::partitioning::merging;
+use crate::monomorphize::partitioning::{
+    Partitioner, PostInliningPartitioning, PreInliningPartitioning,
+};
+
+/// Functions built from more bodies than this are invalidated by so many edits
+/// that grouping them by their inputs doesn't help, so they are treated like
+/// items without inputs. This also bounds the inputs cached per dep node.
+const MAX_INPUTS: usize = 64;
+
+#[derive(Default)]
+pub struct CoInvalidationPartitioning {
+    /// The inputs of the mono items placed into each codegen unit.
+    cgu_inputs: FxHashMap<Symbol, FxHashSet<DepNode>>,
+}
+
+impl<'tcx> Partitioner<'tcx> for CoInvalidationPartitioning {
+    fn place_root_mono_items(
+        &mut self,
+        cx: &PartitioningCx<'_, 'tcx>,
+        mono_items: &mut dyn Iterator<Item = MonoItem<'tcx>>,
+    ) -> PreInliningPartitioning<'tcx> {
+        let PreInliningPartitioning {
+            codegen_units: default_cgus,
+            roots,
+            internalization_candidates,
+        } = DefaultPartitioning.place_root_mono_items(cx, mono_items);
+
+        let mut codegen_units = FxHashMap::default();
+        let mut previous_inputs = cx
+            .tcx
+            .dep_graph
+            .previous_inputs(MAX_INPUTS, |dep_node| dep_node.kind == DepKind::hir_owner_nodes);
+
+        for default_cgu in default_cgus {
+            // Group the functions of this codegen unit that share inputs other
+            // than their own body, using a union-find over their indices.
+            let mut items = Vec::with_capacity(default_cgu.items().len());
+            let mut groups = Vec::new();
+            let mut first_with_input = FxHashMap::default();
+            let mut first_with_own_body_only = None;
+            for (&mono_item, &linkage) in default_cgu.items() {
+                let inputs = dep_nodes(cx.tcx, mono_item).and_then(|(optimized_mir, own_body)| {
+                    Some((previous_inputs.inputs_of(&optimized_mir)?, own_body))
+                });
+                let (inputs, own_body) = match inputs {
+                    Some((inputs, own_body)) if !inputs.is_empty() => (inputs, own_body),
+                    _ => {
+                        items.push((mono_item, linkage, Vec::new(), None));
+                        continue;
+                    }
+                };
+
+                let group = groups.len();
+                groups.push(group);
+                let mut only_own_body = true;
+                for &input in &inputs {
+                    if input != own_body {
+                        only_own_body = false;
+                        let first = *first_with_input.entry(input).or_insert(group);
+                        union_groups(&mut groups, first, group);
+                    }
+                }
+                if only_own_body {
+                    let first = *first_with_own_body_only.get_or_insert(group);
+                    union_groups(&mut groups, first, group);
+                }
+                items.push((mono_item, linkage, inputs, Some((group, own_body))));
+            }
+
+            // Name each group after the bodies from elsewhere its functions
+            // were built from. The order of the inputs doesn't matter, so
+            // combine their hashes in an order-independent way.
+            let mut group_inputs: FxHashMap<usize, FxHashSet<DepNode>> = FxHashMap::default();
+            for (_, _, inputs, group) in &items {
+                if let Some((group, own_body)) = *group {
+                    let group = find_group(&mut groups, group);
+                    let foreign_inputs = group_inputs.entry(group).or_default();
+                    foreign_inputs.extend(inputs.iter().filter(|&&input| input != own_body));
+                }
+            }
+            let group_names: FxHashMap<usize, Symbol> = group_inputs
+                .into_iter()
+                .map(|(group, inputs)| {
+                    let inputs_hash = inputs.iter().fold(Fingerprint::ZERO, |hash, input| {
+                        hash.combine_commutative(input.hash.into())
+                    });
+                    let name =
+                        format!("{}.{:x}", default_cgu.name(), inputs_hash.to_smaller_hash());
+                    let name = if cx.tcx.sess.opts.debugging_opts.human_readable_cgu_names {
+                        Symbol::intern(&name)
+                    } else {
+                        Symbol::intern(&CodegenUnit::mangle_name(&name))
+                    };
+                    (group, name)
+                })
+                .collect();
+
+            for (mono_item, linkage, inputs, group) in items {
+                let codegen_unit_name = match group {
+                    Some((group, _)) => group_names[&find_group(&mut groups, group)],
+                    None => default_cgu.name(),
+                };
+                codegen_units
+                    .entry(codegen_unit_name)
+                    .or_insert_with(|| CodegenUnit::new(codegen_unit_name))
+                    .items_mut()
+                    .insert(mono_item, linkage);
+                self.cgu_inputs.entry(codegen_unit_name).or_default().extend(inputs);
+            }
+
+            // Keep codegen units that had no items, like the fallback one.
+            if default_cgu.items().is_empty() {
+                codegen_units.insert(default_cgu.name(), default_cgu);
+            }
+        }
+
+        PreInliningPartitioning {
+            codegen_units: codegen_units
+                .into_iter()
+                .map(|(_, codegen_unit)| codegen_unit)
+                .collect(),
+            roots,
+            internalization_candidates,
+        }
+    }
+
+    fn merge_codegen_units(
+        &mut self,
+        cx: &PartitioningCx<'_, 'tcx>,
+        initial_partitioning: &mut PreInliningPartitioning<'tcx>,
+    ) {
+        assert!(cx.target_cgu_count >= 1);
+
+        // Start off with a deterministic order, like the default merging. The
+        // codegen units are referred to by their index in this order, and the
+        // ones that were merged into others are `None`.
+        let mut sorted_cgus = mem::take(&mut initial_partitioning.codegen_units);
+        sorted_cgus.sort_by_cached_key(|cgu| cgu.name().as_str());
+        let mut cgu_inputs: Vec<FxHashSet<DepNode>> = sorted_cgus
+            .iter()
+            .map(|cgu| self.cgu_inputs.remove(&cgu.name()).unwrap_or_default())
+            .collect();
+        let mut codegen_units: Vec<Option<CodegenUnit<'tcx>>> =
+            sorted_cgus.into_iter().map(Some).collect();
+
+        // The codegen units that contain each input, so that finding the
+        // codegen units that share inputs with another one doesn't need to
+        // look at all of them.
+        let mut input_cgus: FxHashMap<DepNode, FxHashSet<usize>> = FxHashMap::default();
+        for (index, inputs) in cgu_inputs.iter().enumerate() {
+            for &input in inputs {
+                input_cgus.entry(input).or_default().insert(index);
+            }
+        }
+
+        // The codegen units by increasing size. A codegen unit that grows is
+        // pushed again, so outdated entries are skipped by `smallest_cgu_index`.
+        let mut by_size: BinaryHeap<Reverse<(usize, usize)>> = codegen_units
+            .iter()
+            .enumerate()
+            .map(|(index, cgu)| Reverse((cgu.as_ref().unwrap().size_estimate(), index)))
+            .collect();
+
+        // This map keeps track of what got merged into what.
+        let mut cgu_contents: FxHashMap<Symbol, Vec<SymbolStr>> = codegen_units
+            .iter()
+            .map(|cgu| cgu.as_ref().unwrap().name())
+            .map(|name| (name, vec![name.as_str()]))
+            .collect();
+
+        let mut remaining = codegen_units.len();
+        while remaining > cx.target_cgu_count {
+            let smallest = smallest_cgu_index(&mut by_size, &codegen_units);
+            by_size.pop();
+            let smallest_inputs = mem::take(&mut cgu_inputs[smallest]);
+
+            // Merge into the codegen unit that shares the largest fraction of
+            // inputs, preferring the first one in name order on ties. If none
+            // shares any, merge into the second smallest codegen unit, like the
+            // default merging does.
+            let mut shared_inputs: FxHashMap<usize, usize> = FxHashMap::default();
+            for input in &smallest_inputs {
+                for &index in &input_cgus[input] {
+                    if index != smallest {
+                        *shared_inputs.entry(index).or_default() += 1;
+                    }
+                }
+            }
+            let overlap = |index: usize, shared: usize| {
+                (shared, smallest_inputs.len() + cgu_inputs[index].len() - shared)
+            };
+            let target = shared_inputs
+                .into_iter()
+                .max_by(|&(a, shared_a), &(b, shared_b)| {
+                    compare_overlap(overlap(a, shared_a), overlap(b, shared_b)).then(b.cmp(&a))
+                })
+                .map(|(index, _)| index)
+                .unwrap_or_else(|| smallest_cgu_index(&mut by_size, &codegen_units));
+
+            let mut smallest_cgu = codegen_units[smallest].take().unwrap();
+            let target_cgu = codegen_units[target].as_mut().unwrap();
+
+            // Move the mono-items from `smallest` to `target`
+            target_cgu.modify_size_estimate(smallest_cgu.size_estimate());
+            for (k, v) in smallest_cgu.items_mut().drain() {
+                target_cgu.items_mut().insert(k, v);
+            }
+            by_size.push(Reverse((target_cgu.size_estimate(), target)));
+
+            // Record that `target` now contains all the stuff that was in
+            // `smallest` before.
+            let mut consumed_cgu_names = cgu_contents.remove(&smallest_cgu.name()).unwrap();
+            cgu_contents.get_mut(&target_cgu.name()).unwrap().extend(consumed_cgu_names.drain(..));
+            for input in smallest_inputs {
+                let cgus = input_cgus.get_mut(&input).unwrap();
+                cgus.remove(&smallest);
+                cgus.insert(target);
+                cgu_inputs[target].insert(input);
+            }
+            remaining -= 1;
+
+            debug!(
+                "CodegenUnit {} merged into CodegenUnit {}",
+                smallest_cgu.name(),
+                target_cgu.name()
+            );
+        }
+
+        let mut codegen_units: Vec<_> = codegen_units.into_iter().flatten().collect();
+        merging::rename_codegen_units(cx, &mut codegen_units, cgu_contents);
+        initial_partitioning.codegen_units = codegen_units;
+    }
+
+    fn place_inlined_mono_items(
+        &mut self,
+        cx: &PartitioningCx<'_, 'tcx>,
+        initial_partitioning: PreInliningPartitioning<'tcx>,
+    ) -> PostInliningPartitioning<'tcx> {
+        DefaultPartitioning.place_inlined_mono_items(cx, initial_partitioning)
+    }
+
+    fn internalize_symbols(
+        &mut self,
+        cx: &PartitioningCx<'_, 'tcx>,
+        partitioning: &mut PostInliningPartitioning<'tcx>,
+    ) {
+        DefaultPartitioning.internalize_symbols(cx, partitioning)
+    }
+}
+
+/// Returns the dep node of the optimized MIR of `mono_item`, whose inputs are
+/// the HIR bodies it was built from, along with the dep node of its own body,
+/// or `None` if we don't track its inputs.
+fn dep_nodes<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mono_item: MonoItem<'tcx>,
+) -> Option<(DepNode, DepNode)> {
+    let def_id = match mono_item {
+        MonoItem::Fn(Instance { def: InstanceDef::Item(def), .. }) if def.did.is_local() => def.did,
+        MonoItem::Fn(_) | MonoItem::Static(_) | MonoItem::GlobalAsm(_) => return None,
+    };
+
+    let optimized_mir =
+        DepNode::from_def_path_hash(tcx.def_path_hash(def_id), DepKind::optimized_mir);
+    // Closures are part of the body of their owner.
+    let owner = tcx.hir().local_def_id_to_hir_id(def_id.expect_local()).owner;
+    let own_body =
+        DepNode::from_def_path_hash(tcx.def_path_hash(owner.to_def_id()), DepKind::hir_owner_nodes);
+    Some((optimized_mir, own_body))
+}
+
+/// Returns the representative of the group that `index` belongs to.
+fn find_group(groups: &mut [usize], mut index: usize) -> usize {
+    while groups[index] != index {
+        groups[index] = groups[groups[index]];
+        index = groups[index];
+    }
+    index
+}
+
+/// Merges the groups of `a` and `b`.
+fn union_groups(groups: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find_group(groups, a), find_group(groups, b));
+    groups[cmp::max(a, b)] = cmp::min(a, b);
+}
+
+/// Returns the index of the smallest codegen unit in `by_size`, after dropping
+/// the entries of codegen units that were merged away or grew since.
+fn smallest_cgu_index(
+    by_size: &mut BinaryHeap<Reverse<(usize, usize)>>,
+    codegen_units: &[Option<CodegenUnit<'_>>],
+) -> usize {
+    loop {
+        let Reverse((size, index)) = *by_size.peek().unwrap();
+        match &codegen_units[index] {
+            Some(cgu) if cgu.size_estimate() == size => return index,
+            _ => {
+                by_size.pop();
+            }
+        }
+    }
+}
+
+/// Compares two fractions of shared inputs, each given as the number of inputs
+/// two codegen units share and the number of inputs of both together.
+fn compare_overlap(
+    (shared_a, total_a): (usize, usize),
+    (shared_b, total_b): (usize, usize),
+) -> Ordering {
+    (shared_a * total_b).cmp(&(shared_b * total_a))
+}
@@ -55,6 +55,16 @@ pub fn merge_codegen_units<'tcx>(
         );
     }
 
+    rename_codegen_units(cx, codegen_units, cgu_contents);
+}
+
+/// Names the codegen units after merging, given the names of the codegen units
+/// that were merged into each of them.
+pub fn rename_codegen_units<'tcx>(
+    cx: &PartitioningCx<'_, 'tcx>,
+    codegen_units: &mut [CodegenUnit<'tcx>],
+    cgu_contents: FxHashMap<Symbol, Vec<SymbolStr>>,
+) {
     let cgu_name_builder = &mut CodegenUnitNameBuilder::new(cx.tcx);
 
     if cx.tcx.sess.opts.incremental.is_some() {
@@ -92,6 +92,7 @@
 //! source-level module, functions from the same module will be available for
 //! inlining, even when they are not marked `#[inline]`.
 
+mod co_invalidation;
 mod default;
 mod merging;
 
@@ -148,6 +149,7 @@ fn get_partitioner<'tcx>(tcx: TyCtxt<'tcx>) -> Box<dyn Partitioner<'tcx>> {
 
     match strategy {
         "default" => Box::new(default::DefaultPartitioning),
+        "co-invalidation" => Box::new(co_invalidation::CoInvalidationPartitioning::default()),
         _ => tcx.sess.fatal("unknown partitioning strategy"),
     }
 }
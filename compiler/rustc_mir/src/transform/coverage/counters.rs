@@ -92,6 +92,37 @@ pub fn make_identity_counter(&mut self, counter_operand: ExpressionOperandId) ->
         })
     }
 
+    /// Makes an `Expression` counting how often a branch was not taken, by subtracting the count
+    /// of the branch from the count of the branching BCB.
+    pub fn make_branch_not_taken_expression(
+        &mut self,
+        branching_counter_operand: ExpressionOperandId,
+        branch_counter_operand: ExpressionOperandId,
+    ) -> CoverageKind {
+        self.make_expression(
+            branching_counter_operand,
+            Op::Subtract,
+            branch_counter_operand,
+            || None,
+        )
+    }
+
+    /// Makes an `Expression` counting how often the right-hand side of a short-circuiting `&&` or
+    /// `||` evaluated to an outcome, by subtracting the count of the short-circuiting path from
+    /// the count of the branch taken on that outcome.
+    pub fn make_short_circuit_rhs_expression(
+        &mut self,
+        branch_counter_operand: ExpressionOperandId,
+        short_circuit_counter_operand: ExpressionOperandId,
+    ) -> CoverageKind {
+        self.make_expression(
+            branch_counter_operand,
+            Op::Subtract,
+            short_circuit_counter_operand,
+            || None,
+        )
+    }
+
     /// Counter IDs start from one and go up.
     fn next_counter(&mut self) -> CounterValueReference {
         assert!(self.next_counter_id < u32::MAX - self.num_expressions);
@@ -9,7 +9,7 @@
 mod tests;
 
 use counters::CoverageCounters;
-use graph::{BasicCoverageBlock, BasicCoverageBlockData, CoverageGraph};
+use graph::{BasicCoverageBlock, BasicCoverageBlockData, BcbBranch, CoverageGraph};
 use spans::{CoverageSpan, CoverageSpans};
 
 use crate::transform::MirPass;
@@ -26,8 +26,8 @@
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
 use rustc_middle::mir::coverage::*;
 use rustc_middle::mir::{
-    self, BasicBlock, BasicBlockData, Coverage, SourceInfo, Statement, StatementKind, Terminator,
-    TerminatorKind,
+    self, BasicBlock, BasicBlockData, Coverage, Local, Rvalue, SourceInfo, Statement,
+    StatementKind, Terminator, TerminatorKind,
 };
 use rustc_middle::ty::TyCtxt;
 use rustc_span::def_id::DefId;
@@ -205,7 +205,17 @@ fn inject_counters(&'a mut self) {
             .make_bcb_counters(&mut self.basic_coverage_blocks, &coverage_spans);
 
         let (result, intermediate_expressions) = match intermediate_expressions_or_error {
-            Ok(intermediate_expressions) => {
+            Ok(mut intermediate_expressions) => {
+                ////////////////////////////////////////////////////
+                // With `-Z instrument-coverage=branch`, make a `Branch` for the outcomes of each
+                // counted `SwitchInt`, from the BCB and edge counters of its branches. This must
+                // be done before the counters are taken from the `CoverageGraph` to be injected.
+                let branches = if tcx.sess.instrument_coverage_branch() {
+                    self.make_branches(&mut intermediate_expressions)
+                } else {
+                    Vec::new()
+                };
+
                 // If debugging, add any intermediate expressions (which are not associated with any
                 // BCB) to the `debug_used_expressions` map.
                 if debug_used_expressions.is_enabled() {
@@ -238,6 +248,10 @@ fn inject_counters(&'a mut self) {
                 // their own independent code region's coverage.
                 self.inject_indirect_counters(&mut graphviz_data, &mut debug_used_expressions);
 
+                for (bb, branch, code_region) in branches {
+                    inject_statement(self.mir_body, branch, bb, Some(code_region));
+                }
+
                 // Intermediate expressions will be injected as the final step, after generating
                 // debug output, if any.
                 ////////////////////////////////////////////////////
@@ -329,6 +343,202 @@ fn inject_coverage_span_counters(
         }
     }
 
+    /// Makes a `CoverageKind::Branch` for the outcomes of the `SwitchInt` terminating each counted
+    /// `BasicCoverageBlock`, and returns them with the `BasicBlock` to inject them into and the
+    /// code region of the value the `SwitchInt` branches on.
+    ///
+    /// A two-way branch gets one `Branch`: For a `bool`, the `otherwise` target is the branch
+    /// taken when the condition is true; for any other type, the target of the first value is.
+    /// A branch with more outcomes (such as a `match` with more than two arms) gets one `Branch`
+    /// per outcome, counting how often that outcome was taken or not taken. The "not taken" count
+    /// is computed by an additional `Expression`, which is added to `intermediate_expressions`.
+    ///
+    /// A `SwitchInt` is skipped if its outcomes cannot be counted separately, because two of its
+    /// targets are in the same BCB, or if any of its branches has no counter.
+    ///
+    /// The right-hand side of a short-circuiting `&&` or `||` also gets a `Branch`, see
+    /// `make_short_circuit_rhs_branch()`.
+    fn make_branches(
+        &mut self,
+        intermediate_expressions: &mut Vec<CoverageKind>,
+    ) -> Vec<(BasicBlock, CoverageKind, CodeRegion)> {
+        let source_map = self.tcx.sess.source_map();
+        let body_span = self.body_span;
+        let file_name = Symbol::intern(&self.source_file.name.prefer_remapped().to_string_lossy());
+        let mir_body = &*self.mir_body;
+        let basic_coverage_blocks = &self.basic_coverage_blocks;
+
+        let mut branches = Vec::new();
+        let mut short_circuit_candidates = Vec::new();
+        'bcbs: for (bcb, bcb_data) in basic_coverage_blocks.iter_enumerated() {
+            let branching_counter_operand = match bcb_data.counter() {
+                Some(counter_kind) => counter_kind.as_operand_id(),
+                None => continue,
+            };
+            let branching_bb = bcb_data.last_bb();
+            let (switch_ty, targets) = match &mir_body[branching_bb].terminator().kind {
+                TerminatorKind::SwitchInt { switch_ty, targets, .. } => (switch_ty, targets),
+                _ => continue,
+            };
+
+            // Unreachable targets (such as the `otherwise` target of an exhaustive `match`) are
+            // not part of the `CoverageGraph`, and are not counted as outcomes.
+            let mut target_bcbs = Vec::with_capacity(targets.all_targets().len());
+            for &target_bb in targets.all_targets() {
+                if let TerminatorKind::Unreachable = mir_body[target_bb].terminator().kind {
+                    continue;
+                }
+                match basic_coverage_blocks.bcb_from_bb(target_bb) {
+                    Some(target_bcb)
+                        if basic_coverage_blocks.successors[bcb].contains(&target_bcb)
+                            && !target_bcbs.contains(&target_bcb) =>
+                    {
+                        target_bcbs.push(target_bcb)
+                    }
+                    _ => continue 'bcbs,
+                }
+            }
+            if target_bcbs.len() < 2 {
+                continue;
+            }
+
+            let mut outcome_operands = Vec::with_capacity(target_bcbs.len());
+            for target_bcb in target_bcbs {
+                match BcbBranch::from_to(bcb, target_bcb, basic_coverage_blocks)
+                    .counter(basic_coverage_blocks)
+                {
+                    Some(counter_kind) => outcome_operands.push(counter_kind.as_operand_id()),
+                    None => continue 'bcbs,
+                }
+            }
+
+            let span = spans::function_source_span(
+                branch_condition_span(mir_body, branching_bb),
+                body_span,
+            );
+            if span == body_span {
+                // The condition is not part of the function body's source (for example, it was
+                // generated by a macro defined elsewhere), so there is no region to report.
+                continue;
+            }
+            let code_region =
+                make_code_region(source_map, file_name, &self.source_file, span, body_span);
+
+            let mut true_and_false_operands = Vec::with_capacity(outcome_operands.len());
+            match outcome_operands[..] {
+                [false_operand, true_operand] if switch_ty.is_bool() => {
+                    true_and_false_operands.push((true_operand, false_operand));
+                    short_circuit_candidates.push((bcb, branching_bb, true_operand, false_operand));
+                }
+                [true_operand, false_operand] => {
+                    true_and_false_operands.push((true_operand, false_operand))
+                }
+                _ => {
+                    for true_operand in outcome_operands {
+                        let not_taken = self.coverage_counters.make_branch_not_taken_expression(
+                            branching_counter_operand,
+                            true_operand,
+                        );
+                        true_and_false_operands.push((true_operand, not_taken.as_operand_id()));
+                        intermediate_expressions.push(not_taken);
+                    }
+                }
+            }
+            for (true_operand, false_operand) in true_and_false_operands {
+                debug!(
+                    "{:?} ({:?}) gets a Branch(true: {:?}, false: {:?}) at {:?}",
+                    bcb, branching_bb, true_operand, false_operand, code_region,
+                );
+                branches.push((
+                    branching_bb,
+                    CoverageKind::Branch { true_operand, false_operand },
+                    code_region.clone(),
+                ));
+            }
+        }
+
+        for (bcb, branching_bb, true_operand, false_operand) in short_circuit_candidates {
+            if let Some(branch) = self.make_short_circuit_rhs_branch(
+                bcb,
+                branching_bb,
+                true_operand,
+                false_operand,
+                intermediate_expressions,
+            ) {
+                branches.push(branch);
+            }
+        }
+        branches
+    }
+
+    /// The right-hand side of a short-circuiting `&&` or `||` is not branched on by itself: it is
+    /// assigned to the result of the operator, which is then branched on. The other predecessor
+    /// of the block branching on the result assigns the constant the operator short-circuits to.
+    /// So the right-hand side evaluated to the outcome of the branch as often as the branch was
+    /// taken, minus how often it was short-circuited to the same outcome.
+    ///
+    /// Makes a `Branch` with these counts for the right-hand side of the operator, if `bcb`
+    /// branches on its result, and the additional `Expression` is added to
+    /// `intermediate_expressions`.
+    fn make_short_circuit_rhs_branch(
+        &mut self,
+        bcb: BasicCoverageBlock,
+        branching_bb: BasicBlock,
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+        intermediate_expressions: &mut Vec<CoverageKind>,
+    ) -> Option<(BasicBlock, CoverageKind, CodeRegion)> {
+        if self.bcb_leader_bb(bcb) != branching_bb {
+            return None;
+        }
+        let (short_circuit_bb, short_circuit_value, rhs_span) =
+            short_circuit_rhs(&*self.mir_body, branching_bb)?;
+
+        let basic_coverage_blocks = &self.basic_coverage_blocks;
+        let short_circuit_bcb = basic_coverage_blocks.bcb_from_bb(short_circuit_bb)?;
+        let short_circuit_counter = if basic_coverage_blocks.successors[short_circuit_bcb].len() > 1
+        {
+            BcbBranch::from_to(short_circuit_bcb, bcb, basic_coverage_blocks)
+                .counter(basic_coverage_blocks)?
+        } else {
+            basic_coverage_blocks[short_circuit_bcb].counter()?
+        };
+        let short_circuit_operand = short_circuit_counter.as_operand_id();
+
+        let span = spans::function_source_span(rhs_span, self.body_span);
+        if span == self.body_span {
+            return None;
+        }
+        let code_region = make_code_region(
+            self.tcx.sess.source_map(),
+            Symbol::intern(&self.source_file.name.prefer_remapped().to_string_lossy()),
+            &self.source_file,
+            span,
+            self.body_span,
+        );
+
+        let (true_operand, false_operand) = if short_circuit_value {
+            let rhs_true = self
+                .coverage_counters
+                .make_short_circuit_rhs_expression(true_operand, short_circuit_operand);
+            let rhs_true_operand = rhs_true.as_operand_id();
+            intermediate_expressions.push(rhs_true);
+            (rhs_true_operand, false_operand)
+        } else {
+            let rhs_false = self
+                .coverage_counters
+                .make_short_circuit_rhs_expression(false_operand, short_circuit_operand);
+            let rhs_false_operand = rhs_false.as_operand_id();
+            intermediate_expressions.push(rhs_false);
+            (true_operand, rhs_false_operand)
+        };
+        debug!(
+            "{:?} ({:?}) gets a short-circuit Branch(true: {:?}, false: {:?}) at {:?}",
+            bcb, branching_bb, true_operand, false_operand, code_region,
+        );
+        Some((branching_bb, CoverageKind::Branch { true_operand, false_operand }, code_region))
+    }
+
     /// `inject_coverage_span_counters()` looped through the `CoverageSpan`s and injected the
     /// counter from the `CoverageSpan`s `BasicCoverageBlock`, removing it from the BCB in the
     /// process (via `take_counter()`).
@@ -486,6 +696,88 @@ fn inject_statement(
     data.statements.insert(0, statement);
 }
 
+/// Returns the span of the value a `SwitchInt` branches on. (The span of the `SwitchInt` itself
+/// covers the whole `if` or `match` expression, including the blocks it branches to.) This is the
+/// span of the statement assigning the value, if it is assigned in the same block, or otherwise
+/// the span of the temporary holding it.
+fn branch_condition_span(mir_body: &mir::Body<'tcx>, bb: BasicBlock) -> Span {
+    let data = &mir_body[bb];
+    let terminator = data.terminator();
+    if let TerminatorKind::SwitchInt { discr, .. } = &terminator.kind {
+        match discr {
+            mir::Operand::Constant(constant) => return constant.span,
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => {
+                if let Some(local) = place.as_local() {
+                    if let Some((statement, _)) = last_assignment(data, local) {
+                        return statement.source_info.span;
+                    }
+                    // The span of a user variable is the span of its declaration.
+                    let local_decl = &mir_body.local_decls[local];
+                    if !local_decl.is_user_variable() {
+                        return local_decl.source_info.span;
+                    }
+                }
+            }
+        }
+    }
+    terminator.source_info.span
+}
+
+/// If the `SwitchInt` terminating `bb` branches on the result of a short-circuiting `&&` or `||`,
+/// returns the predecessor of `bb` that short-circuits, the constant it assigns to the result,
+/// and the span of the right-hand side of the operator, which the other predecessor assigns.
+fn short_circuit_rhs(
+    mir_body: &mir::Body<'tcx>,
+    bb: BasicBlock,
+) -> Option<(BasicBlock, bool, Span)> {
+    let data = &mir_body[bb];
+    let result = match &data.terminator().kind {
+        TerminatorKind::SwitchInt { discr, .. } => discr.place()?.as_local()?,
+        _ => return None,
+    };
+    if last_assignment(data, result).is_some() {
+        return None;
+    }
+
+    let predecessors = &mir_body.predecessors()[bb];
+    if predecessors.len() != 2 {
+        return None;
+    }
+    let mut short_circuit = None;
+    let mut rhs_span = None;
+    for &predecessor in predecessors.iter() {
+        let predecessor_data = &mir_body[predecessor];
+        match last_assignment(predecessor_data, result)? {
+            (_, Rvalue::Use(mir::Operand::Constant(constant))) => {
+                short_circuit = Some((predecessor, constant.literal.try_to_bool()?));
+            }
+            (_, Rvalue::Use(operand)) => {
+                // The right-hand side is evaluated into a temporary first, by a statement with
+                // the span of the right-hand side only.
+                let rhs = operand.place()?.as_local()?;
+                let (statement, _) = last_assignment(predecessor_data, rhs)?;
+                rhs_span = Some(statement.source_info.span);
+            }
+            _ => return None,
+        }
+    }
+    let (short_circuit_bb, short_circuit_value) = short_circuit?;
+    Some((short_circuit_bb, short_circuit_value, rhs_span?))
+}
+
+/// Returns the last statement of `data` assigning to `local`, and the value it assigns.
+fn last_assignment<'a>(
+    data: &'a BasicBlockData<'tcx>,
+    local: Local,
+) -> Option<(&'a Statement<'tcx>, &'a Rvalue<'tcx>)> {
+    data.statements.iter().rev().find_map(|statement| match &statement.kind {
+        StatementKind::Assign(box (assigned, rvalue)) if assigned.as_local() == Some(local) => {
+            Some((statement, rvalue))
+        }
+        _ => None,
+    })
+}
+
 // Non-code expressions are injected into the coverage map, without generating executable code.
 fn inject_intermediate_expression(mir_body: &mut mir::Body<'tcx>, expression: CoverageKind) {
     debug_assert!(if let CoverageKind::Expression { .. } = expression { true } else { false });
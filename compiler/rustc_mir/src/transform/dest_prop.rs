@@ -947,7 +947,7 @@ fn is_local_required(local: Local, body: &Body<'_>) -> bool {
 }
 
 /// Walks MIR to find all locals that have their address taken anywhere.
-fn ever_borrowed_locals(body: &Body<'_>) -> BitSet<Local> {
+crate fn ever_borrowed_locals(body: &Body<'_>) -> BitSet<Local> {
     let mut visitor = BorrowCollector { locals: BitSet::new_empty(body.local_decls.len()) };
     visitor.visit_body(body);
     visitor.locals
@@ -0,0 +1,354 @@
+//! Global value numbering.
+//!
+//! MIR building evaluates every expression where it occurs in the source, so pure computations like
+//! the length of a slice, the discriminant of an enum, arithmetic on the same operands or reads of
+//! the same field end up being computed again and again. This pass finds rvalues that are known to
+//! produce the same value as an rvalue computed earlier, and replaces them with a copy of the local
+//! the earlier result was assigned to.
+//!
+//! # Soundness
+//!
+//! MIR locals are not in SSA form, so two rvalues that look the same can still produce different
+//! values if one of their operands is modified between them. To avoid that, value numbering only
+//! looks at rvalues whose operands are constants or *SSA locals*: locals that are assigned at most
+//! once, never borrowed and only used at locations dominated by their assignment. Function
+//! arguments are SSA locals if they are never modified.
+//!
+//! Since the assignment of an SSA local dominates all of its uses, an SSA local always holds the
+//! value of its most recent assignment. So if an rvalue is computed at a location that dominates
+//! another computation of the same rvalue, both computations produce the same value. If the result
+//! of the first one was assigned to an SSA local, the second computation can just copy that local.
+//!
+//! Copies of SSA locals are followed, so that `_3 = _1; _4 = Len(_3);` and `_5 = Len(_1);` are
+//! known to produce the same value.
+//!
+//! Only values of `Copy` types are reused. The storage statements of the locals that are reused
+//! are removed, since the new copies can be outside of the range their storage was live for, and
+//! moves out of them become copies.
+//!
+//! It is recommended to run `SimplifyLocals` some time after this pass, as it leaves assignments
+//! behind whose result is no longer used.
+
+use crate::transform::MirPass;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::graph::dominators::Dominators;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{
+    MutVisitor, MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor,
+};
+use rustc_middle::mir::{
+    traversal, BasicBlock, BasicBlockData, BinOp, Body, CastKind, ConstantKind, Local, LocalDecls,
+    Location, Operand, Place, PlaceElem, Rvalue, Statement, StatementKind, UnOp,
+};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::Span;
+
+pub struct GlobalValueNumbering;
+
+impl<'tcx> MirPass<'tcx> for GlobalValueNumbering {
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        if tcx.sess.mir_opt_level() < 4 {
+            return;
+        }
+
+        let def_id = body.source.def_id();
+        let dominators = body.dominators();
+        let ssa = SsaLocals::new(body, &dominators);
+
+        let mut numbering = ValueNumbering {
+            tcx,
+            param_env: tcx.param_env_reveal_all_normalized(def_id),
+            dominators: &dominators,
+            ssa: &ssa,
+            representatives: body.local_decls.indices().collect(),
+            available: FxHashMap::default(),
+            reused: BitSet::new_empty(body.local_decls.len()),
+        };
+
+        let reverse_postorder: Vec<_> =
+            traversal::reverse_postorder(body).map(|(bb, _)| bb).collect();
+        let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+        for bb in reverse_postorder {
+            numbering.visit_block(bb, &mut basic_blocks[bb], local_decls);
+        }
+
+        if numbering.reused.is_empty() {
+            return;
+        }
+        debug!("reused {:?} in {:?}", numbering.reused, def_id);
+        ReuseLocals { tcx, reused: numbering.reused }.visit_body(body);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Assignment {
+    Unassigned,
+    /// Function arguments are assigned on entry.
+    Arg,
+    Once(Location),
+    NotSsa,
+}
+
+/// Finds the locals that are assigned at most once, and only used where that assignment is known
+/// to have happened.
+struct SsaLocals {
+    assignments: IndexVec<Local, Assignment>,
+}
+
+impl SsaLocals {
+    fn new(body: &Body<'_>, dominators: &Dominators<BasicBlock>) -> SsaLocals {
+        let assignments = body
+            .local_decls
+            .indices()
+            .map(|local| {
+                if local.index() >= 1 && local.index() <= body.arg_count {
+                    Assignment::Arg
+                } else {
+                    Assignment::Unassigned
+                }
+            })
+            .collect();
+        let mut visitor = SsaVisitor { dominators, assignments };
+
+        // Visiting the blocks in reverse postorder means that an assignment is always visited
+        // before the uses it dominates. Unreachable blocks are never executed, so they don't
+        // matter.
+        for (bb, data) in traversal::reverse_postorder(body) {
+            visitor.visit_basic_block_data(bb, data);
+        }
+        SsaLocals { assignments: visitor.assignments }
+    }
+
+    fn is_ssa(&self, local: Local) -> bool {
+        matches!(self.assignments[local], Assignment::Arg | Assignment::Once(_))
+    }
+}
+
+struct SsaVisitor<'a> {
+    dominators: &'a Dominators<BasicBlock>,
+    assignments: IndexVec<Local, Assignment>,
+}
+
+impl SsaVisitor<'_> {
+    fn check_use(&mut self, local: Local, location: Location) {
+        let assignment = &mut self.assignments[local];
+        let dominated = match *assignment {
+            Assignment::Arg => true,
+            // A use in the assignment itself reads the previous value.
+            Assignment::Once(def) => def != location && def.dominates(location, self.dominators),
+            Assignment::Unassigned | Assignment::NotSsa => false,
+        };
+        if !dominated {
+            *assignment = Assignment::NotSsa;
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for SsaVisitor<'_> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        let local = place.local;
+        match context {
+            PlaceContext::NonUse(_) => {}
+            PlaceContext::MutatingUse(MutatingUseContext::Store) if place.projection.is_empty() => {
+                let assignment = &mut self.assignments[local];
+                *assignment = match *assignment {
+                    Assignment::Unassigned => Assignment::Once(location),
+                    _ => Assignment::NotSsa,
+                };
+            }
+            // Any write to part of the local, and any borrow that may be used to write to it.
+            PlaceContext::MutatingUse(_)
+            | PlaceContext::NonMutatingUse(
+                NonMutatingUseContext::SharedBorrow
+                | NonMutatingUseContext::ShallowBorrow
+                | NonMutatingUseContext::UniqueBorrow
+                | NonMutatingUseContext::AddressOf,
+            ) => self.assignments[local] = Assignment::NotSsa,
+            PlaceContext::NonMutatingUse(_) => self.check_use(local, location),
+        }
+
+        for elem in place.projection {
+            if let PlaceElem::Index(index) = elem {
+                self.check_use(index, location);
+            }
+        }
+    }
+}
+
+/// The value of an operand, in terms of the representatives of SSA locals.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Value<'tcx> {
+    Place(Place<'tcx>),
+    Constant(ConstantKind<'tcx>),
+}
+
+/// An rvalue that always produces the same value for the same operands.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Expr<'tcx> {
+    Use(Place<'tcx>),
+    Len(Place<'tcx>),
+    Discriminant(Place<'tcx>),
+    Cast(CastKind, Value<'tcx>, Ty<'tcx>),
+    BinaryOp(BinOp, Value<'tcx>, Value<'tcx>),
+    CheckedBinaryOp(BinOp, Value<'tcx>, Value<'tcx>),
+    UnaryOp(UnOp, Value<'tcx>),
+}
+
+struct ValueNumbering<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    dominators: &'a Dominators<BasicBlock>,
+    ssa: &'a SsaLocals,
+    /// The SSA local each SSA local is a copy of, or the local itself.
+    representatives: IndexVec<Local, Local>,
+    /// The SSA locals an expression was assigned to, and where.
+    available: FxHashMap<Expr<'tcx>, Vec<(Location, Local)>>,
+    /// The locals that copies were introduced for.
+    reused: BitSet<Local>,
+}
+
+impl<'a, 'tcx> ValueNumbering<'a, 'tcx> {
+    fn visit_block(
+        &mut self,
+        block: BasicBlock,
+        data: &mut BasicBlockData<'tcx>,
+        local_decls: &LocalDecls<'tcx>,
+    ) {
+        for (statement_index, statement) in data.statements.iter_mut().enumerate() {
+            if let StatementKind::Assign(box (dest, ref mut rvalue)) = statement.kind {
+                let location = Location { block, statement_index };
+                self.visit_assign(dest, rvalue, location, local_decls, statement.source_info.span);
+            }
+        }
+    }
+
+    fn visit_assign(
+        &mut self,
+        dest: Place<'tcx>,
+        rvalue: &mut Rvalue<'tcx>,
+        location: Location,
+        local_decls: &LocalDecls<'tcx>,
+        span: Span,
+    ) {
+        let dest = dest.as_local().filter(|&local| self.ssa.is_ssa(local));
+
+        if let Rvalue::Use(Operand::Copy(place) | Operand::Move(place)) = *rvalue {
+            if let (Some(dest), Some(src)) = (dest, place.as_local()) {
+                if self.ssa.is_ssa(src) {
+                    self.representatives[dest] = self.representatives[src];
+                }
+                return;
+            }
+        }
+
+        let expr = match self.expr(rvalue) {
+            Some(expr) => expr,
+            None => return,
+        };
+        let dominators = self.dominators;
+        let available = self.available.entry(expr).or_default();
+
+        let previous = available.iter().find(|&&(def, _)| def.dominates(location, dominators));
+        if let Some(&(_, local)) = previous {
+            if self.tcx.consider_optimizing(|| {
+                format!("GlobalValueNumbering {:?} = {:?} at {:?}", local, expr, location)
+            }) {
+                debug!("replacing {:?} with {:?} at {:?}", rvalue, local, location);
+                *rvalue = Rvalue::Use(Operand::Copy(local.into()));
+                self.reused.insert(local);
+                if let Some(dest) = dest {
+                    self.representatives[dest] = self.representatives[local];
+                }
+                return;
+            }
+        }
+
+        if let Some(dest) = dest {
+            if local_decls[dest].ty.is_copy_modulo_regions(self.tcx.at(span), self.param_env) {
+                available.push((location, dest));
+            }
+        }
+    }
+
+    fn expr(&self, rvalue: &Rvalue<'tcx>) -> Option<Expr<'tcx>> {
+        let expr = match *rvalue {
+            // Copies of whole locals are handled by following the representatives instead.
+            Rvalue::Use(Operand::Copy(place)) if !place.projection.is_empty() => {
+                Expr::Use(self.place(place, false)?)
+            }
+            Rvalue::Len(place) => Expr::Len(self.place(place, true)?),
+            Rvalue::Discriminant(place) => Expr::Discriminant(self.place(place, false)?),
+            Rvalue::Cast(kind, ref operand, ty) => Expr::Cast(kind, self.value(operand)?, ty),
+            Rvalue::BinaryOp(op, box (ref lhs, ref rhs)) => {
+                Expr::BinaryOp(op, self.value(lhs)?, self.value(rhs)?)
+            }
+            Rvalue::CheckedBinaryOp(op, box (ref lhs, ref rhs)) => {
+                Expr::CheckedBinaryOp(op, self.value(lhs)?, self.value(rhs)?)
+            }
+            Rvalue::UnaryOp(op, ref operand) => Expr::UnaryOp(op, self.value(operand)?),
+            _ => return None,
+        };
+        Some(expr)
+    }
+
+    fn value(&self, operand: &Operand<'tcx>) -> Option<Value<'tcx>> {
+        match *operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                Some(Value::Place(self.place(place, false)?))
+            }
+            Operand::Constant(ref constant) => Some(Value::Constant(constant.literal)),
+        }
+    }
+
+    /// Returns `place` based on the representative of its local, if it only projects to fields of
+    /// an SSA local. The length of a slice is part of the pointer to it, so `Len` may also look
+    /// through a final dereference.
+    fn place(&self, place: Place<'tcx>, allow_final_deref: bool) -> Option<Place<'tcx>> {
+        if !self.ssa.is_ssa(place.local) {
+            return None;
+        }
+        let projection = place.projection;
+        let only_fields = projection.iter().enumerate().all(|(i, elem)| match elem {
+            PlaceElem::Field(..) => true,
+            PlaceElem::Deref => allow_final_deref && i + 1 == projection.len(),
+            _ => false,
+        });
+        if !only_fields {
+            return None;
+        }
+        Some(Place { local: self.representatives[place.local], projection })
+    }
+}
+
+/// Makes the reused locals live for the whole body.
+struct ReuseLocals<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    reused: BitSet<Local>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for ReuseLocals<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        match statement.kind {
+            StatementKind::StorageLive(local) | StatementKind::StorageDead(local)
+                if self.reused.contains(local) =>
+            {
+                statement.make_nop()
+            }
+            _ => self.super_statement(statement, location),
+        }
+    }
+
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        if let Operand::Move(place) = *operand {
+            if self.reused.contains(place.local) {
+                *operand = Operand::Copy(place);
+            }
+        }
+        self.super_operand(operand, location);
+    }
+}
@@ -17,6 +17,7 @@
 use std::iter;
 use std::ops::{Range, RangeFrom};
 
+crate mod cross_crate;
 crate mod cycle;
 
 const INSTR_COST: usize = 5;
@@ -143,6 +144,7 @@ fn try_inlining(
         let callee_attrs = self.tcx.codegen_fn_attrs(callsite.callee.def_id());
         self.check_codegen_attributes(callsite, callee_attrs)?;
         self.check_mir_is_available(caller_body, &callsite.callee)?;
+        self.check_exported(callsite, callee_attrs)?;
         let callee_body = self.tcx.instance_mir(callsite.callee.def);
         self.check_mir_body(callsite, callee_body, callee_attrs)?;
 
@@ -289,16 +291,6 @@ fn check_codegen_attributes(
             return Err("never inline hint");
         }
 
-        // Only inline local functions if they would be eligible for cross-crate
-        // inlining. This is to ensure that the final crate doesn't have MIR that
-        // reference unexported symbols
-        if callsite.callee.def_id().is_local() {
-            let is_generic = callsite.callee.substs.non_erasable_generics().next().is_some();
-            if !is_generic && !callee_attrs.requests_inline() {
-                return Err("not exported");
-            }
-        }
-
         if callsite.fn_sig.c_variadic() {
             return Err("C variadic");
         }
@@ -328,6 +320,44 @@ fn check_codegen_attributes(
         Ok(())
     }
 
+    /// Only inline local functions if they would be eligible for cross-crate inlining. This is to
+    /// ensure that the final crate doesn't have MIR that reference unexported symbols.
+    ///
+    /// This computes the optimized MIR of the callee, so it must only be checked once
+    /// `check_mir_is_available` ruled out query cycles.
+    fn check_exported(
+        &self,
+        callsite: &CallSite<'tcx>,
+        callee_attrs: &CodegenFnAttrs,
+    ) -> Result<(), &'static str> {
+        if let Some(def_id) = callsite.callee.def_id().as_local() {
+            let is_generic = callsite.callee.substs.non_erasable_generics().next().is_some();
+            if !is_generic
+                && !callee_attrs.requests_inline()
+                && !self.tcx.cross_crate_inlinable(def_id.to_def_id())
+            {
+                return Err("not exported");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the callee is neither generic nor `#[inline]`, but small enough to have its MIR
+    /// encoded for cross-crate inlining anyway.
+    fn is_cross_crate_inlinable(
+        &self,
+        callsite: &CallSite<'tcx>,
+        callee_attrs: &CodegenFnAttrs,
+    ) -> bool {
+        let callee = callsite.callee;
+        if !matches!(callee.def, InstanceDef::Item(_)) || callee_attrs.requests_inline() {
+            return false;
+        }
+        // The decision of upstream crates is read from their metadata.
+        self.tcx.cross_crate_inlinable(callee.def_id())
+    }
+
     /// Returns inlining decision that is based on the examination of callee MIR body.
     /// Assumes that codegen attributes have been checked for compatibility already.
     #[instrument(level = "debug", skip(self, callee_body))]
@@ -339,7 +369,9 @@ fn check_mir_body(
     ) -> Result<(), &'static str> {
         let tcx = self.tcx;
 
-        let mut threshold = if callee_attrs.requests_inline() {
+        let mut threshold = if callee_attrs.requests_inline()
+            || self.is_cross_crate_inlinable(callsite, callee_attrs)
+        {
             self.tcx.sess.opts.debugging_opts.inline_mir_hint_threshold.unwrap_or(100)
         } else {
             self.tcx.sess.opts.debugging_opts.inline_mir_threshold.unwrap_or(50)
@@ -0,0 +1,94 @@
+use rustc_attr::InlineAttr;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::{Rvalue, StatementKind, TerminatorKind};
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::CrateType;
+
+/// The number of statements up to which a function is considered tiny enough to be inlined across
+/// crates without being marked `#[inline]`.
+const CROSS_CRATE_INLINE_THRESHOLD: usize = 10;
+
+/// Returns whether the optimized MIR of a function that is neither generic nor `#[inline]` should
+/// be encoded in the crate metadata anyway, so that the MIR inliner of downstream crates can inline
+/// it. This is the case for tiny leaf functions, like the getters that are often not marked
+/// `#[inline]`.
+///
+/// Leaf functions don't call other functions, so inlining them downstream can only require the
+/// symbols of the statics they use, which are made reachable just like those used by `#[inline]`
+/// functions.
+///
+/// The decision is encoded in the crate metadata, as downstream crates can't tell these functions
+/// apart from the ones whose MIR is only encoded because of `-Zalways-encode-mir`.
+///
+/// This works with incremental compilation: the inliner reads the MIR of the callee through the
+/// `instance_mir` query while computing the `optimized_mir` of the caller, so the caller depends on
+/// the callee's MIR in the dep graph and is optimized and codegened again when that MIR changes.
+crate fn cross_crate_inlinable(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    let def_id = def_id.expect_local();
+
+    // MIR is only encoded for crates that are codegened.
+    if !tcx.sess.opts.output_types.should_codegen() {
+        return false;
+    }
+
+    // Only libraries are compiled against by other crates. Checking the functions of any other
+    // crate would needlessly force their optimized MIR while computing the reachable set.
+    if !tcx.sess.crate_types().iter().any(|ty| matches!(ty, CrateType::Rlib | CrateType::Dylib)) {
+        return false;
+    }
+
+    if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+        return false;
+    }
+
+    // The MIR of generic and `#[inline]` functions is available downstream anyway.
+    if tcx.generics_of(def_id).requires_monomorphization(tcx) {
+        return false;
+    }
+    let attrs = tcx.codegen_fn_attrs(def_id);
+    if attrs.inline != InlineAttr::None || attrs.contains_extern_indicator() {
+        return false;
+    }
+    if attrs.flags.intersects(CodegenFnAttrFlags::NAKED | CodegenFnAttrFlags::COLD) {
+        return false;
+    }
+
+    let body = tcx.optimized_mir(def_id);
+    let mut statements = 0;
+    for data in body.basic_blocks() {
+        for statement in &data.statements {
+            match statement.kind {
+                StatementKind::StorageLive(_)
+                | StatementKind::StorageDead(_)
+                | StatementKind::Nop => {}
+                StatementKind::LlvmInlineAsm(_) => return false,
+                StatementKind::Assign(box (_, Rvalue::ThreadLocalRef(_))) => return false,
+                _ => statements += 1,
+            }
+        }
+        if statements > CROSS_CRATE_INLINE_THRESHOLD {
+            return false;
+        }
+
+        match data.terminator().kind {
+            TerminatorKind::Call { .. }
+            | TerminatorKind::Drop { .. }
+            | TerminatorKind::DropAndReplace { .. }
+            | TerminatorKind::InlineAsm { .. }
+            | TerminatorKind::Yield { .. }
+            | TerminatorKind::GeneratorDrop => return false,
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::SwitchInt { .. }
+            | TerminatorKind::Resume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::Assert { .. }
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. } => {}
+        }
+    }
+    true
+}
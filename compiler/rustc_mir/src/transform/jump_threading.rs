@@ -0,0 +1,326 @@
+//! Jump threading.
+//!
+//! Code using `?`, `match`es on `Option` or `Result` and boolean flags often switches on a value
+//! that is already known along the edge that leads to the switch:
+//!
+//! ```rust,ignore (example)
+//! bb1: {
+//!     ((_3 as Some).0: u32) = move _4;
+//!     discriminant(_3) = 1;
+//!     goto -> bb3;
+//! }
+//!
+//! bb3: {
+//!     _5 = discriminant(_3);
+//!     switchInt(move _5) -> [0_isize: bb4, 1_isize: bb5, otherwise: bb6];
+//! }
+//! ```
+//!
+//! This pass tracks the constant values and enum discriminants that are known at the end of each
+//! block, and on each edge of a `SwitchInt`, the value being switched on. If that is enough to
+//! tell which successor a `SwitchInt` in the target of an edge continues to, the edge is retargeted
+//! straight to that successor, through a copy of the statements of the skipped block:
+//!
+//! ```rust,ignore (example)
+//! bb1: {
+//!     ((_3 as Some).0: u32) = move _4;
+//!     discriminant(_3) = 1;
+//!     goto -> bb7;
+//! }
+//!
+//! bb7: {
+//!     _5 = discriminant(_3);
+//!     goto -> bb5;
+//! }
+//! ```
+//!
+//! Values are only tracked within a block and along a single edge, so this pass is cheap enough to
+//! run on every body. Locals that have their address taken are never tracked, since they may be
+//! modified through a pointer at any time.
+
+use crate::transform::MirPass;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{ParamEnv, TyCtxt};
+
+use super::dest_prop::ever_borrowed_locals;
+use super::simplify::simplify_cfg;
+
+/// Skipped blocks with more statements than this are not duplicated.
+const MAX_DUPLICATED_STATEMENTS: usize = 8;
+
+pub struct JumpThreading;
+
+impl<'tcx> MirPass<'tcx> for JumpThreading {
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        if tcx.sess.mir_opt_level() < 4 {
+            return;
+        }
+        trace!("Running JumpThreading on {:?}", body.source);
+
+        let mut finder = ThreadingFinder {
+            tcx,
+            body,
+            param_env: tcx.param_env_reveal_all_normalized(body.source.def_id()),
+            tracked: {
+                let mut tracked = BitSet::new_filled(body.local_decls.len());
+                tracked.subtract(&ever_borrowed_locals(body));
+                tracked
+            },
+            opportunities: Vec::new(),
+        };
+        finder.find_opportunities();
+        let opportunities = finder.opportunities;
+        if opportunities.is_empty() {
+            return;
+        }
+
+        for opportunity in opportunities {
+            debug!("threading {:?}", opportunity);
+            let skipped = &body.basic_blocks()[opportunity.skipped];
+            let target = if skipped.statements.is_empty() {
+                opportunity.new_target
+            } else {
+                let source_info = skipped.terminator().source_info;
+                let copy = BasicBlockData {
+                    statements: skipped.statements.clone(),
+                    terminator: Some(Terminator {
+                        source_info,
+                        kind: TerminatorKind::Goto { target: opportunity.new_target },
+                    }),
+                    is_cleanup: skipped.is_cleanup,
+                };
+                body.basic_blocks_mut().push(copy)
+            };
+
+            let terminator = body.basic_blocks_mut()[opportunity.block].terminator_mut();
+            match (&mut terminator.kind, opportunity.edge) {
+                (TerminatorKind::Goto { target: goto_target }, None) => *goto_target = target,
+                (TerminatorKind::SwitchInt { targets, .. }, Some(index)) => {
+                    targets.all_targets_mut()[index] = target
+                }
+                (kind, _) => bug!("unexpected terminator {:?} in jump threading", kind),
+            }
+        }
+
+        // The skipped blocks may be unreachable now.
+        simplify_cfg(tcx, body);
+    }
+}
+
+#[derive(Debug)]
+struct Opportunity {
+    /// The block whose terminator is retargeted.
+    block: BasicBlock,
+    /// The index of the retargeted `SwitchInt` target, or `None` for a `Goto`.
+    edge: Option<usize>,
+    /// The block that is no longer jumped to, and whose statements are copied.
+    skipped: BasicBlock,
+    /// The successor of `skipped` that is known to be taken.
+    new_target: BasicBlock,
+}
+
+/// The values known at some point of a block.
+#[derive(Clone, Default, Debug)]
+struct State {
+    /// The bits of locals holding a scalar.
+    values: FxHashMap<Local, u128>,
+    /// The discriminant values of locals holding an enum.
+    discriminants: FxHashMap<Local, u128>,
+    /// Locals holding the discriminant of another local, even if its value isn't known.
+    discriminant_of: FxHashMap<Local, Local>,
+}
+
+impl State {
+    fn forget(&mut self, local: Local) {
+        self.values.remove(&local);
+        self.discriminants.remove(&local);
+        self.discriminant_of.remove(&local);
+        self.discriminant_of.retain(|_, enum_local| *enum_local != local);
+    }
+
+    /// Records the value of `local` on an edge of a `SwitchInt` on it.
+    fn switched_on(&mut self, local: Local, value: u128) {
+        self.values.insert(local, value);
+        if let Some(&enum_local) = self.discriminant_of.get(&local) {
+            self.discriminants.insert(enum_local, value);
+        }
+    }
+}
+
+struct ThreadingFinder<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    body: &'a Body<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    /// The locals whose values can be tracked.
+    tracked: BitSet<Local>,
+    opportunities: Vec<Opportunity>,
+}
+
+impl<'a, 'tcx> ThreadingFinder<'a, 'tcx> {
+    fn find_opportunities(&mut self) {
+        let body = self.body;
+        for (block, data) in body.basic_blocks().iter_enumerated() {
+            match data.terminator().kind {
+                TerminatorKind::Goto { target } => {
+                    let state = self.state_at_end(data);
+                    self.try_thread(block, None, target, state);
+                }
+                TerminatorKind::SwitchInt { ref discr, ref targets, .. } => {
+                    let state = self.state_at_end(data);
+                    let switched = discr.place().and_then(|place| self.tracked_local(place));
+                    for (index, (value, target)) in targets.iter().enumerate() {
+                        let mut state = state.clone();
+                        if let Some(local) = switched {
+                            state.switched_on(local, value);
+                        }
+                        self.try_thread(block, Some(index), target, state);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn try_thread(
+        &mut self,
+        block: BasicBlock,
+        edge: Option<usize>,
+        skipped: BasicBlock,
+        mut state: State,
+    ) {
+        let body = self.body;
+        let skipped_data = &body.basic_blocks()[skipped];
+        if skipped == block || skipped_data.statements.len() > MAX_DUPLICATED_STATEMENTS {
+            return;
+        }
+        let (discr, targets) = match skipped_data.terminator().kind {
+            TerminatorKind::SwitchInt { ref discr, ref targets, .. } => (discr, targets),
+            _ => return,
+        };
+        let local = match discr.place().and_then(|place| self.tracked_local(place)) {
+            Some(local) => local,
+            None => return,
+        };
+
+        for statement in &skipped_data.statements {
+            self.apply_statement(&mut state, statement);
+        }
+        let value = match state.values.get(&local) {
+            Some(&value) => value,
+            None => return,
+        };
+        let new_target = targets
+            .iter()
+            .find(|&(target_value, _)| target_value == value)
+            .map_or_else(|| targets.otherwise(), |(_, target)| target);
+
+        if !self.tcx.consider_optimizing(|| {
+            format!("JumpThreading {:?} -> {:?} -> {:?}", block, skipped, new_target)
+        }) {
+            return;
+        }
+        self.opportunities.push(Opportunity { block, edge, skipped, new_target });
+    }
+
+    fn state_at_end(&self, data: &BasicBlockData<'tcx>) -> State {
+        let mut state = State::default();
+        for statement in &data.statements {
+            self.apply_statement(&mut state, statement);
+        }
+        state
+    }
+
+    fn apply_statement(&self, state: &mut State, statement: &Statement<'tcx>) {
+        let mut known = None;
+        if let StatementKind::Assign(box (place, ref rvalue)) = statement.kind {
+            if let Some(local) = self.tracked_local(place) {
+                known = self.eval_rvalue(state, local, rvalue);
+            }
+        }
+
+        let mut written = WrittenLocals(Vec::new());
+        written.visit_statement(statement, Location::START);
+        for local in written.0 {
+            state.forget(local);
+        }
+
+        match statement.kind {
+            StatementKind::Assign(box (place, ref rvalue)) => {
+                if let (Some(local), Some(value)) = (place.as_local(), known) {
+                    state.values.insert(local, value);
+                }
+                if let (Some(local), Rvalue::Discriminant(enum_place)) = (place.as_local(), rvalue)
+                {
+                    if let Some(enum_local) = self.tracked_local(*enum_place) {
+                        state.discriminant_of.insert(local, enum_local);
+                    }
+                }
+            }
+            StatementKind::SetDiscriminant { box place, variant_index } => {
+                if let Some(local) = self.tracked_local(place) {
+                    let ty = self.body.local_decls[local].ty;
+                    if let Some(discr) = ty.discriminant_for_variant(self.tcx, variant_index) {
+                        state.discriminants.insert(local, discr.val);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the value `rvalue` evaluates to in `state`, if it is known.
+    fn eval_rvalue(&self, state: &State, local: Local, rvalue: &Rvalue<'tcx>) -> Option<u128> {
+        match *rvalue {
+            Rvalue::Use(ref operand) => self.eval_operand(state, operand),
+            Rvalue::Discriminant(place) => {
+                state.discriminants.get(&self.tracked_local(place)?).copied()
+            }
+            // Equal floats don't necessarily have the same bits.
+            Rvalue::BinaryOp(op @ (BinOp::Eq | BinOp::Ne), box (ref lhs, ref rhs))
+                if !lhs.ty(self.body, self.tcx).is_floating_point() =>
+            {
+                let equal = self.eval_operand(state, lhs)? == self.eval_operand(state, rhs)?;
+                Some(u128::from(equal == (op == BinOp::Eq)))
+            }
+            Rvalue::UnaryOp(UnOp::Not, ref operand)
+                if self.body.local_decls[local].ty.is_bool() =>
+            {
+                Some(self.eval_operand(state, operand)? ^ 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn eval_operand(&self, state: &State, operand: &Operand<'tcx>) -> Option<u128> {
+        match *operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                state.values.get(&self.tracked_local(place)?).copied()
+            }
+            Operand::Constant(ref constant) => {
+                let ty = constant.ty();
+                if !(ty.is_integral() || ty.is_bool() || ty.is_char()) {
+                    return None;
+                }
+                constant.literal.try_eval_bits(self.tcx, self.param_env, ty)
+            }
+        }
+    }
+
+    fn tracked_local(&self, place: Place<'tcx>) -> Option<Local> {
+        place.as_local().filter(|&local| self.tracked.contains(local))
+    }
+}
+
+/// Collects the locals a statement may change the value or storage of.
+struct WrittenLocals(Vec<Local>);
+
+impl<'tcx> Visitor<'tcx> for WrittenLocals {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _location: Location) {
+        if context.is_mutating_use() || context.is_storage_marker() {
+            self.0.push(local);
+        }
+    }
+}
@@ -33,8 +33,10 @@
 pub mod elaborate_drops;
 pub mod function_item_references;
 pub mod generator;
+pub mod gvn;
 pub mod inline;
 pub mod instcombine;
+pub mod jump_threading;
 pub mod lower_intrinsics;
 pub mod match_branches;
 pub mod multiple_return_terminators;
@@ -51,6 +53,7 @@
 pub mod simplify_branches;
 pub mod simplify_comparison_integral;
 pub mod simplify_try;
+pub mod sroa;
 pub mod uninhabited_enum_branching;
 pub mod unreachable_prop;
 pub mod validate;
@@ -501,10 +504,13 @@ fn run_optimization_passes<'tcx>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
         &instcombine::InstCombine,
         &const_prop::ConstProp,
         &simplify_branches::SimplifyBranches::new("after-const-prop"),
+        &gvn::GlobalValueNumbering,
         &early_otherwise_branch::EarlyOtherwiseBranch,
         &simplify_comparison_integral::SimplifyComparisonIntegral,
         &simplify_try::SimplifyArmIdentity,
         &simplify_try::SimplifyBranchSame,
+        &jump_threading::JumpThreading,
+        &sroa::ScalarReplacementOfAggregates,
         &dest_prop::DestinationPropagation,
         &simplify_branches::SimplifyBranches::new("final"),
         &remove_noop_landing_pads::RemoveNoopLandingPads,
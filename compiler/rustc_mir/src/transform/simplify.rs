@@ -371,6 +371,11 @@ fn save_unreachable_coverage(
     for dead_block in basic_blocks.raw[first_dead_block..].iter() {
         for statement in dead_block.statements.iter() {
             if let StatementKind::Coverage(coverage) = &statement.kind {
+                // A branch region in a dead block has no counters left to report; the code
+                // regions of the block already report it as uncovered.
+                if matches!(coverage.kind, CoverageKind::Branch { .. }) {
+                    continue;
+                }
                 if let Some(code_region) = &coverage.code_region {
                     dropped_coverage.push((statement.source_info, code_region.clone()));
                 }
@@ -0,0 +1,298 @@
+//! Scalar replacement of aggregates.
+//!
+//! Numeric code in particular tends to build small tuples and structs in locals only to read their
+//! fields again later. Each such local ends up as a single stack slot in codegen, even though none
+//! of its fields need to be in memory. This pass splits such a local into one new local per field
+//! that is used, and replaces every `local.field` place with the new local for that field.
+//!
+//! A local is only split if it is a tuple or a struct, and if it is exclusively accessed through
+//! field projections. Any use of the local as a whole, like moving it, dropping it or passing it to
+//! a function, keeps it intact. So does taking a reference or raw pointer to the local or to any
+//! part of it, since the fields are no longer laid out next to each other once they are split.
+//!
+//! Locals with nested aggregates are split repeatedly, until none of the new locals can be split
+//! any further.
+//!
+//! Debuginfo that describes a single field of a local, like a captured variable of a closure, is
+//! moved over to the new local for that field. Debuginfo can however only describe a user variable
+//! that lives in a single place, so user variables that debuginfo refers to as a whole are never
+//! split when debuginfo is emitted. Without debuginfo, the `VarDebugInfo` of such a variable only
+//! names it in MIR dumps, so the variable is split and its entry is dropped.
+//!
+//! The pass runs whenever MIR optimizations are enabled, and can be toggled with `-Zmir-sroa`.
+//!
+//! It is recommended to run `SimplifyLocals` some time after this pass, as it leaves the split
+//! locals behind without any uses.
+
+use crate::dataflow::impls::MaybeBorrowedLocals;
+use crate::dataflow::{self, Analysis};
+use crate::transform::MirPass;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::visit::{MutVisitor, NonUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::{
+    Body, Field, Local, LocalDecl, LocalKind, Location, Place, PlaceElem, Statement, StatementKind,
+    Terminator, VarDebugInfoContents,
+};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_session::config::DebugInfo;
+
+pub struct ScalarReplacementOfAggregates;
+
+fn is_enabled(tcx: TyCtxt<'_>) -> bool {
+    if let Some(enabled) = tcx.sess.opts.debugging_opts.mir_sroa {
+        return enabled;
+    }
+
+    tcx.sess.mir_opt_level() >= 1
+}
+
+impl<'tcx> MirPass<'tcx> for ScalarReplacementOfAggregates {
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        if !is_enabled(tcx) {
+            return;
+        }
+
+        let def_id = body.source.def_id();
+        if !tcx.consider_optimizing(|| format!("ScalarReplacementOfAggregates {:?}", def_id)) {
+            return;
+        }
+
+        // Splitting a local can produce new locals that are aggregates themselves.
+        while split_locals(tcx, body) {}
+    }
+}
+
+/// Splits all eligible aggregate locals of `body` into per-field locals. Returns `true` if any
+/// local was split.
+fn split_locals<'tcx>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) -> bool {
+    let candidates = find_candidates(tcx, body);
+    if candidates.is_empty() {
+        return false;
+    }
+    debug!("splitting {:?} in {:?}", candidates, body.source.def_id());
+
+    // Candidates are only ever accessed as a whole by debuginfo if no debuginfo is emitted, so
+    // those entries are never used and can just be dropped.
+    body.var_debug_info.retain(|var_debug_info| match var_debug_info.value {
+        VarDebugInfoContents::Place(place) => {
+            !(candidates.contains(place.local) && place.projection.is_empty())
+        }
+        VarDebugInfoContents::Const(_) => true,
+    });
+
+    let replacements = create_field_locals(body, &candidates);
+
+    // Storage statements of a split local apply to all of its fields. The new locals were created
+    // in field order, so sorting them keeps the expanded statements in that order.
+    let mut field_locals: FxHashMap<Local, Vec<Local>> = FxHashMap::default();
+    for (&(local, _), &new_local) in &replacements {
+        field_locals.entry(local).or_default().push(new_local);
+    }
+    for new_locals in field_locals.values_mut() {
+        new_locals.sort();
+    }
+
+    for data in body.basic_blocks_mut() {
+        let splits_storage = data.statements.iter().any(|statement| {
+            storage_local(statement).map_or(false, |local| candidates.contains(local))
+        });
+        if !splits_storage {
+            continue;
+        }
+
+        let mut statements = Vec::with_capacity(data.statements.len());
+        for statement in data.statements.drain(..) {
+            match storage_local(&statement) {
+                Some(local) if candidates.contains(local) => {
+                    let new_locals = field_locals.get(&local).map_or(&[][..], |l| &l[..]);
+                    for &new_local in new_locals {
+                        let kind = match statement.kind {
+                            StatementKind::StorageLive(_) => StatementKind::StorageLive(new_local),
+                            _ => StatementKind::StorageDead(new_local),
+                        };
+                        statements.push(Statement { source_info: statement.source_info, kind });
+                    }
+                }
+                _ => statements.push(statement),
+            }
+        }
+        data.statements = statements;
+    }
+
+    ReplaceFieldPlaces { tcx, replacements }.visit_body(body);
+    true
+}
+
+fn storage_local(statement: &Statement<'_>) -> Option<Local> {
+    match statement.kind {
+        StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => Some(local),
+        _ => None,
+    }
+}
+
+/// Returns the locals that are aggregates only ever accessed through their fields.
+fn find_candidates<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> BitSet<Local> {
+    let mut uses = LocalUses {
+        field_uses: BitSet::new_empty(body.local_decls.len()),
+        whole_uses: BitSet::new_empty(body.local_decls.len()),
+        debuginfo_uses: BitSet::new_empty(body.local_decls.len()),
+    };
+    uses.visit_body(body);
+
+    let mut candidates = uses.field_uses;
+    candidates.subtract(&uses.whole_uses);
+    if tcx.sess.opts.debuginfo != DebugInfo::None {
+        candidates.subtract(&uses.debuginfo_uses);
+    }
+    if candidates.is_empty() {
+        return candidates;
+    }
+
+    // Once a field is split off into its own local, a pointer to it no longer points into the
+    // aggregate, so none of the fields may ever be borrowed.
+    let borrowed = MaybeBorrowedLocals::all_borrows()
+        .into_engine(tcx, body)
+        .pass_name("scalar_replacement_of_aggregates")
+        .iterate_to_fixpoint();
+    let mut ever_borrowed = EverBorrowedLocals(BitSet::new_empty(body.local_decls.len()));
+    borrowed.visit_with(body, body.basic_blocks().indices(), &mut ever_borrowed);
+    candidates.subtract(&ever_borrowed.0);
+
+    for local in body.local_decls.indices() {
+        if !candidates.contains(local) {
+            continue;
+        }
+        let eligible = matches!(body.local_kind(local), LocalKind::Var | LocalKind::Temp)
+            && is_splittable_ty(tcx, body.local_decls[local].ty);
+        if !eligible {
+            candidates.remove(local);
+        }
+    }
+    candidates
+}
+
+/// Whether the fields of a value of type `ty` can be stored independently of each other.
+fn is_splittable_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::Tuple(..) => true,
+        // Fields of unions overlap and SIMD vectors have to be passed around as a whole. A
+        // destructor would observe the aggregate as a whole, too.
+        ty::Adt(adt, _) => adt.is_struct() && !adt.repr.simd() && !adt.has_dtor(tcx),
+        _ => false,
+    }
+}
+
+/// Creates a new local for every field of a candidate that is used.
+fn create_field_locals<'tcx>(
+    body: &mut Body<'tcx>,
+    candidates: &BitSet<Local>,
+) -> FxHashMap<(Local, Field), Local> {
+    let mut fields = FieldCollector { candidates, fields: Vec::new() };
+    fields.visit_body(body);
+    // Keep the order of the new locals deterministic.
+    fields.fields.sort_by_key(|&(local, field, _)| (local, field));
+    fields.fields.dedup_by_key(|&mut (local, field, _)| (local, field));
+
+    let mut replacements = FxHashMap::default();
+    for (local, field, ty) in fields.fields {
+        let source_info = body.local_decls[local].source_info;
+        let new_local = body.local_decls.push(LocalDecl::with_source_info(ty, source_info));
+        replacements.insert((local, field), new_local);
+    }
+    replacements
+}
+
+/// Records how each local is used: through a field projection, or as a whole.
+struct LocalUses {
+    field_uses: BitSet<Local>,
+    whole_uses: BitSet<Local>,
+    debuginfo_uses: BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for LocalUses {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, _location: Location) {
+        match (place.projection.first(), context) {
+            (Some(PlaceElem::Field(..)), _) => {
+                self.field_uses.insert(place.local);
+            }
+            (_, PlaceContext::NonUse(NonUseContext::VarDebugInfo)) => {
+                self.debuginfo_uses.insert(place.local);
+            }
+            _ => {
+                self.whole_uses.insert(place.local);
+            }
+        }
+
+        for elem in place.projection {
+            if let PlaceElem::Index(local) = elem {
+                self.whole_uses.insert(local);
+            }
+        }
+    }
+}
+
+/// Collects the fields of the candidates that are used, along with their types.
+struct FieldCollector<'a, 'tcx> {
+    candidates: &'a BitSet<Local>,
+    fields: Vec<(Local, Field, Ty<'tcx>)>,
+}
+
+impl<'tcx> Visitor<'tcx> for FieldCollector<'_, 'tcx> {
+    fn visit_place(&mut self, place: &Place<'tcx>, _context: PlaceContext, _location: Location) {
+        if let Some(&PlaceElem::Field(field, ty)) = place.projection.first() {
+            if self.candidates.contains(place.local) {
+                self.fields.push((place.local, field, ty));
+            }
+        }
+    }
+}
+
+/// Accumulates all locals that are borrowed at any point in the body.
+struct EverBorrowedLocals(BitSet<Local>);
+
+impl<'mir, 'tcx> dataflow::ResultsVisitor<'mir, 'tcx> for EverBorrowedLocals {
+    type FlowState = BitSet<Local>;
+
+    fn visit_statement_after_primary_effect(
+        &mut self,
+        state: &Self::FlowState,
+        _statement: &'mir Statement<'tcx>,
+        _location: Location,
+    ) {
+        self.0.union(state);
+    }
+
+    fn visit_terminator_after_primary_effect(
+        &mut self,
+        state: &Self::FlowState,
+        _terminator: &'mir Terminator<'tcx>,
+        _location: Location,
+    ) {
+        self.0.union(state);
+    }
+}
+
+/// Replaces `local.field` with the new local for that field.
+struct ReplaceFieldPlaces<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    replacements: FxHashMap<(Local, Field), Local>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for ReplaceFieldPlaces<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_place(&mut self, place: &mut Place<'tcx>, context: PlaceContext, location: Location) {
+        if let Some(&PlaceElem::Field(field, _)) = place.projection.first() {
+            if let Some(&new_local) = self.replacements.get(&(place.local, field)) {
+                *place = Place {
+                    local: new_local,
+                    projection: self.tcx.intern_place_elems(&place.projection[1..]),
+                };
+            }
+        }
+        self.super_place(place, context, location);
+    }
+}
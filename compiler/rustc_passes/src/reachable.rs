@@ -20,8 +20,8 @@
 use rustc_target::spec::abi::Abi;
 
 // Returns true if the given item must be inlined because it may be
-// monomorphized or it was marked with `#[inline]`. This will only return
-// true for functions.
+// monomorphized, it was marked with `#[inline]` or it is small enough to be
+// inlinable across crates anyway. This will only return true for functions.
 fn item_might_be_inlined(tcx: TyCtxt<'tcx>, item: &hir::Item<'_>, attrs: &CodegenFnAttrs) -> bool {
     if attrs.requests_inline() {
         return true;
@@ -29,10 +29,15 @@ fn item_might_be_inlined(tcx: TyCtxt<'tcx>, item: &hir::Item<'_>, attrs: &Codege
 
     match item.kind {
         hir::ItemKind::Fn(ref sig, ..) if sig.header.is_const() => true,
-        hir::ItemKind::Impl { .. } | hir::ItemKind::Fn(..) => {
+        hir::ItemKind::Impl { .. } => {
             let generics = tcx.generics_of(item.def_id);
             generics.requires_monomorphization(tcx)
         }
+        hir::ItemKind::Fn(..) => {
+            let generics = tcx.generics_of(item.def_id);
+            generics.requires_monomorphization(tcx)
+                || tcx.cross_crate_inlinable(item.def_id.to_def_id())
+        }
         _ => false,
     }
 }
@@ -47,6 +52,9 @@ fn method_might_be_inlined(
     if codegen_fn_attrs.requests_inline() || generics.requires_monomorphization(tcx) {
         return true;
     }
+    if tcx.cross_crate_inlinable(impl_item.def_id.to_def_id()) {
+        return true;
+    }
     if let hir::ImplItemKind::Fn(method_sig, _) = &impl_item.kind {
         if method_sig.header.is_const() {
             return true;
@@ -238,6 +238,14 @@ fn alloc_self_profile_query_strings_for_query_cache<'tcx, C>(
     tcx.prof.with_profiler(|profiler| {
         let event_id_builder = profiler.event_id_builder();
 
+        // The summary report only needs to know which query each invocation
+        // belongs to, not the query keys.
+        if profiler.report_enabled() {
+            query_cache.iter_results(&mut |_, _, i| {
+                profiler.map_query_invocation_id_to_query_name(i.into(), query_name);
+            });
+        }
+
         // Walk the entire query cache and allocate the appropriate
         // string representations. Each cache entry is uniquely
         // identified by its dep_node_index.
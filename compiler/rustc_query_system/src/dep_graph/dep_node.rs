@@ -85,11 +85,12 @@ pub fn construct<Ctxt, Key>(tcx: Ctxt, kind: K, arg: &Key) -> DepNode<K>
         let hash = arg.to_fingerprint(tcx);
         let dep_node = DepNode { kind, hash: hash.into() };
 
-        #[cfg(debug_assertions)]
-        {
-            if !kind.can_reconstruct_query_key()
-                && (tcx.sess().opts.debugging_opts.incremental_info
-                    || tcx.sess().opts.debugging_opts.query_dep_graph)
+        if !kind.can_reconstruct_query_key() {
+            let opts = &tcx.sess().opts.debugging_opts;
+            // `-Z incremental-explain` needs to name the nodes it reports in
+            // all builds, the other options are only for debugging the compiler.
+            if opts.incremental_explain
+                || cfg!(debug_assertions) && (opts.incremental_info || opts.query_dep_graph)
             {
                 tcx.dep_graph().register_dep_node_debug_str(dep_node, || arg.to_debug_str(tcx));
             }
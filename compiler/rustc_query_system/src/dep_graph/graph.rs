@@ -13,6 +13,7 @@
 
 use parking_lot::{Condvar, Mutex};
 use smallvec::{smallvec, SmallVec};
+use std::cmp::Reverse;
 use std::collections::hash_map::Entry;
 use std::hash::Hash;
 use std::marker::PhantomData;
@@ -95,6 +96,15 @@ struct DepGraphData<K: DepKind> {
     previous_work_products: FxHashMap<WorkProductId, WorkProduct>,
 
     dep_node_debug: Lock<FxHashMap<DepNode<K>, String>>,
+
+    /// For `-Z incremental-explain`: the first dependency that prevented each
+    /// node of the previous session from being marked green, which is why
+    /// the node had to be re-executed.
+    invalidations: Option<Lock<FxHashMap<SerializedDepNodeIndex, SerializedDepNodeIndex>>>,
+
+    /// For `-Z incremental-explain`: the tracked command-line options that
+    /// changed since the previous session, which discarded all of its nodes.
+    changed_options: Vec<String>,
 }
 
 pub fn hash_result<HashCtxt, R>(hcx: &mut HashCtxt, result: &R) -> Option<Fingerprint>
@@ -115,6 +125,8 @@ pub fn new(
         encoder: FileEncoder,
         record_graph: bool,
         record_stats: bool,
+        record_invalidations: bool,
+        changed_options: Vec<String>,
     ) -> DepGraph<K> {
         let prev_graph_node_count = prev_graph.node_count();
 
@@ -139,6 +151,8 @@ pub fn new(
                 emitting_diagnostics_cond_var: Condvar::new(),
                 previous: prev_graph,
                 colors: DepNodeColorMap::new(prev_graph_node_count),
+                invalidations: if record_invalidations { Some(Default::default()) } else { None },
+                changed_options,
             })),
             virtual_dep_node_index: Lrc::new(AtomicU32::new(0)),
         }
@@ -427,6 +441,22 @@ pub fn prev_fingerprint_of(&self, dep_node: &DepNode<K>) -> Option<Fingerprint>
         self.data.as_ref().unwrap().previous.fingerprint_of(dep_node)
     }
 
+    /// Returns a cache for looking up the nodes of the previous session that
+    /// other nodes transitively depended on and that `is_input` selects, as
+    /// long as there are at most `limit` of them. See
+    /// `PreviousInputs::inputs_of`.
+    pub fn previous_inputs<F>(&self, limit: usize, is_input: F) -> PreviousInputs<'_, K, F>
+    where
+        F: FnMut(&DepNode<K>) -> bool,
+    {
+        PreviousInputs {
+            previous: self.data.as_ref().map(|data| &data.previous),
+            limit,
+            is_input,
+            inputs: FxHashMap::default(),
+        }
+    }
+
     /// Checks whether a previous work product exists for `v` and, if
     /// so, return the path that leads to it. Used to skip doing work.
     pub fn previous_work_product(&self, v: &WorkProductId) -> Option<WorkProduct> {
@@ -648,7 +678,12 @@ fn try_mark_previous_green<Ctxt: QueryContext<DepKind = K>>(
         let prev_deps = data.previous.edge_targets_from(prev_dep_node_index);
 
         for &dep_dep_node_index in prev_deps {
-            self.try_mark_parent_green(tcx, data, dep_dep_node_index, dep_node)?
+            if self.try_mark_parent_green(tcx, data, dep_dep_node_index, dep_node).is_none() {
+                if let Some(invalidations) = &data.invalidations {
+                    invalidations.lock().entry(prev_dep_node_index).or_insert(dep_dep_node_index);
+                }
+                return None;
+            }
         }
 
         // If we got here without hitting a `return` that means that all
@@ -809,6 +844,108 @@ pub fn print_incremental_info(&self) {
         }
     }
 
+    /// Prints, for each input that changed since the previous session, which
+    /// nodes had to be re-executed because of it. This requires the
+    /// invalidations to have been recorded for `-Z incremental-explain`.
+    pub fn print_invalidation_report(&self) {
+        let data = match &self.data {
+            Some(data) => data,
+            None => return,
+        };
+        let invalidations = match &data.invalidations {
+            Some(invalidations) => invalidations.lock(),
+            None => return,
+        };
+
+        // Follow the chain of first red dependencies back to the node that
+        // started it, which is an input that changed. The root of every node
+        // on a chain is memoized, so that each chain is only walked once.
+        let mut root_of: FxHashMap<SerializedDepNodeIndex, SerializedDepNodeIndex> =
+            FxHashMap::default();
+        let mut chain = Vec::new();
+        for &node in invalidations.keys() {
+            let mut current = node;
+            let root = loop {
+                if let Some(&root) = root_of.get(&current) {
+                    break root;
+                }
+                match invalidations.get(&current) {
+                    // A chain can't be longer than the number of invalidated
+                    // nodes, which guards against cycles.
+                    Some(&cause) if chain.len() < invalidations.len() => {
+                        chain.push(current);
+                        current = cause;
+                    }
+                    _ => break current,
+                }
+            };
+            for node in chain.drain(..) {
+                root_of.insert(node, root);
+            }
+        }
+
+        let mut invalidated_by_root: FxHashMap<_, Vec<_>> = FxHashMap::default();
+        for (&node, &root) in root_of.iter() {
+            invalidated_by_root.entry(root).or_default().push(node);
+        }
+
+        let mut roots: Vec<_> = invalidated_by_root.into_iter().collect();
+        roots.sort_by_key(|(_, invalidated)| Reverse(invalidated.len()));
+
+        eprintln!("[incremental]");
+        eprintln!("[incremental] Re-executed Nodes By Changed Input");
+        eprintln!("[incremental] --------------------------------");
+        if !data.changed_options.is_empty() {
+            // The previous session was discarded as a whole, so there are no
+            // invalidated nodes to attribute to these options.
+            let options: Vec<_> =
+                data.changed_options.iter().map(|option| format!("`{}`", option)).collect();
+            eprintln!(
+                "[incremental] command-line options {} changed, which invalidated \
+                 all nodes of the previous session",
+                options.join(", ")
+            );
+        }
+        eprintln!(
+            "[incremental] {} nodes had to be re-executed because of {} inputs",
+            invalidations.len(),
+            roots.len()
+        );
+        for (root, invalidated) in roots {
+            let reason = match data.colors.get(root) {
+                Some(DepNodeColor::Red) => "changed",
+                // The node is not red if it could not be recomputed on its
+                // own, e.g. because the item it belongs to was removed.
+                _ => "could not be recomputed",
+            };
+            eprintln!("[incremental]");
+            eprintln!(
+                "[incremental] `{:?}` {}, which invalidated {} nodes:",
+                data.previous.index_to_node(root),
+                reason,
+                invalidated.len()
+            );
+
+            let mut lines: Vec<_> = invalidated
+                .iter()
+                .map(|&node| {
+                    let node_str = format!("{:?}", data.previous.index_to_node(node));
+                    let cause = invalidations[&node];
+                    if cause == root {
+                        node_str
+                    } else {
+                        format!("{} (via `{:?}`)", node_str, data.previous.index_to_node(cause))
+                    }
+                })
+                .collect();
+            lines.sort();
+            for line in lines {
+                eprintln!("[incremental]     {}", line);
+            }
+        }
+        eprintln!("[incremental]");
+    }
+
     pub fn encode(&self, profiler: &SelfProfilerRef) -> FileEncodeResult {
         if let Some(data) = &self.data {
             data.current.encoder.steal().finish(profiler)
@@ -823,6 +960,77 @@ pub(crate) fn next_virtual_depnode_index(&self) -> DepNodeIndex {
     }
 }
 
+/// The inputs of the nodes of the previous session, computed bottom-up and
+/// cached per node, so that the dependencies shared by several nodes are only
+/// visited once.
+pub struct PreviousInputs<'a, K: DepKind, F> {
+    previous: Option<&'a SerializedDepGraph<K>>,
+    limit: usize,
+    is_input: F,
+    /// The sorted inputs of every node whose dependencies have been visited,
+    /// or `None` if it has more than `limit` of them. This bounds the size of
+    /// the cache to `limit` inputs per node.
+    inputs: FxHashMap<SerializedDepNodeIndex, Option<Vec<SerializedDepNodeIndex>>>,
+}
+
+impl<K: DepKind, F: FnMut(&DepNode<K>) -> bool> PreviousInputs<'_, K, F> {
+    /// Returns the nodes of the previous session that `dep_node` transitively
+    /// depended on and that `is_input` selects. The dependencies of a selected
+    /// node are not visited. Returns `None` if `dep_node` did not exist in the
+    /// previous session, or if it depended on more than `limit` inputs.
+    pub fn inputs_of(&mut self, dep_node: &DepNode<K>) -> Option<Vec<DepNode<K>>> {
+        let previous = self.previous?;
+        let start = previous.node_to_index_opt(dep_node)?;
+
+        // Visit the dependencies in post-order, without recursing since the
+        // dep-graph can be very deep, so that the inputs of all dependencies
+        // of a node are known by the time it is finished.
+        let mut stack = vec![(start, false)];
+        while let Some((index, dependencies_visited)) = stack.pop() {
+            if self.inputs.contains_key(&index) {
+                continue;
+            }
+            if !dependencies_visited {
+                stack.push((index, true));
+                for &dep_index in previous.edge_targets_from(index) {
+                    if !self.selects(previous, dep_index) && !self.inputs.contains_key(&dep_index) {
+                        stack.push((dep_index, false));
+                    }
+                }
+                continue;
+            }
+
+            let mut inputs = Some(Vec::new());
+            for &dep_index in previous.edge_targets_from(index) {
+                if self.selects(previous, dep_index) {
+                    if let Some(inputs) = &mut inputs {
+                        inputs.push(dep_index);
+                    }
+                } else {
+                    inputs = inputs.and_then(|mut inputs| {
+                        inputs.extend_from_slice(self.inputs[&dep_index].as_ref()?);
+                        Some(inputs)
+                    });
+                }
+            }
+            let limit = self.limit;
+            let inputs = inputs.and_then(|mut inputs| {
+                inputs.sort_unstable();
+                inputs.dedup();
+                if inputs.len() <= limit { Some(inputs) } else { None }
+            });
+            self.inputs.insert(index, inputs);
+        }
+
+        let inputs = self.inputs[&start].as_ref()?;
+        Some(inputs.iter().map(|&index| previous.index_to_node(index)).collect())
+    }
+
+    fn selects(&mut self, previous: &SerializedDepGraph<K>, index: SerializedDepNodeIndex) -> bool {
+        (self.is_input)(&previous.index_to_node(index))
+    }
+}
+
 /// A "work product" is an intermediate result that we save into the
 /// incremental directory for later re-use. The primary example are
 /// the object files that we save for each partition at code
@@ -532,6 +532,8 @@ fn load_from_disk_and_cache_in_memory<CTX, K, V: Debug>(
 
     debug_assert!(tcx.dep_context().dep_graph().is_green(dep_node));
 
+    tcx.dep_context().profiler().query_incr_cache_hit(dep_node_index.into());
+
     // First we try to load the result from the on-disk cache.
     let result = if query.cache_on_disk(tcx, &key, None) {
         let prof_timer = tcx.dep_context().profiler().incr_cache_loading();
@@ -672,6 +674,15 @@ fn force_query_with_job<C, CTX>(
 
     prof_timer.finish_with_query_invocation_id(dep_node_index.into());
 
+    // The query existed in the previous session but could not be marked green,
+    // so its result from that session was not reused.
+    let profiler = tcx.dep_context().profiler();
+    if unlikely!(profiler.enabled())
+        && tcx.dep_context().dep_graph().prev_fingerprint_of(&dep_node).is_some()
+    {
+        profiler.query_incr_cache_miss(dep_node_index.into());
+    }
+
     if unlikely!(!diagnostics.is_empty()) && dep_node.kind != DepKind::NULL {
         tcx.store_diagnostics(dep_node_index, diagnostics);
     }
@@ -733,7 +744,9 @@ fn ensure_must_run<CTX, K, V>(tcx: CTX, key: &K, query: &QueryVtable<CTX, K, V>)
             true
         }
         Some((_, dep_node_index)) => {
-            tcx.dep_context().profiler().query_cache_hit(dep_node_index.into());
+            let profiler = tcx.dep_context().profiler();
+            profiler.query_cache_hit(dep_node_index.into());
+            profiler.query_incr_cache_hit(dep_node_index.into());
             false
         }
     }
@@ -1,7 +1,16 @@
 use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::sync::Lock;
+use rustc_serialize::json::{self, Json, ToJson};
 use rustc_target::abi::{Align, Size};
 use std::cmp::{self, Ordering};
+use std::convert::TryFrom;
+
+/// The output format of `-Z print-type-sizes`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TypeSizesFormat {
+    Text,
+    Json,
+}
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -34,6 +43,37 @@ pub enum DataTypeKind {
     Closure,
 }
 
+/// Where and how the discriminant of an enum is stored.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TagInfo {
+    pub offset: u64,
+    pub size: u64,
+    pub encoding: TagEncodingInfo,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TagEncodingInfo {
+    /// The tag stores the discriminant.
+    Direct,
+    /// The tag is a niche of the fields of `dataful_variant`. The variants from
+    /// `niche_variants.0` to `niche_variants.1` are stored as the tag values
+    /// starting at `niche_start`.
+    Niche { dataful_variant: String, niche_variants: (String, String), niche_start: u128 },
+}
+
+/// The largest niche of a type, i.e. invalid values that enclosing enums can
+/// use to store their discriminant in.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NicheInfo {
+    pub offset: u64,
+    pub size: u64,
+    /// The inclusive range of valid values, which wraps around if the start
+    /// is larger than the end.
+    pub valid_range: (u128, u128),
+    /// The number of invalid values.
+    pub available: u128,
+}
+
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct TypeSizeInfo {
     pub kind: DataTypeKind,
@@ -42,6 +82,8 @@ pub struct TypeSizeInfo {
     pub overall_size: u64,
     pub packed: bool,
     pub opt_discr_size: Option<u64>,
+    pub tag: Option<TagInfo>,
+    pub niche: Option<NicheInfo>,
     pub variants: Vec<VariantInfo>,
 }
 
@@ -59,6 +101,8 @@ pub fn record_type_size<S: ToString>(
         overall_size: Size,
         packed: bool,
         opt_discr_size: Option<Size>,
+        tag: Option<TagInfo>,
+        niche: Option<NicheInfo>,
         mut variants: Vec<VariantInfo>,
     ) {
         // Sort variants so the largest ones are shown first. A stable sort is
@@ -72,12 +116,14 @@ pub fn record_type_size<S: ToString>(
             overall_size: overall_size.bytes(),
             packed,
             opt_discr_size: opt_discr_size.map(|s| s.bytes()),
+            tag,
+            niche,
             variants,
         };
         self.type_sizes.borrow_mut().insert(info);
     }
 
-    pub fn print_type_sizes(&self) {
+    pub fn print_type_sizes(&self, format: TypeSizesFormat) {
         let type_sizes = self.type_sizes.borrow();
         let mut sorted: Vec<_> = type_sizes.iter().collect();
 
@@ -91,103 +137,221 @@ pub fn print_type_sizes(&self) {
             }
         });
 
-        for info in &sorted {
-            println!(
-                "print-type-size type: `{}`: {} bytes, alignment: {} bytes",
-                info.type_description, info.overall_size, info.align
-            );
-            let indent = "    ";
+        match format {
+            TypeSizesFormat::Text => print_type_sizes_text(&sorted),
+            TypeSizesFormat::Json => {
+                let types = sorted.iter().map(|info| type_size_json(info)).collect();
+                println!("{}", Json::Array(types).pretty());
+            }
+        }
+    }
+}
 
-            let discr_size = if let Some(discr_size) = info.opt_discr_size {
-                println!("print-type-size {}discriminant: {} bytes", indent, discr_size);
-                discr_size
-            } else {
-                0
-            };
+fn print_type_sizes_text(sorted: &[&TypeSizeInfo]) {
+    for info in sorted {
+        println!(
+            "print-type-size type: `{}`: {} bytes, alignment: {} bytes",
+            info.type_description, info.overall_size, info.align
+        );
+        let indent = "    ";
+
+        let discr_size = if let Some(discr_size) = info.opt_discr_size {
+            println!("print-type-size {}discriminant: {} bytes", indent, discr_size);
+            discr_size
+        } else {
+            0
+        };
 
-            // We start this at discr_size (rather than 0) because
-            // things like C-enums do not have variants but we still
-            // want the max_variant_size at the end of the loop below
-            // to reflect the presence of the discriminant.
-            let mut max_variant_size = discr_size;
+        // We start this at discr_size (rather than 0) because
+        // things like C-enums do not have variants but we still
+        // want the max_variant_size at the end of the loop below
+        // to reflect the presence of the discriminant.
+        let mut max_variant_size = discr_size;
 
-            let struct_like = match info.kind {
-                DataTypeKind::Struct | DataTypeKind::Closure => true,
-                DataTypeKind::Enum | DataTypeKind::Union => false,
-            };
-            for (i, variant_info) in info.variants.iter().enumerate() {
-                let VariantInfo { ref name, kind: _, align: _, size, ref fields } = *variant_info;
-                let indent = if !struct_like {
-                    let name = match name.as_ref() {
-                        Some(name) => name.to_owned(),
-                        None => i.to_string(),
-                    };
-                    println!(
-                        "print-type-size {}variant `{}`: {} bytes",
-                        indent,
-                        name,
-                        size - discr_size
-                    );
-                    "        "
-                } else {
-                    assert!(i < 1);
-                    "    "
+        let struct_like = match info.kind {
+            DataTypeKind::Struct | DataTypeKind::Closure => true,
+            DataTypeKind::Enum | DataTypeKind::Union => false,
+        };
+        for (i, variant_info) in info.variants.iter().enumerate() {
+            let VariantInfo { ref name, kind: _, align: _, size, ref fields } = *variant_info;
+            let indent = if !struct_like {
+                let name = match name.as_ref() {
+                    Some(name) => name.to_owned(),
+                    None => i.to_string(),
                 };
-                max_variant_size = cmp::max(max_variant_size, size);
+                println!(
+                    "print-type-size {}variant `{}`: {} bytes",
+                    indent,
+                    name,
+                    size - discr_size
+                );
+                "        "
+            } else {
+                assert!(i < 1);
+                "    "
+            };
+            max_variant_size = cmp::max(max_variant_size, size);
 
-                let mut min_offset = discr_size;
+            let mut min_offset = discr_size;
 
-                // We want to print fields by increasing offset. We also want
-                // zero-sized fields before non-zero-sized fields, otherwise
-                // the loop below goes wrong; hence the `f.size` in the sort
-                // key.
-                let mut fields = fields.clone();
-                fields.sort_by_key(|f| (f.offset, f.size));
+            // We want to print fields by increasing offset. We also want
+            // zero-sized fields before non-zero-sized fields, otherwise
+            // the loop below goes wrong; hence the `f.size` in the sort
+            // key.
+            let mut fields = fields.clone();
+            fields.sort_by_key(|f| (f.offset, f.size));
 
-                for field in fields.iter() {
-                    let FieldInfo { ref name, offset, size, align } = *field;
+            for field in fields.iter() {
+                let FieldInfo { ref name, offset, size, align } = *field;
 
-                    if offset > min_offset {
-                        let pad = offset - min_offset;
-                        println!("print-type-size {}padding: {} bytes", indent, pad);
-                    }
+                if offset > min_offset {
+                    let pad = offset - min_offset;
+                    println!("print-type-size {}padding: {} bytes", indent, pad);
+                }
 
-                    if offset < min_offset {
-                        // If this happens it's probably a union.
-                        println!(
-                            "print-type-size {}field `.{}`: {} bytes, \
+                if offset < min_offset {
+                    // If this happens it's probably a union.
+                    println!(
+                        "print-type-size {}field `.{}`: {} bytes, \
                                   offset: {} bytes, \
                                   alignment: {} bytes",
-                            indent, name, size, offset, align
-                        );
-                    } else if info.packed || offset == min_offset {
-                        println!("print-type-size {}field `.{}`: {} bytes", indent, name, size);
-                    } else {
-                        // Include field alignment in output only if it caused padding injection
-                        println!(
-                            "print-type-size {}field `.{}`: {} bytes, \
+                        indent, name, size, offset, align
+                    );
+                } else if info.packed || offset == min_offset {
+                    println!("print-type-size {}field `.{}`: {} bytes", indent, name, size);
+                } else {
+                    // Include field alignment in output only if it caused padding injection
+                    println!(
+                        "print-type-size {}field `.{}`: {} bytes, \
                                   alignment: {} bytes",
-                            indent, name, size, align
-                        );
-                    }
-
-                    min_offset = offset + size;
+                        indent, name, size, align
+                    );
                 }
+
+                min_offset = offset + size;
             }
+        }
 
-            assert!(
-                max_variant_size <= info.overall_size,
-                "max_variant_size {} !<= {} overall_size",
-                max_variant_size,
-                info.overall_size
+        assert!(
+            max_variant_size <= info.overall_size,
+            "max_variant_size {} !<= {} overall_size",
+            max_variant_size,
+            info.overall_size
+        );
+        if max_variant_size < info.overall_size {
+            println!(
+                "print-type-size {}end padding: {} bytes",
+                indent,
+                info.overall_size - max_variant_size
             );
-            if max_variant_size < info.overall_size {
-                println!(
-                    "print-type-size {}end padding: {} bytes",
-                    indent,
-                    info.overall_size - max_variant_size
+        }
+    }
+}
+
+/// Describes the layout of a type like `print_type_sizes_text` does, with the
+/// padding between fields and at the end computed the same way.
+fn type_size_json(info: &TypeSizeInfo) -> Json {
+    let discr_size = info.opt_discr_size.unwrap_or(0);
+    let mut max_variant_size = discr_size;
+
+    let variants = info
+        .variants
+        .iter()
+        .map(|variant_info| {
+            max_variant_size = cmp::max(max_variant_size, variant_info.size);
+
+            let mut fields = variant_info.fields.clone();
+            fields.sort_by_key(|f| (f.offset, f.size));
+
+            let mut min_offset = discr_size;
+            let mut padding = 0;
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    // The offset can be smaller for unions.
+                    let padding_before = field.offset.saturating_sub(min_offset);
+                    padding += padding_before;
+                    min_offset = field.offset + field.size;
+
+                    let mut obj = json::Object::new();
+                    obj.insert("name".to_string(), field.name.to_json());
+                    obj.insert("offset".to_string(), field.offset.to_json());
+                    obj.insert("size".to_string(), field.size.to_json());
+                    obj.insert("align".to_string(), field.align.to_json());
+                    obj.insert("padding_before".to_string(), padding_before.to_json());
+                    Json::Object(obj)
+                })
+                .collect();
+
+            let size_kind = match variant_info.kind {
+                SizeKind::Exact => "exact",
+                SizeKind::Min => "min",
+            };
+            let mut obj = json::Object::new();
+            obj.insert("name".to_string(), variant_info.name.to_json());
+            obj.insert("size".to_string(), (variant_info.size - discr_size).to_json());
+            obj.insert("size_kind".to_string(), size_kind.to_json());
+            obj.insert("align".to_string(), variant_info.align.to_json());
+            obj.insert("padding".to_string(), padding.to_json());
+            obj.insert("fields".to_string(), Json::Array(fields));
+            Json::Object(obj)
+        })
+        .collect();
+
+    let kind = match info.kind {
+        DataTypeKind::Struct => "struct",
+        DataTypeKind::Union => "union",
+        DataTypeKind::Enum => "enum",
+        DataTypeKind::Closure => "closure",
+    };
+
+    let discriminant = info.tag.as_ref().map(|tag| {
+        let mut obj = json::Object::new();
+        obj.insert("offset".to_string(), tag.offset.to_json());
+        obj.insert("size".to_string(), tag.size.to_json());
+        match tag.encoding {
+            TagEncodingInfo::Direct => {
+                obj.insert("encoding".to_string(), "direct".to_json());
+            }
+            TagEncodingInfo::Niche { ref dataful_variant, ref niche_variants, niche_start } => {
+                obj.insert("encoding".to_string(), "niche".to_json());
+                obj.insert("dataful_variant".to_string(), dataful_variant.to_json());
+                obj.insert(
+                    "niche_variants".to_string(),
+                    Json::Array(vec![niche_variants.0.to_json(), niche_variants.1.to_json()]),
                 );
+                obj.insert("niche_start".to_string(), u128_json(niche_start));
             }
         }
-    }
+        Json::Object(obj)
+    });
+
+    let niche = info.niche.as_ref().map(|niche| {
+        let mut obj = json::Object::new();
+        obj.insert("offset".to_string(), niche.offset.to_json());
+        obj.insert("size".to_string(), niche.size.to_json());
+        obj.insert(
+            "valid_range".to_string(),
+            Json::Array(vec![u128_json(niche.valid_range.0), u128_json(niche.valid_range.1)]),
+        );
+        obj.insert("available".to_string(), u128_json(niche.available));
+        Json::Object(obj)
+    });
+
+    let mut obj = json::Object::new();
+    obj.insert("type".to_string(), info.type_description.to_json());
+    obj.insert("kind".to_string(), kind.to_json());
+    obj.insert("size".to_string(), info.overall_size.to_json());
+    obj.insert("align".to_string(), info.align.to_json());
+    obj.insert("packed".to_string(), info.packed.to_json());
+    obj.insert("discriminant".to_string(), discriminant.to_json());
+    obj.insert("niche".to_string(), niche.to_json());
+    obj.insert("variants".to_string(), Json::Array(variants));
+    obj.insert("end_padding".to_string(), (info.overall_size - max_variant_size).to_json());
+    Json::Object(obj)
+}
+
+/// JSON numbers are limited to 64 bits, so larger values are written as strings.
+fn u128_json(value: u128) -> Json {
+    u64::try_from(value).map_or_else(|_| Json::String(value.to_string()), Json::U64)
 }
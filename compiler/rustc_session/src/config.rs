@@ -144,10 +144,18 @@ pub enum MirSpanview {
 ///
 /// `ExceptUnusedGenerics` will add synthetic functions to the coverage map,
 /// unless the function has type parameters.
+///
+/// `Branch` instruments the same code regions as `All`, and additionally counts
+/// how often each outcome of a branch (the arms of a `SwitchInt` terminator,
+/// including the ones lowered from `if`, `&&`, `||`, `if let` and `match`) is
+/// taken, which `llvm-cov` reports as "branch" coverage. Branch regions require
+/// LLVM 12 or higher.
 #[derive(Clone, Copy, PartialEq, Hash, Debug)]
 pub enum InstrumentCoverage {
     /// Default `-Z instrument-coverage` or `-Z instrument-coverage=statement`
     All,
+    /// `-Z instrument-coverage=branch`
+    Branch,
     /// `-Z instrument-coverage=except-unused-generics`
     ExceptUnusedGenerics,
     /// `-Z instrument-coverage=except-unused-functions`
@@ -298,6 +306,11 @@ pub enum ErrorOutputType {
         /// human output.
         json_rendered: HumanReadableErrorType,
     },
+    /// A single SARIF log for the whole compilation, consumed by code-scanning tools.
+    Sarif {
+        /// Render the SARIF log in a human readable way (with indents and newlines).
+        pretty: bool,
+    },
 }
 
 impl Default for ErrorOutputType {
@@ -511,6 +524,14 @@ pub enum PrintRequest {
     TlsModels,
     TargetSpec,
     NativeStaticLibs,
+    CrateGraph,
+}
+
+impl PrintRequest {
+    /// Whether this is printed while compiling the crate, rather than instead of compiling it.
+    pub fn is_printed_during_compilation(self) -> bool {
+        matches!(self, PrintRequest::NativeStaticLibs | PrintRequest::CrateGraph)
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -1060,7 +1081,8 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
             "Compiler information to print on stdout",
             "[crate-name|file-names|sysroot|target-libdir|cfg|target-list|\
              target-cpus|target-features|relocation-models|\
-             code-models|tls-models|target-spec-json|native-static-libs]",
+             code-models|tls-models|target-spec-json|native-static-libs|\
+             crate-graph]",
         ),
         opt::flagmulti_s("g", "", "Equivalent to -C debuginfo=2"),
         opt::flagmulti_s("O", "", "Equivalent to -C opt-level=2"),
@@ -1130,7 +1152,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif|pretty-sarif",
         ),
         opt::multi_s("", "json", "Configure the JSON output of the compiler", "CONFIG"),
         opt::opt_s(
@@ -1301,13 +1323,15 @@ pub fn parse_error_format(
             }
             Some("json") => ErrorOutputType::Json { pretty: false, json_rendered },
             Some("pretty-json") => ErrorOutputType::Json { pretty: true, json_rendered },
+            Some("sarif") => ErrorOutputType::Sarif { pretty: false },
+            Some("pretty-sarif") => ErrorOutputType::Sarif { pretty: true },
             Some("short") => ErrorOutputType::HumanReadable(HumanReadableErrorType::Short(color)),
 
             Some(arg) => early_error(
                 ErrorOutputType::HumanReadable(HumanReadableErrorType::Default(color)),
                 &format!(
-                    "argument for `--error-format` must be `human`, `json` or \
-                     `short` (instead was `{}`)",
+                    "argument for `--error-format` must be `human`, `json`, `short`, \
+                     `sarif` or `pretty-sarif` (instead was `{}`)",
                     arg
                 ),
             ),
@@ -1386,6 +1410,12 @@ fn check_debug_option_stability(
                 "`--error-format=human-annotate-rs` is unstable",
             );
         }
+        if let ErrorOutputType::Sarif { .. } = error_format {
+            early_error(
+                ErrorOutputType::Json { pretty: false, json_rendered },
+                "`--error-format=sarif` is unstable",
+            );
+        }
     }
 }
 
@@ -1510,6 +1540,17 @@ fn collect_print_requests(
         "code-models" => PrintRequest::CodeModels,
         "tls-models" => PrintRequest::TlsModels,
         "native-static-libs" => PrintRequest::NativeStaticLibs,
+        "crate-graph" => {
+            if dopts.unstable_options {
+                PrintRequest::CrateGraph
+            } else {
+                early_error(
+                    error_format,
+                    "the `-Z unstable-options` flag must also be passed to \
+                     enable the crate-graph print option",
+                );
+            }
+        }
         "target-spec-json" => {
             if dopts.unstable_options {
                 PrintRequest::TargetSpec
@@ -1991,6 +2032,10 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         }
     }
 
+    if debugging_opts.self_profile_report.is_some() && !debugging_opts.self_profile.enabled() {
+        early_error(error_format, "`-Z self-profile-report` requires `-Z self-profile`");
+    }
+
     if cg.profile_generate.enabled() && cg.profile_use.is_some() {
         early_error(
             error_format,
@@ -1,10 +1,12 @@
 use crate::config::*;
 
 use crate::early_error;
+use crate::code_stats::TypeSizesFormat;
 use crate::lint;
 use crate::search_paths::SearchPath;
 use crate::utils::NativeLib;
 
+use rustc_data_structures::profiling::ReportFormat;
 use rustc_target::spec::{CodeModel, LinkerFlavor, MergeFunctions, PanicStrategy, SanitizerSet};
 use rustc_target::spec::{RelocModel, RelroLevel, SplitDebuginfo, TargetTriple, TlsModel};
 
@@ -52,6 +54,21 @@ macro_rules! hash_substruct {
     };
 }
 
+macro_rules! hash_opt_separately {
+    ($name:expr, $opt_expr:expr, $error_format:expr, $hashes:expr, [UNTRACKED]) => {{}};
+    ($name:expr, $opt_expr:expr, $error_format:expr, $hashes:expr, [TRACKED]) => {{
+        hash_opt_separately!($name, $opt_expr, $error_format, $hashes, [TRACKED_NO_CRATE_HASH])
+    }};
+    ($name:expr, $opt_expr:expr, $error_format:expr, $hashes:expr, [TRACKED_NO_CRATE_HASH]) => {{
+        let mut hasher = DefaultHasher::new();
+        dep_tracking::DepTrackingHash::hash($opt_expr, &mut hasher, $error_format);
+        $hashes.insert($name, hasher.finish());
+    }};
+    ($name:expr, $opt_expr:expr, $error_format:expr, $hashes:expr, [SUBSTRUCT]) => {{
+        $hashes.extend($opt_expr.dep_tracking_hashes($error_format));
+    }};
+}
+
 macro_rules! top_level_options {
     ( $( #[$top_level_attr:meta] )* pub struct Options { $(
         $( #[$attr:meta] )*
@@ -90,6 +107,21 @@ pub fn dep_tracking_hash(&self, for_crate_hash: bool) -> u64 {
                 })*
                 hasher.finish()
             }
+
+            /// The hash of each tracked option on its own, by the name of the
+            /// option. Comparing them with the ones of the previous session
+            /// tells which options changed, for `-Z incremental-explain`.
+            pub fn dep_tracking_hashes(&self) -> BTreeMap<String, u64> {
+                let mut hashes = BTreeMap::new();
+                $({
+                    hash_opt_separately!(stringify!($opt).to_string(),
+                                         &self.$opt,
+                                         self.error_format,
+                                         &mut hashes,
+                                         [$dep_tracking_marker]);
+                })*
+                hashes
+            }
         }
     );
 }
@@ -251,6 +283,18 @@ fn dep_tracking_hash(&self, _for_crate_hash: bool, error_format: ErrorOutputType
                                         error_format);
             hasher.finish()
         }
+
+        fn dep_tracking_hashes(&self, error_format: ErrorOutputType) -> BTreeMap<String, u64> {
+            let mut hashes = BTreeMap::new();
+            $({
+                hash_opt_separately!(format!("-{} {}", $prefix, stringify!($opt).replace('_', "-")),
+                                     &self.$opt,
+                                     error_format,
+                                     &mut hashes,
+                                     [$dep_tracking_marker]);
+            })*
+            hashes
+        }
     }
 
     pub const $stat: OptionDescrs<$struct_name> =
@@ -350,7 +394,7 @@ mod desc {
     pub const parse_optimization_fuel: &str = "crate=integer";
     pub const parse_mir_spanview: &str = "`statement` (default), `terminator`, or `block`";
     pub const parse_instrument_coverage: &str =
-        "`all` (default), `except-unused-generics`, `except-unused-functions`, or `off`";
+        "`all` (default), `branch`, `except-unused-generics`, `except-unused-functions`, or `off`";
     pub const parse_unpretty: &str = "`string` or `string=string`";
     pub const parse_treat_err_as_bug: &str = "either no value or a number bigger than 0";
     pub const parse_lto: &str =
@@ -360,6 +404,9 @@ mod desc {
     pub const parse_switch_with_opt_path: &str =
         "an optional path to the profiling data output directory";
     pub const parse_merge_functions: &str = "one of: `disabled`, `trampolines`, or `aliases`";
+    pub const parse_self_profile_report: &str = "either `table` or `json`";
+    pub const parse_print_type_sizes: &str =
+        "one of: `y`, `yes`, `on`, `text`, `json`, `n`, `no`, or `off`";
     pub const parse_symbol_mangling_version: &str = "either `legacy` or `v0` (RFC 2603)";
     pub const parse_src_file_hash: &str = "either `md5` or `sha1`";
     pub const parse_relocation_model: &str =
@@ -708,6 +755,7 @@ mod parse {
 
         *slot = Some(match v {
             "all" => InstrumentCoverage::All,
+            "branch" => InstrumentCoverage::Branch,
             "except-unused-generics" | "except_unused_generics" => {
                 InstrumentCoverage::ExceptUnusedGenerics
             }
@@ -787,6 +835,26 @@ mod parse {
         true
     }
 
+    crate fn parse_self_profile_report(slot: &mut Option<ReportFormat>, v: Option<&str>) -> bool {
+        match v.and_then(|s| ReportFormat::from_str(s).ok()) {
+            Some(format) => *slot = Some(format),
+            _ => return false,
+        }
+        true
+    }
+
+    crate fn parse_print_type_sizes(slot: &mut Option<TypeSizesFormat>, v: Option<&str>) -> bool {
+        *slot = match v {
+            Some("y") | Some("yes") | Some("on") | Some("text") | None => {
+                Some(TypeSizesFormat::Text)
+            }
+            Some("json") => Some(TypeSizesFormat::Json),
+            Some("n") | Some("no") | Some("off") => None,
+            _ => return false,
+        };
+        true
+    }
+
     crate fn parse_relocation_model(slot: &mut Option<RelocModel>, v: Option<&str>) -> bool {
         match v.and_then(|s| RelocModel::from_str(s).ok()) {
             Some(relocation_model) => *slot = Some(relocation_model),
@@ -1001,7 +1069,8 @@ mod parse {
     borrowck: String = ("migrate".to_string(), parse_string, [UNTRACKED],
         "select which borrowck is used (`mir` or `migrate`) (default: `migrate`)"),
     cgu_partitioning_strategy: Option<String> = (None, parse_opt_string, [TRACKED],
-        "the codegen unit partitioning strategy to use"),
+        "the codegen unit partitioning strategy to use: `default`, or `co-invalidation` to group \
+        items that were invalidated together in the previous incremental session (default: `default`)"),
     chalk: bool = (false, parse_bool, [TRACKED],
         "enable the experimental Chalk-based trait solving engine"),
     codegen_backend: Option<String> = (None, parse_opt_string, [TRACKED],
@@ -1078,6 +1147,9 @@ mod parse {
         "generate human-readable, predictable names for codegen units (default: no)"),
     identify_regions: bool = (false, parse_bool, [UNTRACKED],
         "display unnamed regions as `'<id>`, using a non-ident unique id (default: no)"),
+    incremental_explain: bool = (false, parse_bool, [UNTRACKED],
+        "print which changed inputs caused queries and codegen units to be re-executed \
+        in an incremental session (default: no)"),
     incremental_ignore_spans: bool = (false, parse_bool, [UNTRACKED],
         "ignore spans during ICH computation -- used for testing (default: no)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
@@ -1100,6 +1172,7 @@ mod parse {
         reports (note, the compiler build config must include `profiler = true`); \
         implies `-Z symbol-mangling-version=v0`. Optional values are:
         `=all` (implicit value)
+        `=branch`
         `=except-unused-generics`
         `=except-unused-functions`
         `=off` (default)"),
@@ -1127,6 +1200,9 @@ mod parse {
         (default: no)"),
     mir_opt_level: Option<usize> = (None, parse_opt_number, [TRACKED],
         "MIR optimization level (0-4; default: 1 in non optimized builds and 2 in optimized builds)"),
+    mir_sroa: Option<bool> = (None, parse_opt_bool, [TRACKED],
+        "split aggregate locals in MIR into one local per field (default: yes if \
+        -Zmir-opt-level is at least 1)"),
     mutable_noalias: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "emit noalias metadata for mutable references (default: yes for LLVM >= 12, otherwise no)"),
     new_llvm_pass_manager: Option<bool> = (None, parse_opt_bool, [TRACKED],
@@ -1185,8 +1261,9 @@ mod parse {
         "print the LLVM optimization passes being run (default: no)"),
     print_mono_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "print the result of the monomorphization collection pass"),
-    print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
-        "print layout information for each type encountered (default: no)"),
+    print_type_sizes: Option<TypeSizesFormat> = (None, parse_print_type_sizes, [UNTRACKED],
+        "print layout information for each type encountered, as `text` or `json` \
+        (default: no)"),
     proc_macro_backtrace: bool = (false, parse_bool, [UNTRACKED],
          "show backtraces for panics during proc-macro execution (default: no)"),
     profile: bool = (false, parse_bool, [TRACKED],
@@ -1228,6 +1305,9 @@ mod parse {
         for example: `-Z self-profile-events=default,query-keys`
         all options: none, all, default, generic-activity, query-provider, query-cache-hit
                      query-blocked, incr-cache-load, query-keys, function-args, args, llvm"),
+    self_profile_report: Option<ReportFormat> = (None, parse_self_profile_report, [UNTRACKED],
+        "print a summary of the self profiler's events per query and activity at the end of \
+        the compilation, either as a `table` or as `json` (requires `-Z self-profile`)"),
     share_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "make the current crate share its generic instantiations"),
     show_span: Option<String> = (None, parse_opt_string, [TRACKED],
@@ -1239,6 +1319,9 @@ mod parse {
         "exclude spans when debug-printing compiler state (default: no)"),
     src_hash_algorithm: Option<SourceFileHashAlgorithm> = (None, parse_src_file_hash, [TRACKED],
         "hash algorithm of source files in debug info (`md5`, `sha1`, or `sha256`)"),
+    stack_usage_report: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
+        "write a JSON report of the stack usage of each function, and of the worst-case stack \
+        depth of each entry point, to the given file (ELF targets only)"),
     strip: Strip = (Strip::None, parse_strip, [UNTRACKED],
         "tell the linker which information to strip (`none` (default), `debuginfo` or `symbols`)"),
     split_dwarf_inlining: bool = (true, parse_bool, [UNTRACKED],
@@ -1,6 +1,7 @@
 use crate::cgu_reuse_tracker::CguReuseTracker;
 use crate::code_stats::CodeStats;
 pub use crate::code_stats::{DataTypeKind, FieldInfo, SizeKind, VariantInfo};
+pub use crate::code_stats::{NicheInfo, TagEncodingInfo, TagInfo, TypeSizesFormat};
 use crate::config::{self, CrateType, OutputType, PrintRequest, SwitchWithOptPath};
 use crate::filesearch;
 use crate::lint::{self, LintId};
@@ -20,6 +21,7 @@
 use rustc_errors::emitter::{Emitter, EmitterWriter, HumanReadableErrorType};
 use rustc_errors::json::JsonEmitter;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_errors::{Diagnostic, DiagnosticBuilder, DiagnosticId, ErrorReported};
 use rustc_lint_defs::FutureBreakage;
 pub use rustc_span::crate_disambiguator::CrateDisambiguator;
@@ -502,7 +504,7 @@ pub fn delay_span_bug<S: Into<MultiSpan>>(&self, sp: S, msg: &str) {
     /// warnings or errors are emitted. If no messages are emitted ("good path"), then
     /// it's likely a bug.
     pub fn delay_good_path_bug(&self, msg: &str) {
-        if self.opts.debugging_opts.print_type_sizes
+        if self.opts.debugging_opts.print_type_sizes.is_some()
             || self.opts.debugging_opts.query_dep_graph
             || self.opts.debugging_opts.dump_mir.is_some()
             || self.opts.debugging_opts.unpretty.is_some()
@@ -1103,6 +1105,11 @@ pub fn instrument_coverage(&self) -> bool {
             != config::InstrumentCoverage::Off
     }
 
+    pub fn instrument_coverage_branch(&self) -> bool {
+        self.opts.debugging_opts.instrument_coverage.unwrap_or(config::InstrumentCoverage::Off)
+            == config::InstrumentCoverage::Branch
+    }
+
     pub fn instrument_coverage_except_unused_generics(&self) -> bool {
         self.opts.debugging_opts.instrument_coverage.unwrap_or(config::InstrumentCoverage::Off)
             == config::InstrumentCoverage::ExceptUnusedGenerics
@@ -1242,6 +1249,12 @@ fn default_emitter(
             )
             .ui_testing(sopts.debugging_opts.ui_testing),
         ),
+        (config::ErrorOutputType::Sarif { pretty }, None) => {
+            Box::new(SarifEmitter::stderr(Some(registry), source_map, pretty))
+        }
+        (config::ErrorOutputType::Sarif { pretty }, Some(dst)) => {
+            Box::new(SarifEmitter::new(dst, Some(registry), source_map, pretty))
+        }
     }
 }
 
@@ -1317,6 +1330,7 @@ pub fn build_session(
             directory,
             sopts.crate_name.as_deref(),
             &sopts.debugging_opts.self_profile_events,
+            sopts.debugging_opts.self_profile_report,
         );
         match profiler {
             Ok(profiler) => Some(Arc::new(profiler)),
@@ -1487,7 +1501,7 @@ fn validate_commandline_args_with_session_available(sess: &Session) {
     if sess.opts.cg.profile_generate.enabled()
         && sess.target.is_like_msvc
         && sess.panic_strategy() == PanicStrategy::Unwind
-        && sess.opts.prints.iter().all(|&p| p == PrintRequest::NativeStaticLibs)
+        && sess.opts.prints.iter().all(|&p| p.is_printed_during_compilation())
     {
         sess.err(
             "Profile-guided optimization does not yet work in conjunction \
@@ -1551,6 +1565,7 @@ pub fn early_error_no_abort(output: config::ErrorOutputType, msg: &str) {
         config::ErrorOutputType::Json { pretty, json_rendered } => {
             Box::new(JsonEmitter::basic(pretty, json_rendered, None, false))
         }
+        config::ErrorOutputType::Sarif { pretty } => Box::new(SarifEmitter::early(pretty)),
     };
     let handler = rustc_errors::Handler::with_emitter(true, None, emitter);
     handler.struct_fatal(msg).emit();
@@ -1570,6 +1585,7 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
         config::ErrorOutputType::Json { pretty, json_rendered } => {
             Box::new(JsonEmitter::basic(pretty, json_rendered, None, false))
         }
+        config::ErrorOutputType::Sarif { pretty } => Box::new(SarifEmitter::early(pretty)),
     };
     let handler = rustc_errors::Handler::with_emitter(true, None, emitter);
     handler.struct_warn(msg).emit();
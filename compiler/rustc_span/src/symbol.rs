@@ -1219,6 +1219,7 @@
         test_case,
         test_removed_feature,
         test_runner,
+        test_timeout,
         then_with,
         thread,
         thread_local,
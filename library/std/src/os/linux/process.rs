@@ -0,0 +1,220 @@
+//! Linux-specific extensions to primitives in the `std::process` module.
+
+#![unstable(feature = "linux_pidfd", issue = "none")]
+
+use crate::fmt;
+use crate::io;
+use crate::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use crate::process::{self, ExitStatus};
+use crate::sealed::Sealed;
+#[cfg(not(doc))]
+use crate::sys::process::PidFd as InnerPidFd;
+use crate::sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
+
+// The documentation of this module is also built on platforms without pidfds.
+#[cfg(doc)]
+struct InnerPidFd;
+
+/// A file descriptor that refers to a process.
+///
+/// Unlike a pid, a `PidFd` keeps referring to the same process after it has
+/// exited, so it can be signalled or waited on without the risk of reaching
+/// an unrelated process that reused the pid. It becomes readable once the
+/// process exits, so it can also be registered with `poll`, `select` or
+/// `epoll` to be notified of that.
+///
+/// A `PidFd` can be obtained by enabling [`create_pidfd`] on a [`Command`]
+/// and then calling [`pidfd`] or [`take_pidfd`] on the spawned [`Child`].
+/// Refer to the man page of [`pidfd_open(2)`] for further details.
+///
+/// Creating and signalling pidfds requires Linux 5.3, and waiting on them
+/// requires Linux 5.4.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(linux_pidfd)]
+/// use std::os::linux::process::{ChildExt, CommandExt};
+/// use std::process::Command;
+///
+/// let mut child = Command::new("sleep")
+///     .arg("10")
+///     .create_pidfd(true)
+///     .spawn()
+///     .expect("failed to spawn child");
+///
+/// let pidfd = child.take_pidfd().expect("failed to retrieve pidfd");
+/// pidfd.kill().expect("failed to kill child");
+/// let status = pidfd.wait().expect("failed to wait on child");
+/// assert!(!status.success());
+/// ```
+///
+/// [`Command`]: process::Command
+/// [`create_pidfd`]: CommandExt::create_pidfd
+/// [`Child`]: process::Child
+/// [`pidfd`]: ChildExt::pidfd
+/// [`take_pidfd`]: ChildExt::take_pidfd
+/// [`pidfd_open(2)`]: https://man7.org/linux/man-pages/man2/pidfd_open.2.html
+#[unstable(feature = "linux_pidfd", issue = "none")]
+pub struct PidFd {
+    inner: InnerPidFd,
+}
+
+impl PidFd {
+    /// Forces the process to exit by sending it `SIGKILL`.
+    ///
+    /// This fails if the process has already been waited on.
+    #[unstable(feature = "linux_pidfd", issue = "none")]
+    pub fn kill(&self) -> io::Result<()> {
+        self.inner.kill()
+    }
+
+    /// Waits for the process to exit completely, returning its exit status.
+    ///
+    /// This reaps the process, so waiting on it again, through this `PidFd`
+    /// or through the [`Child`] it came from, returns an error.
+    ///
+    /// [`Child`]: process::Child
+    #[unstable(feature = "linux_pidfd", issue = "none")]
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        self.inner.wait().map(ExitStatus::from_inner)
+    }
+
+    /// Returns the exit status of the process if it has exited, and `None`
+    /// otherwise. Like [`wait`], this reaps the process once it has exited.
+    ///
+    /// [`wait`]: PidFd::wait
+    #[unstable(feature = "linux_pidfd", issue = "none")]
+    pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+        Ok(self.inner.try_wait()?.map(ExitStatus::from_inner))
+    }
+}
+
+impl AsInner<InnerPidFd> for PidFd {
+    fn as_inner(&self) -> &InnerPidFd {
+        &self.inner
+    }
+}
+
+impl FromInner<InnerPidFd> for PidFd {
+    fn from_inner(inner: InnerPidFd) -> PidFd {
+        PidFd { inner }
+    }
+}
+
+impl IntoInner<InnerPidFd> for PidFd {
+    fn into_inner(self) -> InnerPidFd {
+        self.inner
+    }
+}
+
+#[unstable(feature = "linux_pidfd", issue = "none")]
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.raw()
+    }
+}
+
+#[unstable(feature = "linux_pidfd", issue = "none")]
+impl FromRawFd for PidFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> PidFd {
+        PidFd::from_inner(InnerPidFd::new(fd))
+    }
+}
+
+#[unstable(feature = "linux_pidfd", issue = "none")]
+impl IntoRawFd for PidFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw()
+    }
+}
+
+#[unstable(feature = "linux_pidfd", issue = "none")]
+impl fmt::Debug for PidFd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PidFd").field("fd", &self.as_raw_fd()).finish()
+    }
+}
+
+/// Linux-specific extensions to [`process::Child`].
+///
+/// This trait is sealed: it cannot be implemented outside the standard library.
+/// This is so that future additional methods are not breaking changes.
+#[unstable(feature = "linux_pidfd", issue = "none")]
+pub trait ChildExt: Sealed {
+    /// Obtains a reference to the [`PidFd`] created for this child, if any.
+    ///
+    /// While the child holds a pidfd, [`Child::kill`], [`Child::wait`] and
+    /// [`Child::try_wait`] use it instead of the child's pid.
+    ///
+    /// A pidfd will only be available if its creation was requested with
+    /// [`create_pidfd`] when the corresponding [`Command`] was created, and
+    /// the kernel supports pidfds. Otherwise, an error is returned.
+    ///
+    /// [`Command`]: process::Command
+    /// [`create_pidfd`]: CommandExt::create_pidfd
+    /// [`Child::kill`]: process::Child::kill
+    /// [`Child::wait`]: process::Child::wait
+    /// [`Child::try_wait`]: process::Child::try_wait
+    #[unstable(feature = "linux_pidfd", issue = "none")]
+    fn pidfd(&self) -> io::Result<&PidFd>;
+
+    /// Takes ownership of the [`PidFd`] created for this child, if any.
+    ///
+    /// The child falls back to using its pid afterwards.
+    ///
+    /// A pidfd will only be available if its creation was requested with
+    /// [`create_pidfd`] when the corresponding [`Command`] was created, and
+    /// the kernel supports pidfds. Otherwise, an error is returned.
+    ///
+    /// [`Command`]: process::Command
+    /// [`create_pidfd`]: CommandExt::create_pidfd
+    #[unstable(feature = "linux_pidfd", issue = "none")]
+    fn take_pidfd(&mut self) -> io::Result<PidFd>;
+}
+
+#[unstable(feature = "linux_pidfd", issue = "none")]
+impl ChildExt for process::Child {
+    fn pidfd(&self) -> io::Result<&PidFd> {
+        self.as_inner().pidfd().ok_or_else(no_pidfd)
+    }
+
+    fn take_pidfd(&mut self) -> io::Result<PidFd> {
+        self.as_inner_mut().take_pidfd().ok_or_else(no_pidfd)
+    }
+}
+
+fn no_pidfd() -> io::Error {
+    io::Error::new_const(io::ErrorKind::Other, &"no pidfd was created for this child")
+}
+
+/// Linux-specific extensions to the [`process::Command`] builder.
+///
+/// This trait is sealed: it cannot be implemented outside the standard library.
+/// This is so that future additional methods are not breaking changes.
+#[unstable(feature = "linux_pidfd", issue = "none")]
+pub trait CommandExt: Sealed {
+    /// Sets whether a [`PidFd`] should be created for the [`Child`]
+    /// spawned by this [`Command`]. By default, no pidfd is created.
+    ///
+    /// The pidfd can be retrieved from the child with [`pidfd`] or
+    /// [`take_pidfd`].
+    ///
+    /// On kernels older than Linux 5.3, which don't support pidfds, the child
+    /// is still spawned, but without a pidfd.
+    ///
+    /// [`Command`]: process::Command
+    /// [`Child`]: process::Child
+    /// [`pidfd`]: ChildExt::pidfd
+    /// [`take_pidfd`]: ChildExt::take_pidfd
+    #[unstable(feature = "linux_pidfd", issue = "none")]
+    fn create_pidfd(&mut self, val: bool) -> &mut process::Command;
+}
+
+#[unstable(feature = "linux_pidfd", issue = "none")]
+impl CommandExt for process::Command {
+    fn create_pidfd(&mut self, val: bool) -> &mut process::Command {
+        self.as_inner_mut().create_pidfd(val);
+        self
+    }
+}
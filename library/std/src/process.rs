@@ -211,6 +211,12 @@ fn as_inner(&self) -> &imp::Process {
     }
 }
 
+impl AsInnerMut<imp::Process> for Child {
+    fn as_inner_mut(&mut self) -> &mut imp::Process {
+        &mut self.handle
+    }
+}
+
 impl FromInner<(imp::Process, imp::StdioPipes)> for Child {
     fn from_inner((handle, io): (imp::Process, imp::StdioPipes)) -> Child {
         Child {
@@ -228,6 +234,10 @@ fn into_inner(self) -> imp::Process {
     }
 }
 
+/// Allows extension traits within `std`.
+#[unstable(feature = "sealed", issue = "none")]
+impl crate::sealed::Sealed for Child {}
+
 #[stable(feature = "std_debug", since = "1.16.0")]
 impl fmt::Debug for Child {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -2,8 +2,12 @@
 pub use self::process_inner::{ExitStatus, ExitStatusError, Process};
 pub use crate::ffi::OsString as EnvKey;
 pub use crate::sys_common::process::CommandEnvs;
+#[cfg(target_os = "linux")]
+pub use self::pidfd::PidFd;
 
 mod process_common;
+#[cfg(target_os = "linux")]
+mod pidfd;
 
 cfg_if::cfg_if! {
     if #[cfg(target_os = "fuchsia")] {
@@ -0,0 +1,71 @@
+//! Process file descriptors ("pidfds") on Linux.
+//!
+//! A pidfd refers to one particular process, so unlike a pid it can't end up
+//! referring to an unrelated process once the original one has been reaped.
+//! They were added in Linux 5.3; waiting on them requires Linux 5.4.
+
+use crate::io;
+use crate::mem;
+use crate::ptr;
+use crate::sys::fd::FileDesc;
+use crate::sys::process::ExitStatus;
+use crate::sys::{cvt, cvt_r};
+
+use libc::c_int;
+
+// Not yet exposed by the `libc` crate.
+const P_PIDFD: libc::idtype_t = 3;
+
+pub struct PidFd(FileDesc);
+
+impl PidFd {
+    pub fn new(fd: c_int) -> PidFd {
+        PidFd(FileDesc::new(fd))
+    }
+
+    pub fn raw(&self) -> c_int {
+        self.0.raw()
+    }
+
+    pub fn into_raw(self) -> c_int {
+        self.0.into_raw()
+    }
+
+    pub fn kill(&self) -> io::Result<()> {
+        self.send_signal(libc::SIGKILL)
+    }
+
+    pub fn send_signal(&self, signal: c_int) -> io::Result<()> {
+        cvt(unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                self.0.raw(),
+                signal,
+                ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        })
+        .map(drop)
+    }
+
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        let status = self.waitid(libc::WEXITED)?;
+        Ok(status.expect("waitid() without WNOHANG should always report the child"))
+    }
+
+    pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+        self.waitid(libc::WEXITED | libc::WNOHANG)
+    }
+
+    fn waitid(&self, options: c_int) -> io::Result<Option<ExitStatus>> {
+        let mut siginfo: libc::siginfo_t = unsafe { mem::zeroed() };
+        cvt_r(|| unsafe {
+            libc::waitid(P_PIDFD, self.0.raw() as libc::id_t, &mut siginfo, options)
+        })?;
+        // With `WNOHANG`, `si_pid` is left zeroed if the child is still running.
+        if unsafe { siginfo.si_pid() } == 0 {
+            return Ok(None);
+        }
+        Ok(Some(ExitStatus::from_waitid_siginfo(&siginfo)))
+    }
+}
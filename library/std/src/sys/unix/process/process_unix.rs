@@ -15,6 +15,11 @@
 #[cfg(not(target_os = "vxworks"))]
 use libc::{c_int, gid_t, pid_t, uid_t};
 
+#[cfg(target_os = "linux")]
+use crate::os::linux::process::PidFd;
+#[cfg(target_os = "linux")]
+use crate::sys_common::AsInner;
+
 ////////////////////////////////////////////////////////////////////////////////
 // Command
 ////////////////////////////////////////////////////////////////////////////////
@@ -53,7 +58,11 @@ pub fn spawn(
         // a lock any more because the parent won't do anything and the child is
         // in its own process. Thus the parent drops the lock guard while the child
         // forgets it to avoid unlocking it on a new thread, which would be invalid.
-        let (env_lock, pid) = unsafe { (sys::os::env_read_lock(), cvt(libc::fork())?) };
+        let (env_lock, pid, pidfd) = unsafe {
+            let env_lock = sys::os::env_read_lock();
+            let (pid, pidfd) = self.do_fork()?;
+            (env_lock, pid, pidfd)
+        };
 
         if pid == 0 {
             crate::panic::always_abort();
@@ -82,7 +91,8 @@ pub fn spawn(
         drop(env_lock);
         drop(output);
 
-        let mut p = Process { pid, status: None };
+        // SAFETY: `pidfd` was just created for the child and nothing else owns it.
+        let mut p = unsafe { Process::new(pid, pidfd) };
         let mut bytes = [0; 8];
 
         // loop to handle EINTR
@@ -114,6 +124,83 @@ pub fn spawn(
         }
     }
 
+    // Forks the process, returning `(0, -1)` in the child and
+    // `(child_pid, child_pidfd)` in the parent. The pidfd is -1 if none was
+    // requested or it couldn't be created.
+    #[cfg(not(target_os = "linux"))]
+    unsafe fn do_fork(&mut self) -> io::Result<(pid_t, c_int)> {
+        cvt(libc::fork()).map(|pid| (pid, -1))
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe fn do_fork(&mut self) -> io::Result<(pid_t, c_int)> {
+        use crate::sync::atomic::{AtomicBool, Ordering};
+
+        static HAS_CLONE3: AtomicBool = AtomicBool::new(true);
+        const CLONE_PIDFD: u64 = 0x00001000;
+
+        #[repr(C)]
+        struct clone_args {
+            flags: u64,
+            pidfd: u64,
+            child_tid: u64,
+            parent_tid: u64,
+            exit_signal: u64,
+            stack: u64,
+            stack_size: u64,
+            tls: u64,
+            set_tid: u64,
+            set_tid_size: u64,
+            cgroup: u64,
+        }
+
+        let want_pidfd = self.get_create_pidfd();
+        let mut pidfd: c_int = -1;
+
+        // `clone3` can create the pidfd atomically with the child. It bypasses
+        // libc's `fork` bookkeeping though, and sandboxes may only expect
+        // `fork`, so it's only used when a pidfd was asked for.
+        if want_pidfd && HAS_CLONE3.load(Ordering::Relaxed) {
+            let mut args = clone_args {
+                flags: CLONE_PIDFD,
+                pidfd: &mut pidfd as *mut c_int as u64,
+                child_tid: 0,
+                parent_tid: 0,
+                exit_signal: libc::SIGCHLD as u64,
+                stack: 0,
+                stack_size: 0,
+                tls: 0,
+                set_tid: 0,
+                set_tid_size: 0,
+                cgroup: 0,
+            };
+            let res = cvt(libc::syscall(
+                libc::SYS_clone3,
+                &mut args as *mut clone_args,
+                mem::size_of::<clone_args>(),
+            ));
+            match res {
+                Ok(pid) => return Ok((pid as pid_t, pidfd)),
+                Err(e) => match e.raw_os_error() {
+                    // Several threads may race to store this, which is harmless.
+                    Some(libc::ENOSYS) => HAS_CLONE3.store(false, Ordering::Relaxed),
+                    // Seccomp filters commonly reject `clone3` with `EPERM`.
+                    Some(libc::EPERM) => {}
+                    _ => return Err(e),
+                },
+            }
+        }
+
+        let pid = cvt(libc::fork())?;
+        if pid > 0 && want_pidfd {
+            // The child hasn't been waited on yet, so `pid` still refers to it.
+            // On kernels without `pidfd_open` this fails, and the child simply
+            // doesn't get a pidfd.
+            pidfd = libc::syscall(libc::SYS_pidfd_open, pid, 0) as c_int;
+        }
+        Ok((pid, pidfd))
+    }
+
     pub fn exec(&mut self, default: Stdio) -> io::Error {
         let envp = self.capture_env();
 
@@ -304,6 +391,14 @@ fn posix_spawn(
             return Ok(None);
         }
 
+        // A pidfd is created together with the child in `do_fork`.
+        #[cfg(target_os = "linux")]
+        {
+            if self.get_create_pidfd() {
+                return Ok(None);
+            }
+        }
+
         // Only glibc 2.24+ posix_spawn() supports returning ENOENT directly.
         #[cfg(all(target_os = "linux", target_env = "gnu"))]
         {
@@ -344,7 +439,8 @@ fn posix_spawn_file_actions_addchdir_np(
             None => None,
         };
 
-        let mut p = Process { pid: 0, status: None };
+        // SAFETY: -1 is not a pidfd.
+        let mut p = unsafe { Process::new(0, -1) };
 
         struct PosixSpawnFileActions<'a>(&'a mut MaybeUninit<libc::posix_spawn_file_actions_t>);
 
@@ -433,9 +529,40 @@ fn drop(&mut self) {
 pub struct Process {
     pid: pid_t,
     status: Option<ExitStatus>,
+    // The pidfd of the child, if one was requested with `create_pidfd` and the
+    // kernel supports them, and it hasn't been taken out by `take_pidfd`.
+    #[cfg(target_os = "linux")]
+    pidfd: Option<PidFd>,
 }
 
 impl Process {
+    /// # Safety
+    ///
+    /// A nonnegative `pidfd` must be a pidfd for `pid` that is not owned by
+    /// anything else.
+    #[cfg(target_os = "linux")]
+    unsafe fn new(pid: pid_t, pidfd: c_int) -> Self {
+        use crate::os::unix::io::FromRawFd;
+
+        let pidfd = if pidfd >= 0 { Some(PidFd::from_raw_fd(pidfd)) } else { None };
+        Process { pid, status: None, pidfd }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    unsafe fn new(pid: pid_t, _pidfd: c_int) -> Self {
+        Process { pid, status: None }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn pidfd(&self) -> Option<&PidFd> {
+        self.pidfd.as_ref()
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn take_pidfd(&mut self) -> Option<PidFd> {
+        self.pidfd.take()
+    }
+
     pub fn id(&self) -> u32 {
         self.pid as u32
     }
@@ -450,6 +577,12 @@ pub fn kill(&mut self) -> io::Result<()> {
                 &"invalid argument: can't kill an exited process",
             ))
         } else {
+            #[cfg(target_os = "linux")]
+            {
+                if let Some(pidfd) = &self.pidfd {
+                    return pidfd.as_inner().kill();
+                }
+            }
             cvt(unsafe { libc::kill(self.pid, libc::SIGKILL) }).map(drop)
         }
     }
@@ -459,6 +592,13 @@ pub fn wait(&mut self) -> io::Result<ExitStatus> {
         if let Some(status) = self.status {
             return Ok(status);
         }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(Some(status)) = self.wait_pidfd(|pidfd| pidfd.wait().map(Some))? {
+                self.status = Some(status);
+                return Ok(status);
+            }
+        }
         let mut status = 0 as c_int;
         cvt_r(|| unsafe { libc::waitpid(self.pid, &mut status, 0) })?;
         self.status = Some(ExitStatus::new(status));
@@ -469,6 +609,13 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
         if let Some(status) = self.status {
             return Ok(Some(status));
         }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(status) = self.wait_pidfd(|pidfd| pidfd.try_wait())? {
+                self.status = status;
+                return Ok(status);
+            }
+        }
         let mut status = 0 as c_int;
         let pid = cvt(unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) })?;
         if pid == 0 {
@@ -478,6 +625,26 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
             Ok(Some(ExitStatus::new(status)))
         }
     }
+
+    // Waits through the pidfd if there is one, so that the child is reaped
+    // without referring to it by its pid. Returns `None` if the caller should
+    // fall back to `waitpid`: without a pidfd, or when the kernel can create
+    // pidfds but not wait on them (Linux 5.3).
+    #[cfg(target_os = "linux")]
+    fn wait_pidfd<F>(&self, wait: F) -> io::Result<Option<Option<ExitStatus>>>
+    where
+        F: FnOnce(&sys::process::PidFd) -> io::Result<Option<ExitStatus>>,
+    {
+        let pidfd = match &self.pidfd {
+            Some(pidfd) => pidfd.as_inner(),
+            None => return Ok(None),
+        };
+        match wait(pidfd) {
+            Ok(status) => Ok(Some(status)),
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 /// Unix exit statuses
@@ -489,6 +656,19 @@ pub fn new(status: c_int) -> ExitStatus {
         ExitStatus(status)
     }
 
+    // Encodes the exit of a child as reported by `waitid` the way `waitpid`
+    // would have reported it.
+    #[cfg(target_os = "linux")]
+    pub(super) fn from_waitid_siginfo(siginfo: &libc::siginfo_t) -> ExitStatus {
+        let status = unsafe { siginfo.si_status() };
+        match siginfo.si_code {
+            libc::CLD_EXITED => ExitStatus((status & 0xff) << 8),
+            libc::CLD_KILLED => ExitStatus(status),
+            libc::CLD_DUMPED => ExitStatus(status | 0x80),
+            code => panic!("unexpected si_code {} from waitid() with only WEXITED", code),
+        }
+    }
+
     fn exited(&self) -> bool {
         libc::WIFEXITED(self.0)
     }
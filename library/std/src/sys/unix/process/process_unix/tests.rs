@@ -55,3 +55,45 @@ fn test_command_fork_no_unwind() {
     let signal = status.signal().expect("expected child process to die of signal");
     assert!(signal == libc::SIGABRT || signal == libc::SIGILL || signal == libc::SIGTRAP);
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_command_create_pidfd() {
+    use crate::os::linux::process::{ChildExt, CommandExt as _};
+    use crate::os::unix::io::AsRawFd;
+
+    // Pidfds are only created on request.
+    let mut child = Command::new("true").spawn().unwrap();
+    assert!(child.pidfd().is_err());
+    assert!(child.wait().unwrap().success());
+
+    // Waiting on the child goes through its pidfd, if it got one.
+    let mut child = Command::new("sh").args(&["-c", "exit 3"]).create_pidfd(true).spawn().unwrap();
+    assert_eq!(child.wait().unwrap().code(), Some(3));
+
+    let mut child = Command::new("sleep").arg("1000").create_pidfd(true).spawn().unwrap();
+    let pidfd = match child.take_pidfd() {
+        Ok(pidfd) => pidfd,
+        // The kernel doesn't support pidfds.
+        Err(_) => {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            return;
+        }
+    };
+    assert!(pidfd.as_raw_fd() >= 0);
+    assert!(child.pidfd().is_err());
+
+    if let Ok(status) = pidfd.try_wait() {
+        assert_eq!(status, None);
+    }
+    pidfd.kill().unwrap();
+    match pidfd.wait() {
+        Ok(status) => assert_eq!(status.signal(), Some(libc::SIGKILL)),
+        // Waiting on pidfds requires Linux 5.4.
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+            assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+        }
+        Err(e) => panic!("failed to wait on pidfd: {}", e),
+    }
+}
@@ -1,7 +1,9 @@
 use super::Builder;
 use crate::any::Any;
 use crate::mem;
+use crate::panic::{self, AssertUnwindSafe};
 use crate::result;
+use crate::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use crate::sync::mpsc::{channel, Sender};
 use crate::thread::{self, ThreadId};
 use crate::time::Duration;
@@ -258,5 +260,75 @@ fn test_thread_id_not_equal() {
     assert!(thread::current().id() != spawned_id);
 }
 
+#[test]
+fn test_scoped_threads_borrow_locals() {
+    let a = vec![1, 2, 3];
+    let mut x = 0;
+    thread::scope(|s| {
+        s.spawn(|| assert_eq!(a.len(), 3));
+        s.spawn(|| x += a[0] + a[2]);
+    });
+    assert_eq!(x, 4);
+}
+
+#[test]
+fn test_scoped_threads_joined_before_return() {
+    let counter = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for _ in 0..10 {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(10));
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+    });
+    assert_eq!(counter.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn test_scoped_thread_join_result() {
+    let x = 21;
+    let r = thread::scope(|s| s.spawn(|| x * 2).join().unwrap());
+    assert_eq!(r, 42);
+}
+
+#[test]
+fn test_scoped_thread_joined_panic_is_handled() {
+    let r = thread::scope(|s| s.spawn(|| panic!("oh no")).join());
+    assert!(r.is_err());
+}
+
+#[test]
+fn test_scoped_thread_panic_propagates() {
+    let finished = AtomicBool::new(false);
+    let r = panic::catch_unwind(AssertUnwindSafe(|| {
+        thread::scope(|s| {
+            s.spawn(|| panic!("oh no"));
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(10));
+                finished.store(true, Ordering::SeqCst);
+            });
+        })
+    }));
+    assert!(r.is_err());
+    assert!(finished.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_scope_panic_waits_for_threads() {
+    let finished = AtomicBool::new(false);
+    let r = panic::catch_unwind(AssertUnwindSafe(|| {
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(10));
+                finished.store(true, Ordering::SeqCst);
+            });
+            panic!("oh no");
+        })
+    }));
+    assert!(r.is_err());
+    assert!(finished.load(Ordering::SeqCst));
+}
+
 // NOTE: the corresponding test for stderr is in ui/thread-stderr, due
 // to the test harness apparently interfering with stderr configuration.
@@ -0,0 +1,216 @@
+//! Module `baseline` saves benchmark results to a file, and compares the
+//! results of later runs against them.
+//!
+//! Baselines are tab-separated text files. After a comment line identifying
+//! the format and a header line naming the columns, each line holds one
+//! benchmark: its name, the summary statistics of its samples in nanoseconds
+//! per iteration, its throughput, and finally all of its samples separated by
+//! spaces.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, prelude::*, BufReader, BufWriter};
+use std::path::Path;
+
+use super::bench::BenchSamples;
+use super::stats::{self, mann_whitney_z};
+
+const FORMAT_LINE: &str = "# libtest benchmark baseline, version 1";
+const HEADER_LINE: &str = "name\tmedian\tmean\tmin\tmax\tstd_dev\tmedian_abs_dev\tmb_s\tsamples";
+
+/// The standard score beyond which a difference is considered significant,
+/// which corresponds to a two-sided p-value of 0.05.
+const SIGNIFICANT_Z: f64 = 1.96;
+
+/// Changes of the median smaller than this percentage are considered noise,
+/// even when they are statistically significant.
+const NOISE_THRESHOLD_PCT: f64 = 1.0;
+
+/// Benchmark results of a previous run, keyed by benchmark name.
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline {
+    benches: BTreeMap<String, BenchSamples>,
+}
+
+impl Baseline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: &str, samples: BenchSamples) {
+        self.benches.insert(name.to_string(), samples);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BenchSamples> {
+        self.benches.get(name)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?)).map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to read baseline {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()
+    }
+
+    fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line == FORMAT_LINE => {}
+            _ => return Err(invalid("not a libtest benchmark baseline".to_string())),
+        }
+        match lines.next().transpose()? {
+            Some(line) if line == HEADER_LINE => {}
+            _ => return Err(invalid("missing or unexpected header line".to_string())),
+        }
+
+        let mut baseline = Self::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            // The first two lines have already been read.
+            let line_number = i + 3;
+            let (name, samples) = parse_line(&line)
+                .ok_or_else(|| invalid(format!("malformed benchmark on line {}", line_number)))?;
+            baseline.benches.insert(name, samples);
+        }
+        Ok(baseline)
+    }
+
+    fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", FORMAT_LINE)?;
+        writeln!(out, "{}", HEADER_LINE)?;
+        for (name, bs) in &self.benches {
+            let summ = &bs.ns_iter_summ;
+            let samples: Vec<String> = bs.samples.iter().map(f64::to_string).collect();
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                escape(name),
+                summ.median,
+                summ.mean,
+                summ.min,
+                summ.max,
+                summ.std_dev,
+                summ.median_abs_dev,
+                bs.mb_s,
+                samples.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a benchmark line. The summary columns are only there for other
+/// tools, so the summary is recomputed from the samples instead.
+fn parse_line(line: &str) -> Option<(String, BenchSamples)> {
+    let columns: Vec<&str> = line.split('\t').collect();
+    if columns.len() != 9 {
+        return None;
+    }
+    let name = unescape(columns[0])?;
+    let mb_s = columns[7].parse().ok()?;
+    let samples =
+        columns[8].split(' ').map(|sample| sample.parse().ok()).collect::<Option<Vec<f64>>>()?;
+    let ns_iter_summ = stats::Summary::new(&samples);
+    Some((name, BenchSamples { ns_iter_summ, mb_s, samples }))
+}
+
+/// Escapes the characters that would break up the line format in test names.
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(escaped: &str) -> Option<String> {
+    let mut name = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            name.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => name.push('\\'),
+            't' => name.push('\t'),
+            'n' => name.push('\n'),
+            'r' => name.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(name)
+}
+
+/// The outcome of comparing a benchmark against its baseline.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Improved,
+    Regressed,
+    NoChange,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            Verdict::Improved => "improved",
+            Verdict::Regressed => "regressed",
+            Verdict::NoChange => "no change",
+        })
+    }
+}
+
+/// The difference between a benchmark's result and its baseline.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BenchComparison {
+    /// The median nanoseconds per iteration of the baseline.
+    pub baseline_median: f64,
+    /// The median nanoseconds per iteration of this run.
+    pub median: f64,
+    /// The change of the median relative to the baseline, in percent.
+    pub change_pct: f64,
+    /// The Mann-Whitney standard score of the baseline's samples against
+    /// this run's; positive when this run is faster.
+    pub z: f64,
+    pub verdict: Verdict,
+}
+
+impl BenchComparison {
+    pub fn new(baseline: &BenchSamples, current: &BenchSamples) -> Self {
+        let baseline_median = baseline.ns_iter_summ.median;
+        let median = current.ns_iter_summ.median;
+        let change_pct = if baseline_median == 0.0 {
+            0.0
+        } else {
+            (median - baseline_median) / baseline_median * 100.0
+        };
+        let z = mann_whitney_z(&baseline.samples, &current.samples);
+
+        // The medians and the ranks of the samples have to agree on the direction of the change.
+        let verdict = if z.abs() < SIGNIFICANT_Z || change_pct.abs() < NOISE_THRESHOLD_PCT {
+            Verdict::NoChange
+        } else if z > 0.0 && change_pct < 0.0 {
+            Verdict::Improved
+        } else if z < 0.0 && change_pct > 0.0 {
+            Verdict::Regressed
+        } else {
+            Verdict::NoChange
+        };
+
+        BenchComparison { baseline_median, median, change_pct, z, verdict }
+    }
+}
@@ -1,5 +1,6 @@
 //! Benchmarking module.
 use super::{
+    baseline::BenchComparison,
     event::CompletedTest,
     options::BenchMode,
     test_result::TestResult,
@@ -32,6 +33,7 @@ pub fn black_box<T>(dummy: T) -> T {
 pub struct Bencher {
     mode: BenchMode,
     summary: Option<stats::Summary>,
+    samples: Vec<f64>,
     pub bytes: u64,
 }
 
@@ -46,7 +48,9 @@ pub fn iter<T, F>(&mut self, mut inner: F)
             return;
         }
 
-        self.summary = Some(iter(&mut inner));
+        let (summary, samples) = iter_samples(&mut inner);
+        self.summary = Some(summary);
+        self.samples = samples;
     }
 
     pub fn bench<F>(&mut self, mut f: F) -> Option<stats::Summary>
@@ -62,6 +66,9 @@ pub fn bench<F>(&mut self, mut f: F) -> Option<stats::Summary>
 pub struct BenchSamples {
     pub ns_iter_summ: stats::Summary,
     pub mb_s: usize,
+    /// The nanoseconds per iteration measured by each sample that
+    /// `ns_iter_summ` summarizes.
+    pub samples: Vec<f64>,
 }
 
 pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
@@ -84,6 +91,14 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
     output
 }
 
+pub fn fmt_bench_comparison(comparison: &BenchComparison) -> String {
+    format!(
+        "{:+.2}% from {} ns/iter",
+        comparison.change_pct,
+        fmt_thousands_sep(comparison.baseline_median as usize, ',')
+    )
+}
+
 // Format a number with thousands separators
 fn fmt_thousands_sep(mut n: usize, sep: char) -> String {
     use std::fmt::Write;
@@ -120,6 +135,14 @@ fn ns_iter_inner<T, F>(inner: &mut F, k: u64) -> u64
 }
 
 pub fn iter<T, F>(inner: &mut F) -> stats::Summary
+where
+    F: FnMut() -> T,
+{
+    iter_samples(inner).0
+}
+
+/// Like `iter`, but also returns the samples the summary was computed from.
+fn iter_samples<T, F>(inner: &mut F) -> (stats::Summary, Vec<f64>)
 where
     F: FnMut() -> T,
 {
@@ -166,13 +189,13 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
             && summ.median_abs_dev_pct < 1.0
             && summ.median - summ5.median < summ5.median_abs_dev
         {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         total_run += loop_run;
         // Longest we ever run for is 3s.
         if total_run > Duration::from_secs(3) {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         // If we overflow here just return the results so far. We check a
@@ -182,7 +205,7 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
         n = match n.checked_mul(10) {
             Some(_) => n * 2,
             None => {
-                return summ5;
+                return (summ5, samples.to_vec());
             }
         };
     }
@@ -197,7 +220,7 @@ pub fn benchmark<F>(
 ) where
     F: FnMut(&mut Bencher),
 {
-    let mut bs = Bencher { mode: BenchMode::Auto, summary: None, bytes: 0 };
+    let mut bs = Bencher { mode: BenchMode::Auto, summary: None, samples: Vec::new(), bytes: 0 };
 
     let data = Arc::new(Mutex::new(Vec::new()));
 
@@ -215,14 +238,18 @@ pub fn benchmark<F>(
             let ns_iter = cmp::max(ns_iter_summ.median as u64, 1);
             let mb_s = bs.bytes * 1000 / ns_iter;
 
-            let bs = BenchSamples { ns_iter_summ, mb_s: mb_s as usize };
+            let bs = BenchSamples { ns_iter_summ, mb_s: mb_s as usize, samples: bs.samples };
             TestResult::TrBench(bs)
         }
         Ok(None) => {
             // iter not called, so no data.
             // FIXME: error in this case?
             let samples: &mut [f64] = &mut [0.0_f64; 1];
-            let bs = BenchSamples { ns_iter_summ: stats::Summary::new(samples), mb_s: 0 };
+            let bs = BenchSamples {
+                ns_iter_summ: stats::Summary::new(samples),
+                mb_s: 0,
+                samples: samples.to_vec(),
+            };
             TestResult::TrBench(bs)
         }
         Err(_) => TestResult::TrFailed,
@@ -237,6 +264,6 @@ pub fn run_once<F>(f: F)
 where
     F: FnMut(&mut Bencher),
 {
-    let mut bs = Bencher { mode: BenchMode::Single, summary: None, bytes: 0 };
+    let mut bs = Bencher { mode: BenchMode::Single, summary: None, samples: Vec::new(), bytes: 0 };
     bs.bench(f);
 }
@@ -2,10 +2,11 @@
 
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use super::helpers::isatty;
-use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
-use super::time::TestTimeOptions;
+use super::options::{ColorConfig, Options, OutputFormat, RunIgnored, Shard};
+use super::time::{parse_test_timeout, TestTimeOptions};
 
 #[derive(Debug)]
 pub struct TestOpts {
@@ -24,6 +25,20 @@ pub struct TestOpts {
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
     pub time_options: Option<TestTimeOptions>,
+    /// Run the tests in a random order.
+    pub shuffle: bool,
+    /// Run the tests in a random order determined by this seed.
+    /// Implies `shuffle`.
+    pub shuffle_seed: Option<u64>,
+    /// Fail any test still running after this long. Tests may override it
+    /// with `#[test_timeout]`.
+    pub test_timeout: Option<Duration>,
+    /// Only run the tests in this slice of the test suite.
+    pub shard: Option<Shard>,
+    /// Save the benchmark results to this file.
+    pub save_baseline: Option<PathBuf>,
+    /// Compare the benchmark results against the ones saved in this file.
+    pub baseline: Option<PathBuf>,
     pub options: Options,
 }
 
@@ -138,6 +153,35 @@ fn optgroups() -> getopts::Options {
 
             `CRITICAL_TIME` here means the limit that should not be exceeded by test.
             ",
+        )
+        .optflag("", "shuffle", "Run tests in random order")
+        .optopt(
+            "",
+            "shuffle-seed",
+            "Run tests in random order; seed the random number generator with SEED",
+            "SEED",
+        )
+        .optopt(
+            "",
+            "test-timeout",
+            "Fail tests that run for longer than DURATION. Durations are a number
+            of seconds, or a number followed by `ms`, `s` or `m`. Tests running
+            in a subprocess are killed; tests running in-process are abandoned.",
+            "DURATION",
+        )
+        .optopt(
+            "",
+            "shard",
+            "Split the tests into COUNT disjoint shards by name and only run
+            shard INDEX, where INDEX is between 1 and COUNT",
+            "INDEX/COUNT",
+        )
+        .optopt("", "save-baseline", "Save the benchmark results to PATH", "PATH")
+        .optopt(
+            "",
+            "baseline",
+            "Compare the benchmark results against those saved to PATH with --save-baseline",
+            "PATH",
         );
     opts
 }
@@ -218,6 +262,21 @@ macro_rules! unstable_optflag {
     }};
 }
 
+// Gets the option value and checks if unstable features are enabled.
+macro_rules! unstable_optopt {
+    ($matches:ident, $allow_unstable:ident, $option_name:literal) => {{
+        let opt = $matches.opt_str($option_name);
+        if !$allow_unstable && opt.is_some() {
+            return Err(format!(
+                "The \"{}\" option is only accepted on the nightly compiler with -Z unstable-options",
+                $option_name
+            ));
+        }
+
+        opt
+    }};
+}
+
 // Implementation of `parse_opts` that doesn't care about help message
 // and returns a `Result`.
 fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
@@ -227,6 +286,13 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let force_run_in_process = unstable_optflag!(matches, allow_unstable, "force-run-in-process");
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
     let time_options = get_time_options(&matches, allow_unstable)?;
+    let shuffle = get_shuffle(&matches, allow_unstable)?;
+    let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
+    let shard = get_shard(&matches, allow_unstable)?;
+    let save_baseline =
+        unstable_optopt!(matches, allow_unstable, "save-baseline").map(PathBuf::from);
+    let baseline = unstable_optopt!(matches, allow_unstable, "baseline").map(PathBuf::from);
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
@@ -263,6 +329,12 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         test_threads,
         skip,
         time_options,
+        shuffle,
+        shuffle_seed,
+        test_timeout,
+        shard,
+        save_baseline,
+        baseline,
         options,
     };
 
@@ -303,6 +375,81 @@ fn get_time_options(
     Ok(options)
 }
 
+fn get_shuffle(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<bool> {
+    let mut shuffle = unstable_optflag!(matches, allow_unstable, "shuffle");
+    if !shuffle && allow_unstable {
+        shuffle = match env::var("RUST_TEST_SHUFFLE") {
+            Ok(val) => &val != "0",
+            Err(_) => false,
+        };
+    }
+
+    Ok(shuffle)
+}
+
+fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<u64>> {
+    let mut shuffle_seed = match unstable_optopt!(matches, allow_unstable, "shuffle-seed") {
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(n) => Some(n),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --shuffle-seed must be a number \
+                     (error: {})",
+                    e
+                ));
+            }
+        },
+        None => None,
+    };
+
+    if shuffle_seed.is_none() && allow_unstable {
+        shuffle_seed = match env::var("RUST_TEST_SHUFFLE_SEED") {
+            Ok(val) => match val.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => panic!("RUST_TEST_SHUFFLE_SEED is `{}`, should be a number.", val),
+            },
+            Err(_) => None,
+        };
+    }
+
+    Ok(shuffle_seed)
+}
+
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    match unstable_optopt!(matches, allow_unstable, "test-timeout") {
+        Some(s) => match parse_test_timeout(&s) {
+            Some(timeout) => Ok(Some(timeout)),
+            None => Err(format!(
+                "argument for --test-timeout must be a non-zero number of seconds, \
+                 optionally followed by `ms`, `s` or `m` (got `{}`)",
+                s
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_shard(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<Shard>> {
+    let shard_str = match unstable_optopt!(matches, allow_unstable, "shard") {
+        Some(shard_str) => shard_str,
+        None => return Ok(None),
+    };
+    let shard = shard_str.split_once('/').and_then(|(index, count)| {
+        Some(Shard { index: index.parse().ok()?, count: count.parse().ok()? })
+    });
+    match shard {
+        Some(shard) if 1 <= shard.index && shard.index <= shard.count => Ok(Some(shard)),
+        _ => Err(format!(
+            "argument for --shard must be of the form INDEX/COUNT, \
+             with 1 <= INDEX <= COUNT (got `{}`)",
+            shard_str
+        )),
+    }
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
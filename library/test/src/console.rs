@@ -6,6 +6,7 @@
 use std::time::Instant;
 
 use super::{
+    baseline::{Baseline, BenchComparison},
     bench::fmt_bench_samples,
     cli::TestOpts,
     event::{CompletedTest, TestEvent},
@@ -55,6 +56,10 @@ pub struct ConsoleTestState {
     pub failures: Vec<(TestDesc, Vec<u8>)>,
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub time_failures: Vec<(TestDesc, Vec<u8>)>,
+    /// Benchmark results to compare against, loaded from `--baseline`.
+    pub baseline: Option<Baseline>,
+    /// Benchmark results of this run, saved by `--save-baseline`.
+    pub bench_results: Baseline,
     pub options: Options,
 }
 
@@ -79,6 +84,8 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             failures: Vec::new(),
             not_failures: Vec::new(),
             time_failures: Vec::new(),
+            baseline: None,
+            bench_results: Baseline::new(),
             options: opts.options,
         })
     }
@@ -198,6 +205,7 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
                 bs.ns_iter_summ.median,
                 bs.ns_iter_summ.max - bs.ns_iter_summ.min,
             );
+            st.bench_results.insert(test.name.as_slice(), bs);
             st.measured += 1
         }
         TestResult::TrFailed => {
@@ -225,9 +233,9 @@ fn on_test_event(
     out: &mut dyn OutputFormatter,
 ) -> io::Result<()> {
     match (*event).clone() {
-        TestEvent::TeFiltered(ref filtered_tests) => {
+        TestEvent::TeFiltered(ref filtered_tests, shuffle_seed, shard) => {
             st.total = filtered_tests.len();
-            out.write_run_start(filtered_tests.len())?;
+            out.write_run_start(filtered_tests.len(), shuffle_seed, shard)?;
         }
         TestEvent::TeFilteredOut(filtered_out) => {
             st.filtered_out = filtered_out;
@@ -242,6 +250,11 @@ fn on_test_event(
 
             st.write_log_result(test, result, exec_time.as_ref())?;
             out.write_result(test, result, exec_time.as_ref(), &*stdout, st)?;
+            if let TestResult::TrBench(ref bs) = *result {
+                if let Some(old) = st.baseline.as_ref().and_then(|b| b.get(test.name.as_slice())) {
+                    out.write_bench_comparison(test, &BenchComparison::new(old, bs))?;
+                }
+            }
             handle_test_result(st, completed_test);
         }
     }
@@ -280,6 +293,9 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
+    if let Some(ref path) = opts.baseline {
+        st.baseline = Some(Baseline::load(path)?);
+    }
 
     // Prevent the usage of `Instant` in some cases:
     // - It's currently not supported for wasm targets.
@@ -292,6 +308,10 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
 
     assert!(st.current_test_count() == st.total);
 
+    if let Some(ref path) = opts.save_baseline {
+        st.bench_results.save(path)?;
+    }
+
     out.write_run_finish(&st)
 }
 
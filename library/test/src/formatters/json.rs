@@ -2,7 +2,9 @@
 
 use super::OutputFormatter;
 use crate::{
+    baseline::BenchComparison,
     console::{ConsoleTestState, OutputLocation},
+    options::Shard,
     test_result::TestResult,
     time,
     types::TestDesc,
@@ -60,10 +62,25 @@ fn write_event(
 }
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<Shard>,
+    ) -> io::Result<()> {
+        let shuffle_seed_json = if let Some(shuffle_seed) = shuffle_seed {
+            format!(r#", "shuffle_seed": {}"#, shuffle_seed)
+        } else {
+            String::new()
+        };
+        let shard_json = if let Some(shard) = shard {
+            format!(r#", "shard_index": {}, "shard_count": {}"#, shard.index, shard.count)
+        } else {
+            String::new()
+        };
         self.writeln_message(&*format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": {} }}"#,
-            test_count
+            r#"{{ "type": "suite", "event": "started", "test_count": {}{}{} }}"#,
+            test_count, shuffle_seed_json, shard_json
         ))
     }
 
@@ -154,6 +171,28 @@ fn write_result(
         }
     }
 
+    fn write_bench_comparison(
+        &mut self,
+        desc: &TestDesc,
+        comparison: &BenchComparison,
+    ) -> io::Result<()> {
+        self.writeln_message(&*format!(
+            "{{ \"type\": \"bench_comparison\", \
+             \"name\": \"{}\", \
+             \"baseline_median\": {}, \
+             \"median\": {}, \
+             \"change_percent\": {}, \
+             \"z_score\": {}, \
+             \"verdict\": \"{}\" }}",
+            EscapedString(desc.name.as_slice()),
+            comparison.baseline_median as usize,
+            comparison.median as usize,
+            comparison.change_pct,
+            comparison.z,
+            comparison.verdict
+        ))
+    }
+
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         self.writeln_message(&*format!(
             r#"{{ "type": "test", "event": "timeout", "name": "{}" }}"#,
@@ -3,7 +3,9 @@
 
 use super::OutputFormatter;
 use crate::{
+    baseline::BenchComparison,
     console::{ConsoleTestState, OutputLocation},
+    options::Shard,
     test_result::TestResult,
     time,
     types::{TestDesc, TestType},
@@ -12,11 +14,13 @@
 pub struct JunitFormatter<T> {
     out: OutputLocation<T>,
     results: Vec<(TestDesc, TestResult, Duration)>,
+    shuffle_seed: Option<u64>,
+    shard: Option<Shard>,
 }
 
 impl<T: Write> JunitFormatter<T> {
     pub fn new(out: OutputLocation<T>) -> Self {
-        Self { out, results: Vec::new() }
+        Self { out, results: Vec::new(), shuffle_seed: None, shard: None }
     }
 
     fn write_message(&mut self, s: &str) -> io::Result<()> {
@@ -27,7 +31,16 @@ fn write_message(&mut self, s: &str) -> io::Result<()> {
 }
 
 impl<T: Write> OutputFormatter for JunitFormatter<T> {
-    fn write_run_start(&mut self, _test_count: usize) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        _test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<Shard>,
+    ) -> io::Result<()> {
+        // The seed and shard are reported as testsuite properties, which can only be written
+        // once the testsuite node is, so keep them around until then.
+        self.shuffle_seed = shuffle_seed;
+        self.shard = shard;
         // We write xml header on run start
         self.write_message(&"<?xml version=\"1.0\" encoding=\"UTF-8\"?>")
     }
@@ -42,6 +55,15 @@ fn write_timeout(&mut self, _desc: &TestDesc) -> io::Result<()> {
         Ok(())
     }
 
+    fn write_bench_comparison(
+        &mut self,
+        _desc: &TestDesc,
+        _comparison: &BenchComparison,
+    ) -> io::Result<()> {
+        // Benchmark comparisons have no JUnit equivalent.
+        Ok(())
+    }
+
     fn write_result(
         &mut self,
         desc: &TestDesc,
@@ -69,6 +91,26 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
              >",
             state.failed, state.total, state.ignored
         ))?;
+        if self.shuffle_seed.is_some() || self.shard.is_some() {
+            self.write_message("<properties>")?;
+            if let Some(shuffle_seed) = self.shuffle_seed {
+                self.write_message(&*format!(
+                    "<property name=\"shuffle_seed\" value=\"{}\"/>",
+                    shuffle_seed
+                ))?;
+            }
+            if let Some(shard) = self.shard {
+                self.write_message(&*format!(
+                    "<property name=\"shard_index\" value=\"{}\"/>",
+                    shard.index
+                ))?;
+                self.write_message(&*format!(
+                    "<property name=\"shard_count\" value=\"{}\"/>",
+                    shard.count
+                ))?;
+            }
+            self.write_message("</properties>")?;
+        }
         for (desc, result, duration) in std::mem::replace(&mut self.results, Vec::new()) {
             let (class_name, test_name) = parse_class_name(&desc);
             match result {
@@ -1,7 +1,9 @@
 use std::{io, io::prelude::Write};
 
 use crate::{
+    baseline::BenchComparison,
     console::ConsoleTestState,
+    options::Shard,
     test_result::TestResult,
     time,
     types::{TestDesc, TestName},
@@ -18,7 +20,12 @@
 pub(crate) use self::terse::TerseFormatter;
 
 pub(crate) trait OutputFormatter {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()>;
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<Shard>,
+    ) -> io::Result<()>;
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()>;
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()>;
     fn write_result(
@@ -29,6 +36,11 @@ fn write_result(
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()>;
+    fn write_bench_comparison(
+        &mut self,
+        desc: &TestDesc,
+        comparison: &BenchComparison,
+    ) -> io::Result<()>;
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool>;
 }
 
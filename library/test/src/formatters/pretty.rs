@@ -2,8 +2,10 @@
 
 use super::OutputFormatter;
 use crate::{
-    bench::fmt_bench_samples,
+    baseline::{BenchComparison, Verdict},
+    bench::{fmt_bench_comparison, fmt_bench_samples},
     console::{ConsoleTestState, OutputLocation},
+    options::Shard,
     test_result::TestResult,
     time,
     types::TestDesc,
@@ -180,9 +182,24 @@ fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
 }
 
 impl<T: Write> OutputFormatter for PrettyFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<Shard>,
+    ) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
-        self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
+        let shuffle_seed_msg = if let Some(shuffle_seed) = shuffle_seed {
+            format!(" (shuffle seed: {})", shuffle_seed)
+        } else {
+            String::new()
+        };
+        let shard_msg =
+            if let Some(shard) = shard { format!(" (shard {})", shard) } else { String::new() };
+        self.write_plain(&format!(
+            "\nrunning {} {}{}{}\n",
+            test_count, noun, shard_msg, shuffle_seed_msg
+        ))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -225,6 +242,20 @@ fn write_result(
         self.write_plain("\n")
     }
 
+    fn write_bench_comparison(
+        &mut self,
+        _desc: &TestDesc,
+        comparison: &BenchComparison,
+    ) -> io::Result<()> {
+        self.write_plain(&format!("    change: {} (", fmt_bench_comparison(comparison)))?;
+        match comparison.verdict {
+            Verdict::Improved => self.write_pretty("improved", term::color::GREEN)?,
+            Verdict::Regressed => self.write_pretty("regressed", term::color::RED)?,
+            Verdict::NoChange => self.write_plain("no change")?,
+        }
+        self.write_plain(")\n")
+    }
+
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         self.write_plain(&format!(
             "test {} has been running for over {} seconds\n",
@@ -5,3 +5,6 @@
 pub mod exit_code;
 pub mod isatty;
 pub mod metrics;
+pub mod shard;
+pub mod shuffle;
+pub mod subprocess;
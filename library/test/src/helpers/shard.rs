@@ -0,0 +1,23 @@
+//! Helper module for splitting a test suite into disjoint shards.
+
+use crate::options::Shard;
+use crate::types::TestDescAndFn;
+
+/// Keeps only the tests belonging to `shard`.
+///
+/// A test's shard only depends on its name, so every test ends up in exactly
+/// one shard regardless of which other tests exist or how they were filtered.
+pub fn shard_tests(shard: Shard, tests: &mut Vec<TestDescAndFn>) {
+    tests.retain(|test| shard_of(test.desc.name.as_slice(), shard.count) == shard.index);
+}
+
+/// Returns the one-based shard that the test called `name` belongs to.
+fn shard_of(name: &str, count: usize) -> usize {
+    // FNV-1a, so that shards stay stable across platforms and Rust versions.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % count as u64) as usize + 1
+}
@@ -0,0 +1,90 @@
+//! Helper module for running test subprocesses with a hard time limit.
+
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Output collected from a test subprocess.
+pub struct ChildOutput {
+    /// The exit status, or `None` if the child was killed for exceeding its
+    /// time limit.
+    pub status: Option<ExitStatus>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Runs `command` to completion like `Command::output`, but kills the child
+/// once `time_limit` has elapsed.
+///
+/// Output captured before the child was killed is still returned. When the
+/// child is killed, output written afterwards by any of its own children is
+/// not waited for.
+pub fn output_with_time_limit(
+    command: &mut Command,
+    capture: bool,
+    time_limit: Duration,
+) -> io::Result<ChildOutput> {
+    if capture {
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+    }
+    let deadline = Instant::now() + time_limit;
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take().map(read_to_end_in_background);
+    let stderr = child.stderr.take().map(read_to_end_in_background);
+
+    let status = wait_until(&mut child, deadline)?;
+    if status.is_none() {
+        // `kill` fails if the child exited in the meantime, which is fine.
+        let _ = child.kill();
+        child.wait()?;
+    }
+
+    let collect = |reader: Option<(Arc<Mutex<Vec<u8>>>, thread::JoinHandle<()>)>| {
+        reader.map_or_else(Vec::new, |(buf, handle)| {
+            if status.is_some() {
+                let _ = handle.join();
+            }
+            let buf = buf.lock().unwrap_or_else(|e| e.into_inner());
+            buf.clone()
+        })
+    };
+    Ok(ChildOutput { status, stdout: collect(stdout), stderr: collect(stderr) })
+}
+
+/// Polls `child` until it exits or `deadline` passes, returning `None` in the
+/// latter case.
+fn wait_until(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    let mut interval = Duration::from_millis(1);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(interval.min(deadline - now));
+        interval = (interval * 2).min(Duration::from_millis(50));
+    }
+}
+
+/// Drains `pipe` on a separate thread so the child can't block on a full pipe.
+fn read_to_end_in_background<R: Read + Send + 'static>(
+    mut pipe: R,
+) -> (Arc<Mutex<Vec<u8>>>, thread::JoinHandle<()>) {
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let buf2 = buf.clone();
+    let handle = thread::spawn(move || {
+        let mut chunk = [0; 4096];
+        while let Ok(n) = pipe.read(&mut chunk) {
+            if n == 0 {
+                break;
+            }
+            buf2.lock().unwrap().extend_from_slice(&chunk[..n]);
+        }
+    });
+    (buf, handle)
+}
@@ -35,7 +35,7 @@
 // Public reexports
 pub use self::bench::{black_box, Bencher};
 pub use self::console::run_tests_console;
-pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic};
+pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, Shard, ShouldPanic};
 pub use self::types::TestName::*;
 pub use self::types::*;
 pub use self::ColorConfig::*;
@@ -49,7 +49,7 @@ pub mod test {
         cli::{parse_opts, TestOpts},
         filter_tests,
         helpers::metrics::{Metric, MetricMap},
-        options::{Concurrent, Options, RunIgnored, RunStrategy, ShouldPanic},
+        options::{Concurrent, Options, RunIgnored, RunStrategy, Shard, ShouldPanic},
         run_test, test_main, test_main_static,
         test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk},
         time::{TestExecTime, TestTimeOptions},
@@ -72,6 +72,7 @@ pub mod test {
     time::{Duration, Instant},
 };
 
+mod baseline;
 pub mod bench;
 mod cli;
 mod console;
@@ -90,6 +91,9 @@ pub mod test {
 use event::{CompletedTest, TestEvent};
 use helpers::concurrency::get_concurrency;
 use helpers::exit_code::get_exit_code;
+use helpers::shard::shard_tests;
+use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
+use helpers::subprocess::{output_with_time_limit, ChildOutput};
 use options::{Concurrent, RunStrategy};
 use test_result::*;
 use time::TestExecTime;
@@ -212,6 +216,18 @@ pub fn run_tests<F>(
 
     struct RunningTest {
         join_handle: Option<thread::JoinHandle<()>>,
+        desc: TestDesc,
+        start: Instant,
+        /// The hard time limit enforced by this loop. Tests running in a
+        /// subprocess enforce their own limit, so this is only set for tests
+        /// running in-process.
+        time_limit: Option<Duration>,
+    }
+
+    impl RunningTest {
+        fn deadline(&self) -> Option<Instant> {
+            self.time_limit.map(|time_limit| self.start + time_limit)
+        }
     }
 
     // Use a deterministic hasher
@@ -246,15 +262,21 @@ struct TimeoutEntry {
 
     let filtered_descs = filtered_tests.iter().map(|t| t.desc.clone()).collect();
 
-    let event = TestEvent::TeFiltered(filtered_descs);
+    let shuffle_seed = get_shuffle_seed(opts);
+
+    let event = TestEvent::TeFiltered(filtered_descs, shuffle_seed, opts.shard);
     notify_about_test_event(event)?;
 
-    let (filtered_tests, filtered_benchs): (Vec<_>, _) = filtered_tests
+    let (mut filtered_tests, filtered_benchs): (Vec<_>, _) = filtered_tests
         .into_iter()
         .enumerate()
         .map(|(i, e)| (TestId(i), e))
         .partition(|(_, e)| matches!(e.testfn, StaticTestFn(_) | DynTestFn(_)));
 
+    if let Some(shuffle_seed) = shuffle_seed {
+        shuffle_tests(shuffle_seed, &mut filtered_tests);
+    }
+
     let concurrency = opts.test_threads.unwrap_or_else(get_concurrency);
 
     let mut remaining = filtered_tests;
@@ -289,14 +311,47 @@ fn get_timed_out_tests(
         timed_out
     }
 
-    fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
-        timeout_queue.front().map(|&TimeoutEntry { timeout: next_timeout, .. }| {
+    // In-process tests can't be stopped, so tests that exceed their hard time
+    // limit are reported as failed and their threads are left running.
+    fn get_hard_timed_out_tests(running_tests: &mut TestMap) -> Vec<CompletedTest> {
+        let now = Instant::now();
+        let mut timed_out: Vec<TestId> = running_tests
+            .iter()
+            .filter(|(_, test)| test.deadline().map_or(false, |deadline| deadline <= now))
+            .map(|(&id, _)| id)
+            .collect();
+        timed_out.sort_by_key(|id| id.0);
+        timed_out
+            .into_iter()
+            .map(|id| {
+                let test = running_tests.remove(&id).unwrap();
+                let message = format!(
+                    "test exceeded its time limit of {:?} and was abandoned\n",
+                    test.time_limit.unwrap()
+                );
+                CompletedTest::new(id, test.desc, TrTimedFail, None, message.into_bytes())
+            })
+            .collect()
+    }
+
+    fn calc_timeout(
+        running_tests: &TestMap,
+        timeout_queue: &VecDeque<TimeoutEntry>,
+    ) -> Option<Duration> {
+        let next_warning = timeout_queue.front().map(|entry| entry.timeout);
+        let next_deadline = running_tests.values().filter_map(RunningTest::deadline).min();
+        next_warning.into_iter().chain(next_deadline).min().map(|next_timeout| {
             let now = Instant::now();
             if next_timeout >= now { next_timeout - now } else { Duration::new(0, 0) }
         })
     }
 
-    if concurrency == 1 {
+    // Hard time limits on in-process tests are enforced by the loop below, so
+    // those tests can't run on the main thread.
+    let in_process_time_limits = matches!(run_strategy, RunStrategy::InProcess)
+        && remaining.iter().any(|(_, test)| time_limit(opts, &test.desc).is_some());
+
+    if concurrency == 1 && !in_process_time_limits {
         while !remaining.is_empty() {
             let (id, test) = remaining.pop().unwrap();
             let event = TestEvent::TeWait(test.desc.clone());
@@ -315,6 +370,10 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 let (id, test) = remaining.pop().unwrap();
                 let timeout = time::get_default_test_timeout();
                 let desc = test.desc.clone();
+                let time_limit = match run_strategy {
+                    RunStrategy::InProcess => time_limit(opts, &desc),
+                    RunStrategy::SpawnPrimary => None,
+                };
 
                 let event = TestEvent::TeWait(desc.clone());
                 notify_about_test_event(event)?; //here no pad
@@ -327,49 +386,61 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                     tx.clone(),
                     Concurrent::Yes,
                 );
-                running_tests.insert(id, RunningTest { join_handle });
+                let running_test = RunningTest {
+                    join_handle,
+                    desc: desc.clone(),
+                    start: Instant::now(),
+                    time_limit,
+                };
+                running_tests.insert(id, running_test);
                 timeout_queue.push_back(TimeoutEntry { id, desc, timeout });
                 pending += 1;
             }
 
-            let mut res;
-            loop {
-                if let Some(timeout) = calc_timeout(&timeout_queue) {
-                    res = rx.recv_timeout(timeout);
-                    for test in get_timed_out_tests(&running_tests, &mut timeout_queue) {
-                        let event = TestEvent::TeTimeout(test);
-                        notify_about_test_event(event)?;
-                    }
+            // Wait until a test either finishes or exceeds its hard time limit.
+            let mut finished = 0;
+            while finished == 0 {
+                let res = match calc_timeout(&running_tests, &timeout_queue) {
+                    Some(timeout) => rx.recv_timeout(timeout),
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                for test in get_timed_out_tests(&running_tests, &mut timeout_queue) {
+                    let event = TestEvent::TeTimeout(test);
+                    notify_about_test_event(event)?;
+                }
 
-                    match res {
-                        Err(RecvTimeoutError::Timeout) => {
-                            // Result is not yet ready, continue waiting.
-                        }
-                        _ => {
-                            // We've got a result, stop the loop.
-                            break;
+                match res {
+                    Ok(mut completed_test) => {
+                        // Abandoned tests may still report a result, which is ignored.
+                        if let Some(running_test) = running_tests.remove(&completed_test.id) {
+                            if let Some(join_handle) = running_test.join_handle {
+                                if let Err(_) = join_handle.join() {
+                                    if let TrOk = completed_test.result {
+                                        completed_test.result = TrFailedMsg(
+                                            "panicked after reporting success".to_string(),
+                                        );
+                                    }
+                                }
+                            }
+
+                            let event = TestEvent::TeResult(completed_test);
+                            notify_about_test_event(event)?;
+                            finished += 1;
                         }
                     }
-                } else {
-                    res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
-                    break;
+                    Err(RecvTimeoutError::Timeout) => {
+                        // Result is not yet ready, continue waiting.
+                    }
+                    Err(RecvTimeoutError::Disconnected) => panic!("test result channel closed"),
                 }
-            }
 
-            let mut completed_test = res.unwrap();
-            let running_test = running_tests.remove(&completed_test.id).unwrap();
-            if let Some(join_handle) = running_test.join_handle {
-                if let Err(_) = join_handle.join() {
-                    if let TrOk = completed_test.result {
-                        completed_test.result =
-                            TrFailedMsg("panicked after reporting success".to_string());
-                    }
+                for completed_test in get_hard_timed_out_tests(&mut running_tests) {
+                    let event = TestEvent::TeResult(completed_test);
+                    notify_about_test_event(event)?;
+                    finished += 1;
                 }
             }
-
-            let event = TestEvent::TeResult(completed_test);
-            notify_about_test_event(event)?;
-            pending -= 1;
+            pending -= finished;
         }
     }
 
@@ -388,6 +459,12 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
     Ok(())
 }
 
+/// Returns the hard time limit for `desc`: its own `#[test_timeout]` if it has
+/// one, and `--test-timeout` otherwise.
+fn time_limit(opts: &TestOpts, desc: &TestDesc) -> Option<Duration> {
+    desc.timeout().ok().flatten().or(opts.test_timeout)
+}
+
 pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     let mut filtered = tests;
     let matches_filter = |test: &TestDescAndFn, filter: &str| {
@@ -424,6 +501,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         RunIgnored::No => {}
     }
 
+    // Only keep the tests in the requested shard
+    if let Some(shard) = opts.shard {
+        shard_tests(shard, &mut filtered);
+    }
+
     // Sort the tests alphabetically
     filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
 
@@ -471,11 +553,18 @@ pub fn run_test(
         return None;
     }
 
+    if let Err(msg) = desc.timeout() {
+        let message = CompletedTest::new(id, desc, TrFailedMsg(msg), None, Vec::new());
+        monitor_ch.send(message).unwrap();
+        return None;
+    }
+
     struct TestRunOpts {
         pub strategy: RunStrategy,
         pub nocapture: bool,
         pub concurrency: Concurrent,
         pub time: Option<time::TestTimeOptions>,
+        pub time_limit: Option<Duration>,
     }
 
     fn run_test_inner(
@@ -505,6 +594,7 @@ fn run_test_inner(
                 opts.time.is_some(),
                 monitor_ch,
                 opts.time,
+                opts.time_limit,
             ),
         };
 
@@ -532,8 +622,13 @@ fn run_test_inner(
         }
     }
 
-    let test_run_opts =
-        TestRunOpts { strategy, nocapture: opts.nocapture, concurrency, time: opts.time_options };
+    let test_run_opts = TestRunOpts {
+        strategy,
+        nocapture: opts.nocapture,
+        concurrency,
+        time: opts.time_options,
+        time_limit: time_limit(opts, &desc),
+    };
 
     match testfn {
         DynBenchFn(bencher) => {
@@ -611,7 +706,9 @@ fn run_test_in_process(
     };
     let stdout = data.lock().unwrap_or_else(|e| e.into_inner()).to_vec();
     let message = CompletedTest::new(id, desc, test_result, exec_time, stdout);
-    monitor_ch.send(message).unwrap();
+    // The receiver is gone if this test was abandoned for exceeding its time
+    // limit and the test run has since finished.
+    let _ = monitor_ch.send(message);
 }
 
 fn spawn_test_subprocess(
@@ -621,6 +718,7 @@ fn spawn_test_subprocess(
     report_time: bool,
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
+    time_limit: Option<Duration>,
 ) {
     let (result, test_output, exec_time) = (|| {
         let args = env::args().collect::<Vec<_>>();
@@ -634,7 +732,13 @@ fn spawn_test_subprocess(
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let output = match time_limit {
+            Some(time_limit) => output_with_time_limit(&mut command, !nocapture, time_limit),
+            None => command.output().map(|std::process::Output { stdout, stderr, status }| {
+                ChildOutput { status: Some(status), stdout, stderr }
+            }),
+        };
+        let output = match output {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
@@ -646,11 +750,25 @@ fn spawn_test_subprocess(
             TestExecTime(duration)
         });
 
-        let std::process::Output { stdout, stderr, status } = output;
+        let ChildOutput { stdout, stderr, status } = output;
         let mut test_output = stdout;
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
+        let status = match status {
+            Some(status) => status,
+            None => {
+                let time_limit = time_limit.unwrap();
+                write!(
+                    &mut test_output,
+                    "test exceeded its time limit of {:?} and was killed",
+                    time_limit
+                )
+                .unwrap();
+                return (TrTimedFail, test_output, exec_time);
+            }
+        };
+
         let result = match (|| -> Result<TestResult, String> {
             let exit_code = get_exit_code(status)?;
             Ok(get_result_from_exit_code(&desc, exit_code, &time_opts, &exec_time))
@@ -1,5 +1,7 @@
 //! Enums denoting options for test execution.
 
+use std::fmt;
+
 /// Whether to execute tests concurrently or not
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Concurrent {
@@ -64,6 +66,21 @@ pub enum RunStrategy {
     SpawnPrimary,
 }
 
+/// The slice of the test suite to run, selected with `--shard`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// The one-based index of this shard.
+    pub index: usize,
+    /// The total number of shards.
+    pub count: usize,
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
 /// Options for the test run defined by the caller (instead of CLI arguments).
 /// In case we want to add other options as well, just add them in this struct.
 #[derive(Copy, Clone, Debug)]
@@ -566,6 +566,17 @@ fn test_unif25() {
 fn test_sum_f64s() {
     assert_eq!([0.5f64, 3.2321f64, 1.5678f64].sum(), 5.2999);
 }
+#[test]
+fn test_mann_whitney_z() {
+    // Completely separated samples.
+    assert_approx_eq!(mann_whitney_z(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), -1.9639610121239315);
+    assert_approx_eq!(mann_whitney_z(&[4.0, 5.0, 6.0], &[1.0, 2.0, 3.0]), 1.9639610121239315);
+    // Identical samples, which are all ties.
+    assert_eq!(mann_whitney_z(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]), 0.0);
+    assert_eq!(mann_whitney_z(&[2.0, 2.0], &[2.0, 2.0, 2.0]), 0.0);
+    assert_eq!(mann_whitney_z(&[], &[1.0]), 0.0);
+}
+
 #[test]
 fn test_sum_f64_between_ints_that_sum_to_0() {
     assert_eq!([1e30f64, 1.2f64, -1e30f64].sum(), 1.2);
@@ -1,7 +1,8 @@
 use super::*;
 
 use crate::{
-    bench::Bencher,
+    baseline::{Baseline, BenchComparison, Verdict},
+    bench::{BenchSamples, Bencher},
     console::OutputLocation,
     formatters::PrettyFormatter,
     options::OutputFormat,
@@ -25,8 +26,10 @@
         // ShouldPanic, StaticTestName, TestDesc, TestDescAndFn, TestOpts, TestTimeOptions,
         // TestType, TrFailedMsg, TrIgnored, TrOk,
     },
+    stats::Summary,
     time::{TestTimeOptions, TimeThreshold},
 };
+use std::path::PathBuf;
 use std::sync::mpsc::channel;
 use std::time::Duration;
 
@@ -48,6 +51,12 @@ fn new() -> TestOpts {
             test_threads: None,
             skip: vec![],
             time_options: None,
+            shuffle: false,
+            shuffle_seed: None,
+            test_timeout: None,
+            shard: None,
+            save_baseline: None,
+            baseline: None,
             options: Options::new(),
         }
     }
@@ -65,6 +74,8 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 #[cfg(not(bootstrap))]
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(move || {})),
@@ -79,6 +90,8 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 #[cfg(not(bootstrap))]
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(move || {})),
@@ -101,6 +114,8 @@ fn f() {
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -124,6 +139,8 @@ fn f() {}
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -151,6 +168,8 @@ fn f() {
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -178,6 +197,8 @@ fn f() {
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -210,6 +231,8 @@ fn f() {
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -246,6 +269,8 @@ fn f() {
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -274,6 +299,8 @@ fn f() {}
                 compile_fail: false,
                 #[cfg(not(bootstrap))]
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(f)),
@@ -310,6 +337,8 @@ fn f() {}
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -347,6 +376,8 @@ fn f() {}
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -388,6 +419,8 @@ fn typed_test_desc(test_type: TestType) -> TestDesc {
         compile_fail: false,
         #[cfg(not(bootstrap))]
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type,
     }
 }
@@ -503,6 +536,8 @@ pub fn exclude_should_panic_option() {
             compile_fail: false,
             #[cfg(not(bootstrap))]
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(move || {})),
@@ -529,6 +564,8 @@ fn tests() -> Vec<TestDescAndFn> {
                     compile_fail: false,
                     #[cfg(not(bootstrap))]
                     no_run: false,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
                     test_type: TestType::Unknown,
                 },
                 testfn: DynTestFn(Box::new(move || {})),
@@ -625,6 +662,8 @@ fn testfn() {}
                     compile_fail: false,
                     #[cfg(not(bootstrap))]
                     no_run: false,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
                     test_type: TestType::Unknown,
                 },
                 testfn: DynTestFn(Box::new(testfn)),
@@ -654,6 +693,234 @@ fn testfn() {}
     }
 }
 
+fn shuffle_test_ids(shuffle_seed: u64, names: &[&'static str]) -> Vec<TestId> {
+    let mut tests: Vec<_> = names
+        .iter()
+        .enumerate()
+        .map(|(i, &name)| {
+            let test = TestDescAndFn {
+                desc: TestDesc {
+                    name: StaticTestName(name),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                    allow_fail: false,
+                    #[cfg(not(bootstrap))]
+                    compile_fail: false,
+                    #[cfg(not(bootstrap))]
+                    no_run: false,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
+                    test_type: TestType::Unknown,
+                },
+                testfn: DynTestFn(Box::new(move || {})),
+            };
+            (TestId(i), test)
+        })
+        .collect();
+    shuffle_tests(shuffle_seed, &mut tests);
+    tests.into_iter().map(|(id, _)| id).collect()
+}
+
+#[test]
+pub fn shuffle_tests_is_deterministic() {
+    let names = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+
+    let first = shuffle_test_ids(42, &names);
+    let second = shuffle_test_ids(42, &names);
+    assert_eq!(first, second);
+
+    // Every test is still run exactly once.
+    let mut sorted = first.clone();
+    sorted.sort_by_key(|id| id.0);
+    assert_eq!(sorted, (0..names.len()).map(TestId).collect::<Vec<_>>());
+
+    // With ten tests, some seed out of a handful must produce another order.
+    assert!((0..8).any(|seed| shuffle_test_ids(seed, &names) != first));
+}
+
+#[test]
+fn parse_shuffle_flags() {
+    let args = vec!["progname".to_string(), "--shuffle".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--shuffle-seed".to_string(),
+        "1234".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.shuffle_seed, Some(1234));
+    assert_eq!(get_shuffle_seed(&opts), Some(1234));
+
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--shuffle-seed".to_string(),
+        "not-a-number".to_string(),
+    ];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+pub fn shards_partition_tests() {
+    let names: Vec<String> = (0..100).map(|i| format!("test_{}", i)).collect();
+    let tests = || -> Vec<TestDescAndFn> {
+        names
+            .iter()
+            .map(|name| TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(name.clone()),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                    allow_fail: false,
+                    #[cfg(not(bootstrap))]
+                    compile_fail: false,
+                    #[cfg(not(bootstrap))]
+                    no_run: false,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
+                    test_type: TestType::Unknown,
+                },
+                testfn: DynTestFn(Box::new(move || {})),
+            })
+            .collect()
+    };
+
+    let mut seen = Vec::new();
+    for index in 1..=3 {
+        let opts = TestOpts { shard: Some(Shard { index, count: 3 }), ..TestOpts::new() };
+        let shard = filter_tests(&opts, tests());
+        assert!(!shard.is_empty());
+
+        // Sharding is deterministic.
+        let again = filter_tests(&opts, tests());
+        assert!(shard.iter().map(|t| &t.desc.name).eq(again.iter().map(|t| &t.desc.name)));
+
+        seen.extend(shard.into_iter().map(|t| t.desc.name.to_string()));
+    }
+
+    // Every test runs in exactly one shard.
+    seen.sort();
+    let mut expected = names.clone();
+    expected.sort();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn parse_shard_flag() {
+    let parse = |shard: &str| {
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--shard".to_string(),
+            shard.to_string(),
+        ];
+        parse_opts(&args).unwrap().map(|opts| opts.shard)
+    };
+    assert_eq!(parse("1/1"), Ok(Some(Shard { index: 1, count: 1 })));
+    assert_eq!(parse("2/5"), Ok(Some(Shard { index: 2, count: 5 })));
+    assert!(parse("0/5").is_err());
+    assert!(parse("6/5").is_err());
+    assert!(parse("1/0").is_err());
+    assert!(parse("1").is_err());
+    assert!(parse("a/b").is_err());
+
+    let args = vec!["progname".to_string(), "--shard".to_string(), "1/2".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+fn parse_test_timeout_flag() {
+    let parse = |timeout: &str| {
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--test-timeout".to_string(),
+            timeout.to_string(),
+        ];
+        parse_opts(&args).unwrap().map(|opts| opts.test_timeout)
+    };
+    assert_eq!(parse("30"), Ok(Some(Duration::from_secs(30))));
+    assert_eq!(parse("250ms"), Ok(Some(Duration::from_millis(250))));
+    assert_eq!(parse("5s"), Ok(Some(Duration::from_secs(5))));
+    assert_eq!(parse("2m"), Ok(Some(Duration::from_secs(120))));
+    assert!(parse("0").is_err());
+    assert!(parse("1h").is_err());
+    assert!(parse("-1s").is_err());
+
+    let args = vec!["progname".to_string(), "--test-timeout".to_string(), "5s".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+#[cfg(not(any(target_os = "emscripten", target_arch = "wasm32")))]
+pub fn run_tests_abandons_timed_out_test() {
+    let desc = |name, timeout| TestDesc {
+        name: StaticTestName(name),
+        ignore: false,
+        should_panic: ShouldPanic::No,
+        allow_fail: false,
+        compile_fail: false,
+        no_run: false,
+        timeout,
+        test_type: TestType::Unknown,
+    };
+    let (release_tx, release_rx) = channel();
+    let (finished_tx, finished_rx) = channel();
+    let tests = vec![
+        TestDescAndFn {
+            desc: desc("hangs", Some("50ms")),
+            // Blocks until the test run is over, long after its time limit.
+            testfn: DynTestFn(Box::new(move || {
+                release_rx.recv().unwrap();
+                finished_tx.send(()).unwrap();
+            })),
+        },
+        TestDescAndFn { desc: desc("passes", None), testfn: DynTestFn(Box::new(move || {})) },
+    ];
+
+    // Hard time limits are enforced even when running tests one at a time.
+    let opts = TestOpts { run_tests: true, test_threads: Some(1), ..TestOpts::new() };
+    let mut results = Vec::new();
+    run_tests(&opts, tests, |event| {
+        if let TestEvent::TeResult(completed_test) = event {
+            results.push((completed_test.desc.name.to_string(), completed_test.result));
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(results, vec![("hangs".to_string(), TrTimedFail), ("passes".to_string(), TrOk)]);
+
+    // Let the abandoned test finish rather than leaking its thread.
+    release_tx.send(()).unwrap();
+    finished_rx.recv().unwrap();
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+pub fn malformed_test_timeout_fails_test() {
+    let desc = TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName("whatever"),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+            compile_fail: false,
+            no_run: false,
+            timeout: Some("soon"),
+            test_type: TestType::Unknown,
+        },
+        testfn: DynTestFn(Box::new(move || {})),
+    };
+    let (tx, rx) = channel();
+    run_test(&TestOpts::new(), false, TestId(0), desc, RunStrategy::InProcess, tx, Concurrent::No);
+    let result = rx.recv().unwrap().result;
+    assert!(matches!(result, TrFailedMsg(_)));
+}
+
 #[test]
 pub fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -706,6 +973,8 @@ fn f(_: &mut Bencher) {}
         compile_fail: false,
         #[cfg(not(bootstrap))]
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -730,6 +999,8 @@ fn f(b: &mut Bencher) {
         compile_fail: false,
         #[cfg(not(bootstrap))]
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -737,6 +1008,62 @@ fn f(b: &mut Bencher) {
     rx.recv().unwrap();
 }
 
+fn bench_samples(samples: &[f64]) -> BenchSamples {
+    BenchSamples { ns_iter_summ: Summary::new(samples), mb_s: 0, samples: samples.to_vec() }
+}
+
+#[test]
+pub fn baseline_round_trip() {
+    let mut baseline = Baseline::new();
+    baseline.insert("fast", bench_samples(&[1.5, 2.0, 2.25]));
+    baseline.insert("odd\tname\\", bench_samples(&[1000.0, 1e-7, 12345.678]));
+
+    let path = std::env::temp_dir()
+        .join(format!("libtest-baseline-round-trip-{}.tsv", std::process::id()));
+    baseline.save(&path).unwrap();
+    let loaded = Baseline::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), baseline);
+}
+
+#[test]
+pub fn bench_comparison_verdicts() {
+    let old: Vec<f64> = (0..50).map(|i| 100.0 + i as f64 % 5.0).collect();
+    let slower: Vec<f64> = old.iter().map(|ns| ns * 1.1).collect();
+    let faster: Vec<f64> = old.iter().map(|ns| ns * 0.9).collect();
+    let noisy: Vec<f64> = old.iter().map(|ns| ns * 1.001).collect();
+
+    let compare = |new: &[f64]| BenchComparison::new(&bench_samples(&old), &bench_samples(new));
+    assert_eq!(compare(&old).verdict, Verdict::NoChange);
+    assert_eq!(compare(&slower).verdict, Verdict::Regressed);
+    assert_eq!(compare(&faster).verdict, Verdict::Improved);
+    // Significant, but below the noise threshold.
+    assert_eq!(compare(&noisy).verdict, Verdict::NoChange);
+
+    let regression = compare(&slower);
+    assert_eq!(regression.baseline_median, 102.0);
+    assert!((regression.change_pct - 10.0).abs() < 1e-9);
+    assert!(regression.z < -1.96);
+}
+
+#[test]
+fn parse_baseline_flags() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--save-baseline".to_string(),
+        "new.tsv".to_string(),
+        "--baseline".to_string(),
+        "old.tsv".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.save_baseline, Some(PathBuf::from("new.tsv")));
+    assert_eq!(opts.baseline, Some(PathBuf::from("old.tsv")));
+
+    let args = vec!["progname".to_string(), "--baseline".to_string(), "old.tsv".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
 #[test]
 fn should_sort_failures_before_printing_them() {
     let test_a = TestDesc {
@@ -748,6 +1075,8 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         #[cfg(not(bootstrap))]
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -760,6 +1089,8 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         #[cfg(not(bootstrap))]
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -780,6 +1111,8 @@ fn should_sort_failures_before_printing_them() {
         options: Options::new(),
         not_failures: Vec::new(),
         time_failures: Vec::new(),
+        baseline: None,
+        bench_results: Baseline::new(),
     };
 
     out.write_failures(&st).unwrap();
@@ -61,6 +61,25 @@ pub fn get_default_test_timeout() -> Instant {
     Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S)
 }
 
+/// Parses a hard test timeout of the form `<number>[ms|s|m]`, where a bare
+/// number is a count of seconds. Zero durations are rejected.
+///
+/// This is used for both `--test-timeout` and the `#[test_timeout]` attribute,
+/// which the compiler passes through unparsed.
+pub fn parse_test_timeout(s: &str) -> Option<Duration> {
+    let (number, multiplier) = if let Some(ms) = s.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        (secs, 1_000)
+    } else if let Some(mins) = s.strip_suffix('m') {
+        (mins, 60_000)
+    } else {
+        (s, 1_000)
+    };
+    let millis = u64::from_str(number).ok()?.checked_mul(multiplier)?;
+    if millis == 0 { None } else { Some(Duration::from_millis(millis)) }
+}
+
 /// The measured execution time of a unit test.
 #[derive(Debug, Clone, PartialEq)]
 pub struct TestExecTime(pub Duration);
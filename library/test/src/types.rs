@@ -2,9 +2,11 @@
 
 use std::borrow::Cow;
 use std::fmt;
+use std::time::Duration;
 
 use super::bench::Bencher;
 use super::options;
+use super::time::parse_test_timeout;
 
 pub use NamePadding::*;
 pub use TestFn::*;
@@ -128,6 +130,10 @@ pub struct TestDesc {
     pub compile_fail: bool,
     #[cfg(not(bootstrap))]
     pub no_run: bool,
+    /// A hard time limit set with `#[test_timeout]`, overriding `--test-timeout`.
+    /// It is kept as written in the attribute and parsed when the test is run.
+    #[cfg(not(bootstrap))]
+    pub timeout: Option<&'static str>,
     pub test_type: TestType,
 }
 
@@ -174,6 +180,28 @@ pub fn test_mode(&self) -> Option<&'static str> {
     pub fn test_mode(&self) -> Option<&'static str> {
         None
     }
+
+    /// Returns the hard time limit set on the test itself, if any, or an error
+    /// if its `#[test_timeout]` value is malformed.
+    #[cfg(not(bootstrap))]
+    pub fn timeout(&self) -> Result<Option<Duration>, String> {
+        match self.timeout {
+            Some(timeout) => match parse_test_timeout(timeout) {
+                Some(duration) => Ok(Some(duration)),
+                None => Err(format!(
+                    "malformed `#[test_timeout]` value `{}`: expected a non-zero number of \
+                     seconds, optionally followed by `ms`, `s` or `m`",
+                    timeout
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
+    #[cfg(bootstrap)]
+    pub fn timeout(&self) -> Result<Option<Duration>, String> {
+        Ok(None)
+    }
 }
 
 #[derive(Debug)]
@@ -8,6 +8,7 @@
 use rustc_data_structures::fx::FxHashMap;
 use rustc_session::config::{
     self, parse_crate_types_from_list, parse_externs, parse_target_triple, CrateType,
+    InstrumentCoverage,
 };
 use rustc_session::config::{get_cmd_lint_options, nightly_options};
 use rustc_session::config::{CodegenOptions, DebuggingOptions, ErrorOutputType, Externs};
@@ -31,6 +32,7 @@
 crate enum OutputFormat {
     Json,
     Html,
+    Markdown,
 }
 
 impl Default for OutputFormat {
@@ -52,6 +54,7 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "json" => Ok(OutputFormat::Json),
             "html" => Ok(OutputFormat::Html),
+            "markdown" => Ok(OutputFormat::Markdown),
             _ => Err(format!("unknown output format `{}`", value)),
         }
     }
@@ -111,6 +114,12 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
     /// Optional path to persist the doctest executables to, defaults to a
     /// temporary directory if not set.
     crate persist_doctests: Option<PathBuf>,
+    /// Whether to compile compatible doctests together into a single binary instead of building
+    /// one binary per doctest.
+    crate merge_doctests: bool,
+    /// Optional path to write the coverage profiles of the doctest executables into, when
+    /// running doctests with `-Z instrument-coverage`.
+    crate doctest_profile_dir: Option<PathBuf>,
     /// Runtool to run doctests with
     crate runtool: Option<String>,
     /// Arguments to pass to the runtool
@@ -141,6 +150,8 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
     /// Whether to run the `calculate-doc-coverage` pass, which counts the number of public items
     /// with and without documentation.
     crate show_coverage: bool,
+    /// The percentage of documented items under which `--show-coverage` fails.
+    crate coverage_threshold: Option<f64>,
 
     // Options that alter generated documentation pages
     /// Crate version to note on the sidebar of generated docs.
@@ -156,6 +167,9 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
     crate run_check: bool,
     /// Whether doctests should emit unused externs
     crate json_unused_externs: bool,
+    /// The JSON output of an older version of the crate to compare the public API of `input`
+    /// with, instead of generating documentation.
+    crate api_diff: Option<PathBuf>,
 }
 
 impl fmt::Debug for Options {
@@ -188,10 +202,13 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("test_args", &self.test_args)
             .field("test_run_directory", &self.test_run_directory)
             .field("persist_doctests", &self.persist_doctests)
+            .field("merge_doctests", &self.merge_doctests)
+            .field("doctest_profile_dir", &self.doctest_profile_dir)
             .field("default_passes", &self.default_passes)
             .field("manual_passes", &self.manual_passes)
             .field("display_warnings", &self.display_warnings)
             .field("show_coverage", &self.show_coverage)
+            .field("coverage_threshold", &self.coverage_threshold)
             .field("crate_version", &self.crate_version)
             .field("render_options", &self.render_options)
             .field("runtool", &self.runtool)
@@ -199,6 +216,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("enable-per-target-ignores", &self.enable_per_target_ignores)
             .field("run_check", &self.run_check)
             .field("no_run", &self.no_run)
+            .field("api_diff", &self.api_diff)
             .finish()
     }
 }
@@ -561,6 +579,27 @@ fn println_condition(condition: Condition) {
 
         let show_coverage = matches.opt_present("show-coverage");
 
+        let coverage_threshold = match matches.opt_str("coverage-threshold") {
+            Some(s) => match s.parse::<f64>() {
+                Ok(threshold) if (0.0..=100.0).contains(&threshold) => {
+                    if !show_coverage {
+                        diag.struct_err("`--coverage-threshold` requires `--show-coverage`").emit();
+                        return Err(1);
+                    }
+                    Some(threshold)
+                }
+                _ => {
+                    diag.struct_err(&format!(
+                        "`--coverage-threshold` must be a percentage between 0 and 100, got `{}`",
+                        s
+                    ))
+                    .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+
         let default_passes = if matches.opt_present("no-defaults") {
             passes::DefaultPassOption::None
         } else if show_coverage {
@@ -582,9 +621,10 @@ fn println_condition(condition: Condition) {
             Some(s) => match OutputFormat::try_from(s.as_str()) {
                 Ok(out_fmt) => {
                     if !out_fmt.is_json() && show_coverage {
-                        diag.struct_err(
-                            "html output format isn't supported for the --show-coverage option",
-                        )
+                        diag.struct_err(&format!(
+                            "{} output format isn't supported for the --show-coverage option",
+                            s
+                        ))
                         .emit();
                         return Err(1);
                     }
@@ -613,7 +653,14 @@ fn println_condition(condition: Condition) {
         let static_root_path = matches.opt_str("static-root-path");
         let generate_search_filter = !matches.opt_present("disable-per-crate-search");
         let test_run_directory = matches.opt_str("test-run-directory").map(PathBuf::from);
-        let persist_doctests = matches.opt_str("persist-doctests").map(PathBuf::from);
+        let doctest_profile_dir = matches.opt_str("doctest-profile-dir").map(PathBuf::from);
+        // The coverage profiles can't be used without the executables that wrote them, so keep
+        // those next to the profiles unless told otherwise.
+        let persist_doctests = matches
+            .opt_str("persist-doctests")
+            .map(PathBuf::from)
+            .or_else(|| doctest_profile_dir.clone());
+        let merge_doctests = matches.opt_present("merge-doctests");
         let test_builder = matches.opt_str("test-builder").map(PathBuf::from);
         let codegen_options_strs = matches.opt_strs("C");
         let debugging_opts_strs = matches.opt_strs("Z");
@@ -627,6 +674,21 @@ fn println_condition(condition: Condition) {
         let run_check = matches.opt_present("check");
         let generate_redirect_map = matches.opt_present("generate-redirect-map");
         let show_type_layout = matches.opt_present("show-type-layout");
+        let api_diff = matches.opt_str("api-diff").map(PathBuf::from);
+
+        if doctest_profile_dir.is_some()
+            && matches!(debugging_opts.instrument_coverage, None | Some(InstrumentCoverage::Off))
+        {
+            diag.struct_err("`--doctest-profile-dir` requires `-Z instrument-coverage`").emit();
+            return Err(1);
+        }
+
+        if api_diff.is_some() && input.extension() != Some(OsStr::new("json")) {
+            diag.struct_err("the input of `--api-diff` must be the JSON output of rustdoc")
+                .help("generate it with `--output-format json`")
+                .emit();
+            return Err(1);
+        }
 
         let (lint_opts, describe_lints, lint_cap, _) =
             get_cmd_lint_options(matches, error_format, &debugging_opts);
@@ -656,9 +718,12 @@ fn println_condition(condition: Condition) {
             manual_passes,
             display_warnings,
             show_coverage,
+            coverage_threshold,
             crate_version,
             test_run_directory,
             persist_doctests,
+            merge_doctests,
+            doctest_profile_dir,
             runtool,
             runtool_args,
             enable_per_target_ignores,
@@ -696,6 +761,7 @@ fn println_condition(condition: Condition) {
             crate_name,
             output_format,
             json_unused_externs,
+            api_diff,
         })
     }
 
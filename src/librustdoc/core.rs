@@ -4,6 +4,7 @@
 use rustc_driver::abort_on_err;
 use rustc_errors::emitter::{Emitter, EmitterWriter};
 use rustc_errors::json::JsonEmitter;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_feature::UnstableFeatures;
 use rustc_hir::def::Res;
 use rustc_hir::def_id::{DefId, LocalDefId};
@@ -81,6 +82,8 @@
     crate inlined: FxHashSet<FakeDefId>,
     /// Used by `calculate_doc_coverage`.
     crate output_format: OutputFormat,
+    /// Used by `calculate_doc_coverage`.
+    crate coverage_threshold: Option<f64>,
 }
 
 impl<'tcx> DocContext<'tcx> {
@@ -140,8 +143,8 @@ impl<'tcx> DocContext<'tcx> {
 
 /// Creates a new diagnostic `Handler` that can be used to emit warnings and errors.
 ///
-/// If the given `error_format` is `ErrorOutputType::Json` and no `SourceMap` is given, a new one
-/// will be created for the handler.
+/// If the given `error_format` is `ErrorOutputType::Json` or `ErrorOutputType::Sarif` and no
+/// `SourceMap` is given, a new one will be created for the handler.
 crate fn new_handler(
     error_format: ErrorOutputType,
     source_map: Option<Lrc<source_map::SourceMap>>,
@@ -178,6 +181,12 @@ impl<'tcx> DocContext<'tcx> {
                 .ui_testing(debugging_opts.ui_testing),
             )
         }
+        ErrorOutputType::Sarif { pretty } => {
+            let source_map = source_map.unwrap_or_else(|| {
+                Lrc::new(source_map::SourceMap::new(source_map::FilePathMapping::empty()))
+            });
+            Box::new(SarifEmitter::stderr(None, source_map, pretty))
+        }
     };
 
     rustc_errors::Handler::with_emitter_and_flags(
@@ -320,6 +329,7 @@ impl<'tcx> DocContext<'tcx> {
     manual_passes: Vec<String>,
     render_options: RenderOptions,
     output_format: OutputFormat,
+    coverage_threshold: Option<f64>,
 ) -> (clean::Crate, RenderOptions, Cache) {
     // Certain queries assume that some checks were run elsewhere
     // (see https://github.com/rust-lang/rust/pull/73566#issuecomment-656954425),
@@ -381,6 +391,7 @@ impl<'tcx> DocContext<'tcx> {
         cache: Cache::new(access_levels, render_options.document_private),
         inlined: FxHashSet::default(),
         output_format,
+        coverage_threshold,
         render_options,
     };
 
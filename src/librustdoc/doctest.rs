@@ -8,7 +8,7 @@
 use rustc_interface::interface;
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
-use rustc_session::config::{self, CrateType, ErrorOutputType};
+use rustc_session::config::{self, CrateType, ErrorOutputType, InstrumentCoverage};
 use rustc_session::{lint, DiagnosticOutput, Session};
 use rustc_span::edition::Edition;
 use rustc_span::source_map::SourceMap;
@@ -20,7 +20,7 @@
 use std::env;
 use std::io::{self, Write};
 use std::panic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command, Stdio};
 use std::str;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -32,6 +32,10 @@
 use crate::lint::init_lints;
 use crate::passes::span_of_attrs;
 
+use self::merged::MergedDoctests;
+
+mod merged;
+
 #[derive(Clone, Default)]
 crate struct TestOptions {
     /// Whether to disable the default `extern crate my_crate;` when creating doctests.
@@ -271,6 +275,26 @@ enum DirState {
 }
 
 impl DirState {
+    /// Returns the directory to compile the doctest `name` into: a directory of that name in the
+    /// `--persist-doctests` directory if there is one, and a temporary directory otherwise.
+    fn new(options: &Options, name: &str) -> DirState {
+        if let Some(mut path) = options.persist_doctests.clone() {
+            path.push(name);
+
+            std::fs::create_dir_all(&path)
+                .expect("Couldn't create directory for doctest executables");
+
+            DirState::Perm(path)
+        } else {
+            DirState::Temp(
+                TempFileBuilder::new()
+                    .prefix("rustdoctest")
+                    .tempdir()
+                    .expect("rustdoc needs a tempdir"),
+            )
+        }
+    }
+
     fn path(&self) -> &std::path::Path {
         match self {
             DirState::Temp(t) => t.path(),
@@ -299,9 +323,6 @@ fn run_test(
     should_panic: bool,
     no_run: bool,
     as_test_harness: bool,
-    runtool: Option<String>,
-    runtool_args: Vec<String>,
-    target: TargetTriple,
     compile_fail: bool,
     mut error_codes: Vec<String>,
     opts: &TestOptions,
@@ -316,22 +337,9 @@ fn run_test(
 
     let output_file = outdir.path().join("rust_out");
 
-    let rustc_binary = options
-        .test_builder
-        .as_deref()
-        .unwrap_or_else(|| rustc_interface::util::rustc_path().expect("found rustc"));
-    let mut compiler = Command::new(&rustc_binary);
-    compiler.arg("--crate-type").arg("bin");
-    for cfg in &options.cfgs {
-        compiler.arg("--cfg").arg(&cfg);
-    }
-    if let Some(sysroot) = options.maybe_sysroot {
-        compiler.arg("--sysroot").arg(sysroot);
-    }
-    compiler.arg("--edition").arg(&edition.to_string());
+    let mut compiler = rustc_command(&options, edition, &output_file);
     compiler.env("UNSTABLE_RUSTDOC_TEST_PATH", path);
     compiler.env("UNSTABLE_RUSTDOC_TEST_LINE", format!("{}", line as isize - line_offset as isize));
-    compiler.arg("-o").arg(&output_file);
     if as_test_harness {
         compiler.arg("--test");
     }
@@ -341,28 +349,9 @@ fn run_test(
         compiler.arg("-Z").arg("unstable-options");
         compiler.arg("-W").arg("unused_crate_dependencies");
     }
-    for lib_str in &options.lib_strs {
-        compiler.arg("-L").arg(&lib_str);
-    }
-    for extern_str in &options.extern_strs {
-        compiler.arg("--extern").arg(&extern_str);
-    }
-    compiler.arg("-Ccodegen-units=1");
-    for codegen_options_str in &options.codegen_options_strs {
-        compiler.arg("-C").arg(&codegen_options_str);
-    }
-    for debugging_option_str in &options.debugging_opts_strs {
-        compiler.arg("-Z").arg(&debugging_option_str);
-    }
     if no_run && !compile_fail {
         compiler.arg("--emit=metadata");
     }
-    compiler.arg("--target").arg(match target {
-        TargetTriple::TargetTriple(s) => s,
-        TargetTriple::TargetPath(path) => {
-            path.to_str().expect("target path must be valid unicode").to_string()
-        }
-    });
     if let ErrorOutputType::HumanReadable(kind) = options.error_format {
         let (short, color_config) = kind.unzip();
 
@@ -448,18 +437,79 @@ fn drop(&mut self) {
     }
 
     // Run the code!
+    run_binary(&output_file, &[], &options, test_id, should_panic)
+}
+
+/// Creates the `rustc` invocation shared by all doctests, which compiles a binary for the
+/// requested target into `output_file`. The caller still has to pass the source to compile.
+fn rustc_command(options: &Options, edition: Edition, output_file: &Path) -> Command {
+    let rustc_binary = options
+        .test_builder
+        .as_deref()
+        .unwrap_or_else(|| rustc_interface::util::rustc_path().expect("found rustc"));
+    let mut compiler = Command::new(&rustc_binary);
+    compiler.arg("--crate-type").arg("bin");
+    for cfg in &options.cfgs {
+        compiler.arg("--cfg").arg(&cfg);
+    }
+    if let Some(sysroot) = &options.maybe_sysroot {
+        compiler.arg("--sysroot").arg(sysroot);
+    }
+    compiler.arg("--edition").arg(&edition.to_string());
+    compiler.arg("-o").arg(output_file);
+    for lib_str in &options.lib_strs {
+        compiler.arg("-L").arg(&lib_str);
+    }
+    for extern_str in &options.extern_strs {
+        compiler.arg("--extern").arg(&extern_str);
+    }
+    compiler.arg("-Ccodegen-units=1");
+    for codegen_options_str in &options.codegen_options_strs {
+        compiler.arg("-C").arg(&codegen_options_str);
+    }
+    for debugging_option_str in &options.debugging_opts_strs {
+        compiler.arg("-Z").arg(&debugging_option_str);
+    }
+    compiler.arg("--target").arg(match &options.target {
+        TargetTriple::TargetTriple(s) => s.clone(),
+        TargetTriple::TargetPath(path) => {
+            path.to_str().expect("target path must be valid unicode").to_string()
+        }
+    });
+    compiler
+}
+
+/// Runs a compiled doctest binary with the given arguments, checking its exit status against
+/// `should_panic`.
+fn run_binary(
+    output_file: &Path,
+    args: &[&str],
+    options: &Options,
+    test_id: &str,
+    should_panic: bool,
+) -> Result<(), TestFailure> {
     let mut cmd;
 
-    if let Some(tool) = runtool {
+    if let Some(tool) = &options.runtool {
         cmd = Command::new(tool);
-        cmd.args(runtool_args);
+        cmd.args(&options.runtool_args);
         cmd.arg(output_file);
     } else {
         cmd = Command::new(output_file);
     }
-    if let Some(run_directory) = options.test_run_directory {
+    cmd.args(args);
+    if let Some(run_directory) = &options.test_run_directory {
         cmd.current_dir(run_directory);
     }
+    if let Some(profile_dir) = &options.doctest_profile_dir {
+        // The executable may run in another directory, so the path has to be absolute. `%p` and
+        // `%m` keep the profiles of different processes and executables apart.
+        let profile_file = env::current_dir()
+            .expect("failed to get the current directory")
+            .join(profile_dir)
+            .join(format!("{}-%p-%m.profraw", test_id));
+        cmd.env("LLVM_PROFILE_FILE", profile_file);
+    }
 
     match cmd.output() {
         Err(e) => return Err(TestFailure::ExecutionError(e)),
@@ -805,6 +855,7 @@ fn register_header(&mut self, _name: &str, _level: u32) {}
     visited_tests: FxHashMap<(String, usize), usize>,
     unused_extern_reports: Arc<Mutex<Vec<UnusedExterns>>>,
     compiling_test_count: AtomicUsize,
+    merged_doctests: Option<Arc<MergedDoctests>>,
 }
 
 impl Collector {
@@ -817,6 +868,21 @@ impl Collector {
         filename: Option<PathBuf>,
         enable_per_target_ignores: bool,
     ) -> Collector {
+        // Merged doctests are never built with `--no-run`, and they can't report the unused
+        // externs of each doctest. Their coverage can't be mapped back to the doctests either.
+        let instrument_coverage = !matches!(
+            options.debugging_opts.instrument_coverage,
+            None | Some(InstrumentCoverage::Off)
+        );
+        let merged_doctests = if options.merge_doctests
+            && !options.no_run
+            && !options.json_unused_externs
+            && !instrument_coverage
+        {
+            Some(Arc::new(MergedDoctests::new(cratename.clone(), options.clone(), opts.clone())))
+        } else {
+            None
+        };
         Collector {
             tests: Vec::new(),
             names: Vec::new(),
@@ -831,6 +897,7 @@ impl Collector {
             visited_tests: FxHashMap::default(),
             unused_extern_reports: Default::default(),
             compiling_test_count: AtomicUsize::new(0),
+            merged_doctests,
         }
     }
 
@@ -875,10 +942,7 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
         let opts = self.opts.clone();
         let edition = config.edition.unwrap_or(self.options.edition);
         let options = self.options.clone();
-        let runtool = self.options.runtool.clone();
-        let runtool_args = self.options.runtool_args.clone();
-        let target = self.options.target.clone();
-        let target_str = target.to_string();
+        let target_str = self.options.target.to_string();
         let unused_externs = self.unused_extern_reports.clone();
         let no_run = config.no_run || options.no_run;
         if !config.compile_fail {
@@ -914,22 +978,12 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
                 self.visited_tests.entry((file.clone(), line)).and_modify(|v| *v += 1).or_insert(0)
             },
         );
-        let outdir = if let Some(mut path) = options.persist_doctests.clone() {
-            path.push(&test_id);
-
-            std::fs::create_dir_all(&path)
-                .expect("Couldn't create directory for doctest executables");
-
-            DirState::Perm(path)
-        } else {
-            DirState::Temp(
-                TempFileBuilder::new()
-                    .prefix("rustdoctest")
-                    .tempdir()
-                    .expect("rustdoc needs a tempdir"),
-            )
+        let merged_doctests = match self.merged_doctests {
+            Some(ref merged) if merged.try_add(&test, &config, &test_id, &path, line) => {
+                Some(merged.clone())
+            }
+            _ => None,
         };
-
         debug!("creating test {}: {}", name, test);
         self.tests.push(testing::TestDescAndFn {
             desc: testing::TestDesc {
@@ -946,32 +1000,41 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
                 compile_fail: config.compile_fail,
                 #[cfg(not(bootstrap))]
                 no_run,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: testing::TestType::DocTest,
             },
             testfn: testing::DynTestFn(box move || {
                 let report_unused_externs = |uext| {
                     unused_externs.lock().unwrap().push(uext);
                 };
-                let res = run_test(
-                    &test,
-                    &cratename,
-                    line,
-                    options,
-                    config.should_panic,
-                    no_run,
-                    config.test_harness,
-                    runtool,
-                    runtool_args,
-                    target,
-                    config.compile_fail,
-                    config.error_codes,
-                    &opts,
-                    edition,
-                    outdir,
-                    path,
-                    &test_id,
-                    report_unused_externs,
-                );
+                let merged_res = merged_doctests
+                    .as_ref()
+                    .and_then(|merged| merged.run_test(&test_id, config.should_panic));
+                let res = match merged_res {
+                    Some(res) => res,
+                    None => {
+                        // Merged doctests don't get a directory of their own.
+                        let outdir = DirState::new(&options, &test_id);
+                        run_test(
+                            &test,
+                            &cratename,
+                            line,
+                            options,
+                            config.should_panic,
+                            no_run,
+                            config.test_harness,
+                            config.compile_fail,
+                            config.error_codes,
+                            &opts,
+                            edition,
+                            outdir,
+                            path,
+                            &test_id,
+                            report_unused_externs,
+                        )
+                    }
+                };
 
                 if let Err(err) = res {
                     match err {
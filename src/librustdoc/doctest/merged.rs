@@ -0,0 +1,284 @@
+//! Support for `--merge-doctests`, which compiles all compatible doctests of a crate into a single
+//! binary instead of building one binary per doctest.
+//!
+//! Every merged doctest becomes a function of the generated crate and the binary's `main` calls
+//! the one whose name was given as the first argument. Each doctest still runs in its own process,
+//! so only the compilation is shared.
+//!
+//! The merged crate is compiled from stdin, so the locations it reports, like those of panic
+//! messages, are in `<anon>`. When a doctest fails, the locations within its code are mapped back
+//! to the file the doctest comes from.
+
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::str;
+use std::sync::Mutex;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_span::edition::Edition;
+
+use super::{partition_source, run_binary, rustc_command, DirState, TestFailure, TestOptions};
+use crate::config::Options;
+use crate::html::markdown::{Ignore, LangString};
+
+/// A doctest compiled as part of the merged binary.
+crate struct MergedTest {
+    /// The unique identifier of the doctest, which is also used to select it at runtime.
+    crate test_id: String,
+    /// The code of the doctest.
+    crate code: String,
+    /// The file the doctest comes from.
+    crate path: PathBuf,
+    /// The line of `path` right before the code of the doctest.
+    crate line: usize,
+}
+
+enum BuildState {
+    NotBuilt,
+    /// The merged binary, and the location of the code of each doctest by its identifier.
+    Built(DirState, FxHashMap<String, CodeLocation>),
+    Failed,
+}
+
+/// Where the code of a merged doctest is, in the merged source and in the file it comes from.
+#[derive(Clone)]
+struct CodeLocation {
+    path: PathBuf,
+    line: usize,
+    /// The lines of the merged source with the code of the doctest.
+    merged_lines: Range<usize>,
+}
+
+/// How the locations in the merged source start, as it is read from stdin.
+const MERGED_SOURCE_PREFIX: &str = "<anon>:";
+
+impl CodeLocation {
+    /// Rewrites the locations within the code of the doctest in `output` to locations in the file
+    /// the doctest comes from. Other locations, and output that isn't UTF-8, are left alone.
+    fn map_locations(&self, output: Vec<u8>) -> Vec<u8> {
+        let output = match str::from_utf8(&output) {
+            Ok(output) => output,
+            Err(_) => return output,
+        };
+        let mut mapped = String::with_capacity(output.len());
+        let mut rest = output;
+        while let Some(start) = rest.find(MERGED_SOURCE_PREFIX) {
+            mapped.push_str(&rest[..start]);
+            rest = &rest[start + MERGED_SOURCE_PREFIX.len()..];
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            match rest[..digits].parse::<usize>() {
+                Ok(merged_line) if self.merged_lines.contains(&merged_line) => {
+                    let line = self.line + 1 + merged_line - self.merged_lines.start;
+                    mapped.push_str(&format!("{}:{}", self.path.display(), line));
+                }
+                _ => {
+                    mapped.push_str(MERGED_SOURCE_PREFIX);
+                    mapped.push_str(&rest[..digits]);
+                }
+            }
+            rest = &rest[digits..];
+        }
+        mapped.push_str(rest);
+        mapped.into_bytes()
+    }
+}
+
+crate struct MergedDoctests {
+    cratename: String,
+    options: Options,
+    opts: TestOptions,
+    tests: Mutex<Vec<MergedTest>>,
+    state: Mutex<BuildState>,
+}
+
+impl MergedDoctests {
+    crate fn new(cratename: String, options: Options, opts: TestOptions) -> MergedDoctests {
+        MergedDoctests {
+            cratename,
+            options,
+            opts,
+            tests: Mutex::new(Vec::new()),
+            state: Mutex::new(BuildState::NotBuilt),
+        }
+    }
+
+    /// Adds the doctest to the merged binary if it can be compiled together with other doctests,
+    /// and returns whether it was added. `path` and `line` are where the doctest comes from.
+    crate fn try_add(
+        &self,
+        test: &str,
+        config: &LangString,
+        test_id: &str,
+        path: &Path,
+        line: usize,
+    ) -> bool {
+        if !is_mergeable(test, config, self.options.edition) {
+            return false;
+        }
+        let (_, everything_else, _) = partition_source(test);
+        self.tests.lock().unwrap().push(MergedTest {
+            test_id: test_id.to_owned(),
+            code: everything_else.trim().to_owned(),
+            path: path.to_owned(),
+            line,
+        });
+        true
+    }
+
+    /// Runs the doctest `test_id`, building the merged binary first if no other doctest did it
+    /// yet. Returns `None` if the merged binary failed to build, in which case the doctest has to
+    /// be compiled on its own, which also reports the compilation errors properly.
+    pub(super) fn run_test(
+        &self,
+        test_id: &str,
+        should_panic: bool,
+    ) -> Option<Result<(), TestFailure>> {
+        let (output_file, location) = {
+            let mut state = self.state.lock().unwrap();
+            if let BuildState::NotBuilt = *state {
+                *state = self.build();
+            }
+            match *state {
+                BuildState::Built(ref outdir, ref locations) => {
+                    (outdir.path().join("rust_out"), locations[test_id].clone())
+                }
+                BuildState::NotBuilt | BuildState::Failed => return None,
+            }
+        };
+
+        let res = run_binary(&output_file, &[test_id], &self.options, test_id, should_panic);
+        Some(res.map_err(|failure| match failure {
+            TestFailure::ExecutionFailure(out) => TestFailure::ExecutionFailure(process::Output {
+                status: out.status,
+                stdout: location.map_locations(out.stdout),
+                stderr: location.map_locations(out.stderr),
+            }),
+            failure => failure,
+        }))
+    }
+
+    fn build(&self) -> BuildState {
+        let tests = self.tests.lock().unwrap();
+        let (source, merged_lines) = make_merged_source(&tests, &self.cratename, &self.opts);
+        let locations = tests
+            .iter()
+            .zip(merged_lines)
+            .map(|(test, merged_lines)| {
+                let location =
+                    CodeLocation { path: test.path.clone(), line: test.line, merged_lines };
+                (test.test_id.clone(), location)
+            })
+            .collect();
+
+        let outdir = DirState::new(&self.options, "merged_doctests");
+
+        let output_file = outdir.path().join("rust_out");
+        let mut compiler = rustc_command(&self.options, self.options.edition, &output_file);
+        // Errors are reported when the doctests fall back to being compiled one by one.
+        compiler.arg("--color").arg("never");
+        compiler.arg("-");
+        compiler.stdin(Stdio::piped());
+        compiler.stdout(Stdio::null());
+        compiler.stderr(Stdio::null());
+
+        let mut child = compiler.spawn().expect("Failed to spawn rustc process");
+        {
+            let stdin = child.stdin.as_mut().expect("Failed to open stdin");
+            stdin.write_all(source.as_bytes()).expect("could write out test sources");
+        }
+        let status = child.wait().expect("Failed to wait for rustc");
+
+        if status.success() {
+            BuildState::Built(outdir, locations)
+        } else {
+            debug!("failed to build merged doctests:\n{}", source);
+            BuildState::Failed
+        }
+    }
+}
+
+/// Returns whether the doctest can be compiled together with other doctests, meaning that it is
+/// a regular runnable test and doesn't rely on anything at the crate level: no crate attributes,
+/// no `extern crate` and no `fn main` of its own.
+crate fn is_mergeable(test: &str, config: &LangString, edition: Edition) -> bool {
+    if config.compile_fail
+        || config.no_run
+        || config.test_harness
+        || !matches!(config.ignore, Ignore::None)
+        || config.edition.map_or(false, |e| e != edition)
+    {
+        return false;
+    }
+
+    let (crate_attrs, _, crates) = partition_source(test);
+    if crate_attrs.contains("#![") || !crates.is_empty() {
+        return false;
+    }
+
+    !test
+        .lines()
+        .map(|line| {
+            let comment = line.find("//");
+            if let Some(comment_begins) = comment { &line[0..comment_begins] } else { line }
+        })
+        .any(|code| code.contains("fn main"))
+}
+
+/// Generates the crate containing all the merged doctests. Also returns the lines (starting at 1)
+/// with the code of each doctest.
+crate fn make_merged_source(
+    tests: &[MergedTest],
+    cratename: &str,
+    opts: &TestOptions,
+) -> (String, Vec<Range<usize>>) {
+    let mut prog = String::new();
+
+    // See `make_test` for why this is only added when there are no test attributes.
+    if opts.attrs.is_empty() && !opts.display_warnings {
+        prog.push_str("#![allow(unused)]\n");
+    }
+    for attr in &opts.attrs {
+        prog.push_str(&format!("#![{}]\n", attr));
+    }
+
+    if !opts.no_crate_inject
+        && cratename != "std"
+        && tests.iter().any(|test| test.code.contains(cratename))
+    {
+        prog.push_str(&format!("extern crate r#{};\n", cratename));
+    }
+
+    let mut dispatch = String::new();
+    let mut merged_lines = Vec::with_capacity(tests.len());
+    let mut line_count = prog.matches('\n').count();
+    for test in tests {
+        let returns_result = test.code.trim_end().ends_with("(())");
+        let (ret, unwrap) = if returns_result {
+            (" -> Result<(), impl core::fmt::Debug>", ".unwrap()")
+        } else {
+            ("", "")
+        };
+        let function = format!(
+            "#[allow(non_snake_case)]\nfn _doctest_main_{}(){} {{\n{}\n}}\n",
+            test.test_id, ret, test.code
+        );
+        // The code starts after the attribute and the signature.
+        let code_start = line_count + 3;
+        merged_lines.push(code_start..code_start + test.code.lines().count());
+        line_count += function.matches('\n').count();
+        prog.push_str(&function);
+        dispatch.push_str(&format!(
+            "        Some(\"{id}\") => _doctest_main_{id}(){unwrap},\n",
+            id = test.test_id,
+            unwrap = unwrap
+        ));
+    }
+
+    prog.push_str("fn main() {\n    match std::env::args().nth(1).as_deref() {\n");
+    prog.push_str(&dispatch);
+    prog.push_str("        name => panic!(\"unknown doctest {:?}\", name),\n    }\n}\n");
+
+    (prog, merged_lines)
+}
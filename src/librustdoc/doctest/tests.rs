@@ -1,5 +1,8 @@
+use super::merged::{is_mergeable, make_merged_source, MergedTest};
 use super::{make_test, TestOptions};
-use rustc_span::edition::DEFAULT_EDITION;
+use crate::html::markdown::LangString;
+use rustc_span::edition::{Edition, DEFAULT_EDITION};
+use std::path::PathBuf;
 
 #[test]
 fn make_test_basic() {
@@ -312,3 +315,66 @@ fn main() { #[allow(non_snake_case)] fn _doctest_main__some_unique_name() {
         make_test(input, None, false, &opts, DEFAULT_EDITION, Some("_some_unique_name"));
     assert_eq!((output, len), (expected, 2));
 }
+
+#[test]
+fn merged_test_is_mergeable() {
+    let config = LangString::default();
+    assert!(is_mergeable("let x = 5;\nassert_eq!(x, 5);", &config, DEFAULT_EDITION));
+    // `fn main` in a comment doesn't count.
+    assert!(is_mergeable("// no fn main here\nassert!(true);", &config, DEFAULT_EDITION));
+
+    assert!(!is_mergeable("#![feature(never_type)]\nlet x = 5;", &config, DEFAULT_EDITION));
+    assert!(!is_mergeable("extern crate foo;\nfoo::bar();", &config, DEFAULT_EDITION));
+    assert!(!is_mergeable("fn main() {\n    assert!(true);\n}", &config, DEFAULT_EDITION));
+
+    let compile_fail = LangString { compile_fail: true, ..LangString::default() };
+    assert!(!is_mergeable("let x: u8 = \"\";", &compile_fail, DEFAULT_EDITION));
+    let test_harness = LangString { test_harness: true, ..LangString::default() };
+    assert!(!is_mergeable("#[test]\nfn it_works() {}", &test_harness, DEFAULT_EDITION));
+    let edition = LangString { edition: Some(Edition::Edition2015), ..LangString::default() };
+    assert!(!is_mergeable("assert!(true);", &edition, Edition::Edition2018));
+}
+
+#[test]
+fn merged_test_source() {
+    // each doctest gets its own function, selected by the first argument of the binary
+    let opts = TestOptions::default();
+    let tests = vec![
+        MergedTest {
+            test_id: "_a".to_string(),
+            code: "use asdf::qwop;\nqwop();".to_string(),
+            path: PathBuf::from("a.rs"),
+            line: 1,
+        },
+        MergedTest {
+            test_id: "_b".to_string(),
+            code: "let x = \"1\".parse::<u8>()?;\nOk::<(), std::num::ParseIntError>(())"
+                .to_string(),
+            path: PathBuf::from("b.rs"),
+            line: 7,
+        },
+    ];
+    let expected = "#![allow(unused)]
+extern crate r#asdf;
+#[allow(non_snake_case)]
+fn _doctest_main__a() {
+use asdf::qwop;
+qwop();
+}
+#[allow(non_snake_case)]
+fn _doctest_main__b() -> Result<(), impl core::fmt::Debug> {
+let x = \"1\".parse::<u8>()?;
+Ok::<(), std::num::ParseIntError>(())
+}
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some(\"_a\") => _doctest_main__a(),
+        Some(\"_b\") => _doctest_main__b().unwrap(),
+        name => panic!(\"unknown doctest {:?}\", name),
+    }
+}
+";
+    // the code of `_a` is on lines 5 and 6, and the code of `_b` on lines 10 and 11
+    let expected_lines = vec![5..7, 10..12];
+    assert_eq!(make_merged_source(&tests, "asdf", &opts), (expected.to_string(), expected_lines));
+}
@@ -0,0 +1,786 @@
+//! Comparison of the public API of two versions of a crate, used by `--api-diff`.
+//!
+//! Both versions are read from the output of `--output-format json`. The ids of items are not
+//! stable between two runs of rustdoc, so items are matched by the path they are reachable at
+//! from the crate root instead, and ids in signatures are replaced by the paths of the items they
+//! refer to before comparing them. Renaming arguments or generic parameters doesn't affect other
+//! crates, so signatures leave out the former and number the latter.
+//!
+//! Every difference is classified as breaking or not, following the usual rules of semantic
+//! versioning: removing items, changing their signature, making them private, adding required
+//! items to traits, adding variants or fields to exhaustive enums and structs, and no longer
+//! implementing auto traits break users of the crate, while additions usually don't.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use rustdoc_json_types as types;
+
+use crate::config::OutputFormat;
+
+/// Compares the JSON output of rustdoc for an old and a new version of a crate, and prints the
+/// differences between them.
+crate fn run(old: &Path, new: &Path, output_format: OutputFormat) -> Result<(), String> {
+    let old = read_crate(old)?;
+    let new = read_crate(new)?;
+    if old.format_version != new.format_version {
+        return Err(format!(
+            "cannot compare JSON output of format version {} with format version {}",
+            old.format_version, new.format_version
+        ));
+    }
+
+    let changes = diff(&ApiCollector::collect(&old), &ApiCollector::collect(&new));
+    if output_format.is_json() {
+        let breaking = changes.iter().filter(|change| change.breaking).count();
+        let report = json!({
+            "breaking_changes": breaking,
+            "non_breaking_changes": changes.len() - breaking,
+            "changes": changes,
+        });
+        println!("{}", report);
+    } else {
+        print_changes(&changes);
+    }
+    Ok(())
+}
+
+fn read_crate(path: &Path) -> Result<types::Crate, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("failed to read `{}`: {}", path.display(), error))?;
+    serde_json::from_str(&contents).map_err(|error| {
+        format!("`{}` is not a valid rustdoc JSON output: {}", path.display(), error)
+    })
+}
+
+/// An item of the API of one version of the crate.
+struct ApiItem {
+    kind: &'static str,
+    /// Whether other crates can use the item. Items that are not exported are only present in
+    /// the output of `--document-private-items`.
+    exported: bool,
+    visibility: String,
+    /// Everything other crates can rely on about the item, except for its children, which are
+    /// items of their own.
+    signature: Value,
+    /// Whether this is a trait item that implementations have to provide.
+    required: bool,
+    /// Whether this is an enum or struct that other crates can match exhaustively, or a struct
+    /// they can construct.
+    exhaustive: bool,
+    /// The path of the item this one is a child of, if adding this item to it breaks other
+    /// crates, like for required trait items or variants of exhaustive enums.
+    breaks_parent: Option<String>,
+}
+
+/// Collects the API of a crate, keyed by the paths of the items.
+struct ApiCollector<'a> {
+    krate: &'a types::Crate,
+    items: BTreeMap<String, ApiItem>,
+    /// The modules currently being visited, to not follow cyclic glob imports forever.
+    visiting: FxHashSet<&'a types::Id>,
+    /// The generics of the items whose children are being visited, outermost first. The
+    /// signatures of the children can refer to their parameters.
+    generics: Vec<&'a types::Generics>,
+}
+
+/// The canonical names of the generic parameters in scope, by their names in the source.
+type GenericNames = FxHashMap<String, String>;
+
+impl ApiCollector<'a> {
+    fn collect(krate: &'a types::Crate) -> BTreeMap<String, ApiItem> {
+        let mut collector = ApiCollector {
+            krate,
+            items: BTreeMap::new(),
+            visiting: FxHashSet::default(),
+            generics: Vec::new(),
+        };
+        if let Some(root) = krate.index.get(&krate.root) {
+            let name = root.name.clone().unwrap_or_default();
+            collector.visit_item(root, name, true, None);
+        }
+        collector.items
+    }
+
+    fn visit_module(
+        &mut self,
+        id: &'a types::Id,
+        module: &'a types::Module,
+        path: &str,
+        exported: bool,
+    ) {
+        if !self.visiting.insert(id) {
+            return;
+        }
+        for item in module.items.iter().filter_map(|id| self.krate.index.get(id)) {
+            let item_exported = exported && is_exported(&item.visibility, false);
+            match item.inner {
+                types::ItemEnum::Import(ref import) => {
+                    let target = import.id.as_ref().and_then(|id| self.krate.index.get(id));
+                    match target {
+                        Some(types::Item {
+                            id, inner: types::ItemEnum::Module(module), ..
+                        }) if import.glob => self.visit_module(id, module, path, item_exported),
+                        // The variants of enums imported by globs are already reachable through
+                        // the enum.
+                        _ if import.glob => {}
+                        Some(target) => self.visit_item(
+                            target,
+                            format!("{}::{}", path, import.name),
+                            item_exported,
+                            None,
+                        ),
+                        // The item is defined in another crate, so all we know is where it
+                        // comes from.
+                        None => {
+                            let import_path = format!("{}::{}", path, import.name);
+                            self.insert(import_path, item, item_exported, json!(import.source));
+                        }
+                    }
+                }
+                types::ItemEnum::ExternCrate { .. } => {}
+                _ => {
+                    if let Some(ref name) = item.name {
+                        self.visit_item(item, format!("{}::{}", path, name), item_exported, None);
+                    }
+                }
+            }
+        }
+        self.visiting.remove(id);
+    }
+
+    /// Adds `item` and its children to the API.
+    fn visit_item(
+        &mut self,
+        item: &'a types::Item,
+        path: String,
+        exported: bool,
+        breaks_parent: Option<&str>,
+    ) {
+        let signature = self.signature(item);
+        let api_item = self.insert(path.clone(), item, exported, signature);
+        api_item.breaks_parent = breaks_parent.map(|parent| parent.to_string());
+        // Adding variants or public fields to exhaustive types breaks patterns and struct
+        // expressions.
+        let breaks = if is_exhaustive(item) { Some(path.as_str()) } else { None };
+        let generics = generics_of(item);
+        self.generics.extend(generics);
+
+        match item.inner {
+            types::ItemEnum::Module(ref module) => {
+                self.visit_module(&item.id, module, &path, exported);
+            }
+            types::ItemEnum::Struct(ref struct_) => {
+                self.visit_fields(&struct_.fields, &path, exported, false, breaks);
+                self.visit_impls(&struct_.impls, &path, exported);
+            }
+            types::ItemEnum::Union(ref union_) => {
+                self.visit_fields(&union_.fields, &path, exported, false, breaks);
+                self.visit_impls(&union_.impls, &path, exported);
+            }
+            types::ItemEnum::Enum(ref enum_) => {
+                self.visit_fields(&enum_.variants, &path, exported, true, breaks);
+                self.visit_impls(&enum_.impls, &path, exported);
+            }
+            types::ItemEnum::Variant(types::Variant::Struct(ref fields)) => {
+                self.visit_fields(fields, &path, exported, true, breaks);
+            }
+            types::ItemEnum::Trait(ref trait_) => {
+                for trait_item in trait_.items.iter().filter_map(|id| self.krate.index.get(id)) {
+                    let name = trait_item.name.as_deref().unwrap_or("_");
+                    let breaks = if is_required(trait_item) { Some(path.as_str()) } else { None };
+                    self.visit_item(trait_item, format!("{}::{}", path, name), exported, breaks);
+                }
+            }
+            _ => {}
+        }
+
+        if generics.is_some() {
+            self.generics.pop();
+        }
+    }
+
+    /// Adds the fields of a struct or union, or the variants of an enum. Variants and the fields
+    /// of variants have the visibility of their parent.
+    fn visit_fields(
+        &mut self,
+        fields: &'a [types::Id],
+        path: &str,
+        exported: bool,
+        inherits_visibility: bool,
+        breaks_parent: Option<&str>,
+    ) {
+        for field in fields.iter().filter_map(|id| self.krate.index.get(id)) {
+            let field_exported = exported && is_exported(&field.visibility, inherits_visibility);
+            // Adding private fields does not break anything.
+            let breaks = breaks_parent.filter(|_| field_exported);
+            let name = field.name.as_deref().unwrap_or("_");
+            self.visit_item(field, format!("{}::{}", path, name), field_exported, breaks);
+        }
+    }
+
+    /// Adds the items of the inherent impls and the implemented traits of a type.
+    fn visit_impls(&mut self, impls: &'a [types::Id], path: &str, exported: bool) {
+        // Impls don't see the generic parameters of the type.
+        let outer_generics = mem::take(&mut self.generics);
+        for item in impls.iter().filter_map(|id| self.krate.index.get(id)) {
+            let impl_ = match item.inner {
+                types::ItemEnum::Impl(ref impl_) => impl_,
+                _ => continue,
+            };
+            match impl_.trait_ {
+                None => {
+                    self.generics.push(&impl_.generics);
+                    for impl_item in impl_.items.iter().filter_map(|id| self.krate.index.get(id)) {
+                        let name = impl_item.name.as_deref().unwrap_or("_");
+                        let impl_item_exported =
+                            exported && is_exported(&impl_item.visibility, false);
+                        self.visit_item(
+                            impl_item,
+                            format!("{}::{}", path, name),
+                            impl_item_exported,
+                            None,
+                        );
+                    }
+                    self.generics.pop();
+                }
+                // Blanket impls come from the crate defining the trait, and negative impls are
+                // only there to show that a type does *not* implement an auto trait.
+                Some(_) if impl_.negative || impl_.blanket_impl.is_some() => {}
+                Some(ref trait_) => {
+                    // A type can implement a trait several times, for different generic
+                    // arguments of either.
+                    let names = canonical_names(&[&impl_.generics]);
+                    let self_ty = match impl_.for_ {
+                        types::Type::ResolvedPath { ref args, .. } => {
+                            format!("{}{}", path, ArgsDisplay(args.as_deref(), &names))
+                        }
+                        ref for_ => TypeDisplay(for_, &names).to_string(),
+                    };
+                    let impl_path = format!("<{} as {}>", self_ty, TypeDisplay(trait_, &names));
+                    let signature = self.signature(item);
+                    self.insert(impl_path, item, exported, signature);
+                }
+            }
+        }
+        self.generics = outer_generics;
+    }
+
+    fn insert(
+        &mut self,
+        path: String,
+        item: &types::Item,
+        exported: bool,
+        signature: Value,
+    ) -> &mut ApiItem {
+        let api_item = ApiItem {
+            kind: kind_name(item),
+            exported,
+            visibility: visibility_name(&item.visibility),
+            signature,
+            required: is_required(item),
+            exhaustive: is_exhaustive(item),
+            breaks_parent: None,
+        };
+        // An item reachable through several paths is part of the API at each of them.
+        self.items.entry(path).or_insert(api_item)
+    }
+
+    /// Returns what other crates can rely on about `item`, with ids replaced by paths.
+    fn signature(&self, item: &types::Item) -> Value {
+        use types::ItemEnum::*;
+
+        let mut signature = match item.inner {
+            Function(ref f) => {
+                json!({ "decl": f.decl, "generics": f.generics, "header": f.header, "abi": f.abi })
+            }
+            // Whether a method has a default body is checked separately, as providing one
+            // doesn't break anything.
+            Method(ref m) => {
+                json!({ "decl": m.decl, "generics": m.generics, "header": m.header, "abi": m.abi })
+            }
+            Struct(ref s) => json!({ "struct_type": s.struct_type, "generics": s.generics }),
+            Union(ref u) => json!({ "generics": u.generics }),
+            Enum(ref e) => json!({ "generics": e.generics }),
+            Variant(types::Variant::Tuple(ref fields)) => json!({ "tuple": fields }),
+            Variant(types::Variant::Plain) => json!("plain"),
+            Variant(types::Variant::Struct(_)) => json!("struct"),
+            StructField(ref ty) => json!(ty),
+            Trait(ref t) => json!({
+                "is_auto": t.is_auto,
+                "is_unsafe": t.is_unsafe,
+                "generics": t.generics,
+                "bounds": t.bounds,
+            }),
+            TraitAlias(ref t) => json!(t),
+            Impl(ref i) => json!({ "is_unsafe": i.is_unsafe, "generics": i.generics }),
+            Typedef(ref t) => json!(t),
+            OpaqueTy(ref t) => json!(t),
+            Constant(ref c) => json!({ "type": c.type_ }),
+            Static(ref s) => json!({ "type": s.type_, "mutable": s.mutable }),
+            ProcMacro(ref p) => json!(p),
+            AssocConst { ref type_, .. } => json!({ "type": type_ }),
+            AssocType { ref bounds, .. } => json!({ "bounds": bounds }),
+            // Whether the definition of a macro changed in a compatible way can't be told from
+            // its source alone.
+            Module(_) | ExternCrate { .. } | Import(_) | ForeignType | Macro(_) => Value::Null,
+        };
+        let mut scopes: Vec<&types::Generics> = self.generics.clone();
+        scopes.extend(generics_of(item));
+        self.normalize(&mut signature, &canonical_names(&scopes));
+        signature
+    }
+
+    /// Replaces ids by the paths of the items they refer to and generic parameters by their
+    /// canonical names, leaves out the names of arguments, and sorts the sets of function
+    /// qualifiers, which are serialized in an arbitrary order.
+    fn normalize(&self, value: &mut Value, names: &GenericNames) {
+        match value {
+            Value::Object(object) => {
+                // Type parameters are serialized as `{"kind": "generic", "inner": name}`, and the
+                // definitions of generic parameters as `{"name": name, "kind": kind}`.
+                let is_type_param = object.get("kind").map_or(false, |kind| kind == "generic");
+                let is_param_def = object.contains_key("name") && object.contains_key("kind");
+                for (key, value) in object.iter_mut() {
+                    match (key.as_str(), &mut *value) {
+                        ("id", Value::String(id)) => {
+                            let id = types::Id(id.clone());
+                            *value = match self.krate.paths.get(&id) {
+                                Some(summary) => json!(summary.path.join("::")),
+                                // The name next to the id is all we have to go on.
+                                None => Value::Null,
+                            };
+                        }
+                        ("header", Value::Array(qualifiers)) => {
+                            qualifiers.sort_by_key(|qualifier| qualifier.to_string());
+                        }
+                        // Arguments are serialized as `[name, type]`.
+                        ("inputs", Value::Array(inputs)) => {
+                            for input in inputs.iter_mut() {
+                                if let Value::Array(argument) = input {
+                                    if argument.len() == 2 && argument[0].is_string() {
+                                        *input = argument.pop().unwrap();
+                                    }
+                                }
+                                self.normalize(input, names);
+                            }
+                        }
+                        ("inner", Value::String(name)) if is_type_param => rename(name, names),
+                        ("name", Value::String(name)) if is_param_def => rename(name, names),
+                        // The lengths of arrays and const arguments can be const parameters.
+                        ("len" | "expr", Value::String(name)) => rename(name, names),
+                        _ => self.normalize(value, names),
+                    }
+                }
+            }
+            Value::Array(values) => {
+                values.iter_mut().for_each(|value| self.normalize(value, names))
+            }
+            // Lifetimes are serialized as strings including the leading `'`.
+            Value::String(lifetime) if lifetime.starts_with('\'') => rename(lifetime, names),
+            _ => {}
+        }
+    }
+}
+
+fn generics_of(item: &types::Item) -> Option<&types::Generics> {
+    use types::ItemEnum::*;
+
+    match item.inner {
+        Function(ref f) => Some(&f.generics),
+        Method(ref m) => Some(&m.generics),
+        Struct(ref s) => Some(&s.generics),
+        Union(ref u) => Some(&u.generics),
+        Enum(ref e) => Some(&e.generics),
+        Trait(ref t) => Some(&t.generics),
+        TraitAlias(ref t) => Some(&t.generics),
+        Impl(ref i) => Some(&i.generics),
+        Typedef(ref t) => Some(&t.generics),
+        OpaqueTy(ref t) => Some(&t.generics),
+        _ => None,
+    }
+}
+
+/// Numbers the generic parameters of `scopes`, outermost first, so that the signatures referring
+/// to them stay the same when they are renamed. Parameters of inner scopes shadow those of outer
+/// ones.
+fn canonical_names(scopes: &[&types::Generics]) -> GenericNames {
+    let mut names = GenericNames::default();
+    let params = scopes.iter().flat_map(|generics| &generics.params);
+    for (index, param) in params.enumerate() {
+        let canonical = if param.name.starts_with('\'') {
+            format!("'{}", index)
+        } else {
+            format!("T{}", index)
+        };
+        names.insert(param.name.clone(), canonical);
+    }
+    names
+}
+
+fn canonical_name<'a>(name: &'a str, names: &'a GenericNames) -> &'a str {
+    names.get(name).map_or(name, |canonical| canonical.as_str())
+}
+
+fn rename(name: &mut String, names: &GenericNames) {
+    if let Some(canonical) = names.get(name) {
+        *name = canonical.clone();
+    }
+}
+
+fn is_exported(visibility: &types::Visibility, inherits_visibility: bool) -> bool {
+    match visibility {
+        types::Visibility::Public => true,
+        types::Visibility::Default => inherits_visibility,
+        types::Visibility::Crate | types::Visibility::Restricted { .. } => false,
+    }
+}
+
+fn is_non_exhaustive(item: &types::Item) -> bool {
+    item.attrs.iter().any(|attr| attr == "#[non_exhaustive]")
+}
+
+/// Returns whether other crates can match `item` exhaustively, or construct it if it's a struct.
+/// Adding fields to a union doesn't break anything, as union expressions and patterns only ever
+/// mention one field.
+fn is_exhaustive(item: &types::Item) -> bool {
+    match item.inner {
+        types::ItemEnum::Struct(ref struct_) => {
+            !struct_.fields_stripped && !is_non_exhaustive(item)
+        }
+        types::ItemEnum::Enum(ref enum_) => !enum_.variants_stripped && !is_non_exhaustive(item),
+        types::ItemEnum::Variant(types::Variant::Struct(_)) => !is_non_exhaustive(item),
+        _ => false,
+    }
+}
+
+/// Returns whether `item` is a trait item without a default.
+fn is_required(item: &types::Item) -> bool {
+    match item.inner {
+        types::ItemEnum::Method(ref method) => !method.has_body,
+        types::ItemEnum::AssocConst { ref default, .. } => default.is_none(),
+        types::ItemEnum::AssocType { ref default, .. } => default.is_none(),
+        _ => false,
+    }
+}
+
+fn kind_name(item: &types::Item) -> &'static str {
+    use types::ItemEnum::*;
+
+    match item.inner {
+        Module(_) => "module",
+        ExternCrate { .. } => "extern crate",
+        Import(_) => "re-export",
+        Union(_) => "union",
+        Struct(_) => "struct",
+        StructField(_) => "field",
+        Enum(_) => "enum",
+        Variant(_) => "variant",
+        Function(_) => "function",
+        Trait(_) => "trait",
+        TraitAlias(_) => "trait alias",
+        Method(_) => "method",
+        Impl(ref impl_) if impl_.synthetic => "auto trait impl",
+        Impl(_) => "trait impl",
+        Typedef(_) => "type alias",
+        OpaqueTy(_) => "opaque type",
+        Constant(_) => "constant",
+        Static(_) => "static",
+        ForeignType => "foreign type",
+        Macro(_) => "macro",
+        ProcMacro(_) => "proc macro",
+        AssocConst { .. } => "associated constant",
+        AssocType { .. } => "associated type",
+    }
+}
+
+fn visibility_name(visibility: &types::Visibility) -> String {
+    match visibility {
+        types::Visibility::Public => "pub".to_string(),
+        types::Visibility::Default => "private".to_string(),
+        types::Visibility::Crate => "pub(crate)".to_string(),
+        types::Visibility::Restricted { path, .. } => format!("pub(in {})", path),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Serialize)]
+struct Change {
+    path: String,
+    kind: &'static str,
+    change: ChangeKind,
+    breaking: bool,
+    reason: String,
+}
+
+fn diff(old: &BTreeMap<String, ApiItem>, new: &BTreeMap<String, ApiItem>) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut push = |path: &str, kind, change, breaking, reason: String| {
+        changes.push(Change { path: path.to_string(), kind, change, breaking, reason })
+    };
+
+    for (path, old_item) in old {
+        let new_item = match new.get(path) {
+            Some(new_item) => new_item,
+            None => {
+                if old_item.exported {
+                    push(path, old_item.kind, ChangeKind::Removed, true, "removed".to_string());
+                }
+                continue;
+            }
+        };
+
+        match (old_item.exported, new_item.exported) {
+            (false, false) => continue,
+            (true, false) => {
+                let reason = format!(
+                    "visibility changed from `{}` to `{}`",
+                    old_item.visibility, new_item.visibility
+                );
+                push(path, new_item.kind, ChangeKind::Removed, true, reason);
+                continue;
+            }
+            (false, true) => {
+                let reason = format!(
+                    "visibility changed from `{}` to `{}`",
+                    old_item.visibility, new_item.visibility
+                );
+                push(path, new_item.kind, ChangeKind::Added, false, reason);
+                continue;
+            }
+            (true, true) => {}
+        }
+
+        let kind = new_item.kind;
+        if old_item.kind != new_item.kind {
+            let reason = format!("changed from {} to {}", old_item.kind, new_item.kind);
+            push(path, kind, ChangeKind::Changed, true, reason);
+        } else if old_item.signature != new_item.signature {
+            push(path, kind, ChangeKind::Changed, true, "the signature changed".to_string());
+        }
+        match (old_item.required, new_item.required) {
+            (false, true) => {
+                let reason = "the trait no longer provides a default".to_string();
+                push(path, kind, ChangeKind::Changed, true, reason);
+            }
+            (true, false) => {
+                let reason = "the trait now provides a default".to_string();
+                push(path, kind, ChangeKind::Changed, false, reason);
+            }
+            _ => {}
+        }
+        match (old_item.exhaustive, new_item.exhaustive) {
+            (true, false) => {
+                let reason = "is no longer exhaustive".to_string();
+                push(path, kind, ChangeKind::Changed, true, reason);
+            }
+            (false, true) => {
+                let reason = "is now exhaustive".to_string();
+                push(path, kind, ChangeKind::Changed, false, reason);
+            }
+            _ => {}
+        }
+    }
+
+    for (path, new_item) in new {
+        if old.contains_key(path) || !new_item.exported {
+            continue;
+        }
+        // Adding a required item to a trait or a variant to an enum only breaks other crates if
+        // the trait or enum already existed.
+        let breaks_parent = new_item
+            .breaks_parent
+            .as_ref()
+            .and_then(|parent| old.get(parent))
+            .map_or(false, |parent| parent.exported);
+        let reason = match (breaks_parent, new_item.kind) {
+            (true, "field") | (true, "variant") => "added to an exhaustive type",
+            (true, _) => "added without a default to an existing trait",
+            (false, _) => "added",
+        };
+        push(path, new_item.kind, ChangeKind::Added, breaks_parent, reason.to_string());
+    }
+
+    changes.sort_by(|a, b| (!a.breaking, &a.path).cmp(&(!b.breaking, &b.path)));
+    changes
+}
+
+fn print_changes(changes: &[Change]) {
+    if changes.is_empty() {
+        println!("No changes to the public API");
+        return;
+    }
+
+    let breaking = changes.iter().filter(|change| change.breaking).count();
+    for &(title, is_breaking) in &[("Breaking changes:", true), ("Non-breaking changes:", false)] {
+        let mut changes = changes.iter().filter(|change| change.breaking == is_breaking).peekable();
+        if changes.peek().is_none() {
+            continue;
+        }
+        println!("{}", title);
+        for change in changes {
+            println!("    {} `{}`: {}", change.kind, change.path, change.reason);
+        }
+    }
+    println!("\n{} breaking and {} non-breaking changes", breaking, changes.len() - breaking);
+}
+
+/// Formats a type the way it would be written in Rust code, for the paths of trait impls. Generic
+/// parameters are written with their canonical names.
+struct TypeDisplay<'a>(&'a types::Type, &'a GenericNames);
+
+impl fmt::Display for TypeDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use types::Type::*;
+
+        let names = self.1;
+        match self.0 {
+            ResolvedPath { name, args, .. } => {
+                write!(f, "{}{}", name, ArgsDisplay(args.as_deref(), names))
+            }
+            Generic(name) => write!(f, "{}", canonical_name(name, names)),
+            Primitive(name) => write!(f, "{}", name),
+            FunctionPointer(fn_pointer) => {
+                write!(f, "fn(")?;
+                let inputs = fn_pointer.decl.inputs.iter().map(|(_, ty)| TypeDisplay(ty, names));
+                write_list(f, inputs)?;
+                write!(f, ")")?;
+                match fn_pointer.decl.output {
+                    Some(ref output) => write!(f, " -> {}", TypeDisplay(output, names)),
+                    None => Ok(()),
+                }
+            }
+            Tuple(elements) => {
+                write!(f, "(")?;
+                write_list(f, elements.iter().map(|ty| TypeDisplay(ty, names)))?;
+                if elements.len() == 1 { write!(f, ",)") } else { write!(f, ")") }
+            }
+            Slice(ty) => write!(f, "[{}]", TypeDisplay(ty, names)),
+            Array { type_, len } => {
+                write!(f, "[{}; {}]", TypeDisplay(type_, names), canonical_name(len, names))
+            }
+            ImplTrait(bounds) => write!(f, "impl {}", BoundsDisplay(bounds, names)),
+            Never => write!(f, "!"),
+            Infer => write!(f, "_"),
+            RawPointer { mutable, type_ } => {
+                let mutability = if *mutable { "mut" } else { "const" };
+                write!(f, "*{} {}", mutability, TypeDisplay(type_, names))
+            }
+            BorrowedRef { lifetime, mutable, type_ } => {
+                write!(f, "&")?;
+                if let Some(lifetime) = lifetime {
+                    write!(f, "{} ", canonical_name(lifetime, names))?;
+                }
+                if *mutable {
+                    write!(f, "mut ")?;
+                }
+                write!(f, "{}", TypeDisplay(type_, names))
+            }
+            QualifiedPath { name, self_type, trait_ } => write!(
+                f,
+                "<{} as {}>::{}",
+                TypeDisplay(self_type, names),
+                TypeDisplay(trait_, names),
+                name
+            ),
+        }
+    }
+}
+
+/// Formats the generic arguments of a path, if there are any.
+struct ArgsDisplay<'a>(Option<&'a types::GenericArgs>, &'a GenericNames);
+
+impl fmt::Display for ArgsDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.1;
+        match self.0 {
+            Some(types::GenericArgs::AngleBracketed { args, bindings })
+                if !args.is_empty() || !bindings.is_empty() =>
+            {
+                write!(f, "<")?;
+                let args = args.iter().map(|arg| match arg {
+                    types::GenericArg::Lifetime(lifetime) => {
+                        canonical_name(lifetime, names).to_string()
+                    }
+                    types::GenericArg::Type(ty) => TypeDisplay(ty, names).to_string(),
+                    types::GenericArg::Const(constant) => {
+                        canonical_name(&constant.expr, names).to_string()
+                    }
+                });
+                let bindings = bindings.iter().map(|binding| match binding.binding {
+                    types::TypeBindingKind::Equality(ref ty) => {
+                        format!("{} = {}", binding.name, TypeDisplay(ty, names))
+                    }
+                    types::TypeBindingKind::Constraint(ref bounds) => {
+                        format!("{}: {}", binding.name, BoundsDisplay(bounds, names))
+                    }
+                });
+                write_list(f, args.chain(bindings))?;
+                write!(f, ">")
+            }
+            Some(types::GenericArgs::Parenthesized { inputs, output }) => {
+                write!(f, "(")?;
+                write_list(f, inputs.iter().map(|ty| TypeDisplay(ty, names)))?;
+                write!(f, ")")?;
+                match output {
+                    Some(output) => write!(f, " -> {}", TypeDisplay(output, names)),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+struct BoundsDisplay<'a>(&'a [types::GenericBound], &'a GenericNames);
+
+impl fmt::Display for BoundsDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, bound) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            match bound {
+                types::GenericBound::TraitBound { trait_, modifier, .. } => {
+                    let modifier = match modifier {
+                        types::TraitBoundModifier::None => "",
+                        types::TraitBoundModifier::Maybe => "?",
+                        types::TraitBoundModifier::MaybeConst => "?const ",
+                    };
+                    write!(f, "{}{}", modifier, TypeDisplay(trait_, self.1))?;
+                }
+                types::GenericBound::Outlives(lifetime) => {
+                    write!(f, "{}", canonical_name(lifetime, self.1))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_list<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl Iterator<Item = T>,
+) -> fmt::Result {
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
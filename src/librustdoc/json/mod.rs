@@ -5,6 +5,7 @@
 //! docs for usage and details.
 
 mod conversions;
+crate mod diff;
 
 use std::cell::RefCell;
 use std::fs::File;
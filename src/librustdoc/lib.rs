@@ -119,6 +119,7 @@ macro_rules! map {
 mod json;
 crate mod lint;
 mod markdown;
+mod md;
 mod passes;
 mod theme;
 mod visit_ast;
@@ -489,7 +490,7 @@ fn opts() -> Vec<RustcOptGroup> {
                 "",
                 "error-format",
                 "How errors and other messages are produced",
-                "human|json|short",
+                "human|json|short|sarif|pretty-sarif",
             )
         }),
         stable("json", |o| {
@@ -550,6 +551,17 @@ fn opts() -> Vec<RustcOptGroup> {
                 "PATH",
             )
         }),
+        unstable("merge-doctests", |o| {
+            o.optflag("", "merge-doctests", "compile compatible doctests into a single binary")
+        }),
+        unstable("doctest-profile-dir", |o| {
+            o.optopt(
+                "",
+                "doctest-profile-dir",
+                "Directory to write the coverage profiles of doctest executables into",
+                "PATH",
+            )
+        }),
         unstable("show-coverage", |o| {
             o.optflag(
                 "",
@@ -557,6 +569,14 @@ fn opts() -> Vec<RustcOptGroup> {
                 "calculate percentage of public items with documentation",
             )
         }),
+        unstable("coverage-threshold", |o| {
+            o.optopt(
+                "",
+                "coverage-threshold",
+                "fail --show-coverage if less than this percentage of items are documented",
+                "PERCENT",
+            )
+        }),
         unstable("enable-per-target-ignores", |o| {
             o.optflag(
                 "",
@@ -603,6 +623,15 @@ fn opts() -> Vec<RustcOptGroup> {
         unstable("show-type-layout", |o| {
             o.optflag("", "show-type-layout", "Include the memory layout of types in the docs")
         }),
+        unstable("api-diff", |o| {
+            o.optopt(
+                "",
+                "api-diff",
+                "compare the public API of the JSON documentation given as input with this \
+                 older version of it, and report the breaking and non-breaking changes",
+                "PATH",
+            )
+        }),
     ]
 }
 
@@ -685,6 +714,10 @@ fn run_renderer<'tcx, T: formats::FormatRenderer<'tcx>>(
 fn main_options(options: config::Options) -> MainResult {
     let diag = core::new_handler(options.error_format, None, &options.debugging_opts);
 
+    if let Some(ref old) = options.api_diff {
+        return wrap_return(&diag, json::diff::run(old, &options.input, options.output_format));
+    }
+
     match (options.should_test, options.markdown_input()) {
         (true, true) => return wrap_return(&diag, markdown::test(options)),
         (true, false) => return doctest::run(options),
@@ -713,6 +746,7 @@ fn main_options(options: config::Options) -> MainResult {
 
     let default_passes = options.default_passes;
     let output_format = options.output_format;
+    let coverage_threshold = options.coverage_threshold;
     // FIXME: fix this clone (especially render_options)
     let manual_passes = options.manual_passes.clone();
     let render_options = options.render_options.clone();
@@ -748,6 +782,7 @@ fn main_options(options: config::Options) -> MainResult {
                         manual_passes,
                         render_options,
                         output_format,
+                        coverage_threshold,
                     )
                 });
                 info!("finished with rustc");
@@ -771,6 +806,9 @@ fn main_options(options: config::Options) -> MainResult {
                     config::OutputFormat::Json => sess.time("render_json", || {
                         run_renderer::<json::JsonRenderer<'_>>(krate, render_opts, cache, tcx)
                     }),
+                    config::OutputFormat::Markdown => sess.time("render_markdown", || {
+                        run_renderer::<md::MarkdownRenderer<'_>>(krate, render_opts, cache, tcx)
+                    }),
                 }
             })
         })
@@ -0,0 +1,669 @@
+//! Plain-text formatting of signatures for the Markdown backend.
+//!
+//! The printers in `html::format` link every path they print to its page, which needs the HTML
+//! renderer's `Context`. Signatures are shown in code blocks in Markdown, which can't contain
+//! links, so this module prints them as plain Rust code instead.
+
+use std::fmt;
+
+use rustc_data_structures::captures::Captures;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir as hir;
+use rustc_hir::def::CtorKind;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::def_id::CRATE_DEF_INDEX;
+use rustc_span::symbol::{kw, Symbol};
+use rustc_span::MacroKind;
+
+use crate::clean::{self, utils::find_nearest_parent_module, FakeDefId, GetDefId};
+use crate::html::format::{display_fn, print_abi_with_space, print_default_space, PrintWithSpace};
+
+fn comma_sep<T: fmt::Display>(items: impl Iterator<Item = T>) -> impl fmt::Display {
+    display_fn(move |f| {
+        for (i, item) in items.enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            fmt::Display::fmt(&item, f)?;
+        }
+        Ok(())
+    })
+}
+
+crate fn print_type<'a, 'tcx: 'a>(
+    t: &'a clean::Type,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| match *t {
+        clean::Generic(name) => write!(f, "{}", name),
+        clean::ResolvedPath { ref param_names, ref path, is_generic, .. } => {
+            if param_names.is_some() {
+                f.write_str("dyn ")?;
+            }
+            // Paths like `T::Output` and `Self::Output` should be printed with all segments.
+            write!(f, "{}", print_path(path, is_generic, tcx))?;
+            for bound in param_names.iter().flatten() {
+                write!(f, " + {}", print_generic_bound(bound, tcx))?;
+            }
+            Ok(())
+        }
+        clean::Infer => f.write_str("_"),
+        clean::Primitive(prim) => write!(f, "{}", prim.as_sym()),
+        clean::BareFunction(ref decl) => {
+            if !decl.generic_params.is_empty() {
+                write!(
+                    f,
+                    "for<{}> ",
+                    comma_sep(decl.generic_params.iter().map(|g| print_generic_param(g, tcx)))
+                )?;
+            }
+            write!(
+                f,
+                "{}{:#}fn{}",
+                decl.unsafety.print_with_space(),
+                print_abi_with_space(decl.abi),
+                print_fn_decl(&decl.decl, tcx),
+            )
+        }
+        clean::Tuple(ref typs) => match &typs[..] {
+            [one] => write!(f, "({},)", print_type(one, tcx)),
+            many => write!(f, "({})", comma_sep(many.iter().map(|t| print_type(t, tcx)))),
+        },
+        clean::Slice(ref t) => write!(f, "[{}]", print_type(t, tcx)),
+        clean::Array(ref t, ref n) => write!(f, "[{}; {}]", print_type(t, tcx), n),
+        clean::Never => f.write_str("!"),
+        clean::RawPointer(m, ref t) => {
+            let m = match m {
+                hir::Mutability::Mut => "mut",
+                hir::Mutability::Not => "const",
+            };
+            write!(f, "*{} {}", m, print_type(t, tcx))
+        }
+        clean::BorrowedRef { ref lifetime, mutability, ref type_ } => {
+            f.write_str("&")?;
+            if let Some(lt) = lifetime {
+                write!(f, "{} ", lt.print())?;
+            }
+            f.write_str(mutability.print_with_space())?;
+            match **type_ {
+                clean::ResolvedPath { param_names: Some(ref v), .. } if !v.is_empty() => {
+                    write!(f, "({})", print_type(type_, tcx))
+                }
+                _ => write!(f, "{}", print_type(type_, tcx)),
+            }
+        }
+        clean::ImplTrait(ref bounds) => write!(f, "impl {}", print_generic_bounds(bounds, tcx)),
+        clean::QPath { ref name, ref self_type, ref trait_, ref self_def_id } => {
+            let should_show_cast = match **trait_ {
+                clean::ResolvedPath { ref path, .. } => {
+                    !path.segments.is_empty()
+                        && self_def_id
+                            .zip(trait_.def_id())
+                            .map_or(!self_type.is_self_type(), |(id, trait_)| id != trait_)
+                }
+                _ => true,
+            };
+            if should_show_cast {
+                write!(
+                    f,
+                    "<{} as {}>::{}",
+                    print_type(self_type, tcx),
+                    print_type(trait_, tcx),
+                    name
+                )
+            } else {
+                write!(f, "{}::{}", print_type(self_type, tcx), name)
+            }
+        }
+    })
+}
+
+fn print_path<'a, 'tcx: 'a>(
+    path: &'a clean::Path,
+    print_all: bool,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        let last = path.segments.last().unwrap();
+        if print_all {
+            for seg in &path.segments[..path.segments.len() - 1] {
+                write!(f, "{}::", seg.name)?;
+            }
+        }
+        write!(f, "{}{}", last.name, print_generic_args(&last.args, tcx))
+    })
+}
+
+fn print_generic_args<'a, 'tcx: 'a>(
+    args: &'a clean::GenericArgs,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| match args {
+        clean::GenericArgs::AngleBracketed { args, bindings } => {
+            if args.is_empty() && bindings.is_empty() {
+                return Ok(());
+            }
+            let mut parts: Vec<String> = args
+                .iter()
+                .map(|arg| match arg {
+                    clean::GenericArg::Lifetime(lt) => lt.print().to_string(),
+                    clean::GenericArg::Type(ty) => print_type(ty, tcx).to_string(),
+                    clean::GenericArg::Const(ct) => format!("{:#}", ct.print(tcx)),
+                })
+                .collect();
+            parts.extend(bindings.iter().map(|binding| match binding.kind {
+                clean::TypeBindingKind::Equality { ref ty } => {
+                    format!("{} = {}", binding.name, print_type(ty, tcx))
+                }
+                clean::TypeBindingKind::Constraint { ref bounds } => {
+                    format!("{}: {}", binding.name, print_generic_bounds(bounds, tcx))
+                }
+            }));
+            write!(f, "<{}>", parts.join(", "))
+        }
+        clean::GenericArgs::Parenthesized { inputs, output } => {
+            write!(f, "({})", comma_sep(inputs.iter().map(|ty| print_type(ty, tcx))))?;
+            if let Some(ref ty) = *output {
+                write!(f, " -> {}", print_type(ty, tcx))?;
+            }
+            Ok(())
+        }
+    })
+}
+
+fn print_generic_bound<'a, 'tcx: 'a>(
+    bound: &'a clean::GenericBound,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| match bound {
+        clean::GenericBound::Outlives(lt) => write!(f, "{}", lt.print()),
+        clean::GenericBound::TraitBound(poly_trait, modifier) => {
+            let modifier_str = match modifier {
+                hir::TraitBoundModifier::None => "",
+                hir::TraitBoundModifier::Maybe => "?",
+                hir::TraitBoundModifier::MaybeConst => "?const",
+            };
+            f.write_str(modifier_str)?;
+            if !poly_trait.generic_params.is_empty() {
+                write!(
+                    f,
+                    "for<{}> ",
+                    comma_sep(
+                        poly_trait.generic_params.iter().map(|g| print_generic_param(g, tcx))
+                    )
+                )?;
+            }
+            write!(f, "{}", print_type(&poly_trait.trait_, tcx))
+        }
+    })
+}
+
+crate fn print_generic_bounds<'a, 'tcx: 'a>(
+    bounds: &'a [clean::GenericBound],
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        let mut bounds_dup = FxHashSet::default();
+        let bounds = bounds
+            .iter()
+            .map(|b| print_generic_bound(b, tcx).to_string())
+            .filter(|b| bounds_dup.insert(b.clone()));
+        for (i, bound) in bounds.enumerate() {
+            if i > 0 {
+                f.write_str(" + ")?;
+            }
+            f.write_str(&bound)?;
+        }
+        Ok(())
+    })
+}
+
+fn print_generic_param<'a, 'tcx: 'a>(
+    param: &'a clean::GenericParamDef,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| match param.kind {
+        clean::GenericParamDefKind::Lifetime => write!(f, "{}", param.name),
+        clean::GenericParamDefKind::Type { ref bounds, ref default, .. } => {
+            write!(f, "{}", param.name)?;
+            if !bounds.is_empty() {
+                write!(f, ": {}", print_generic_bounds(bounds, tcx))?;
+            }
+            if let Some(ref ty) = default {
+                write!(f, " = {}", print_type(ty, tcx))?;
+            }
+            Ok(())
+        }
+        clean::GenericParamDefKind::Const { ref ty, ref default, .. } => {
+            write!(f, "const {}: {}", param.name, print_type(ty, tcx))?;
+            if let Some(default) = default {
+                write!(f, " = {}", default)?;
+            }
+            Ok(())
+        }
+    })
+}
+
+crate fn print_generics<'a, 'tcx: 'a>(
+    generics: &'a clean::Generics,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        let real_params = generics.params.iter().filter(|p| !p.is_synthetic_type_param());
+        let mut real_params = real_params.peekable();
+        if real_params.peek().is_none() {
+            return Ok(());
+        }
+        write!(f, "<{}>", comma_sep(real_params.map(|g| print_generic_param(g, tcx))))
+    })
+}
+
+/// Prints the where-clause of `generics` on a single line, with a leading space.
+crate fn print_where_clause<'a, 'tcx: 'a>(
+    generics: &'a clean::Generics,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        if generics.where_predicates.is_empty() {
+            return Ok(());
+        }
+        let predicates = generics.where_predicates.iter().map(|pred| {
+            display_fn(move |f| match pred {
+                clean::WherePredicate::BoundPredicate { ty, bounds } => {
+                    write!(f, "{}: {}", print_type(ty, tcx), print_generic_bounds(bounds, tcx))
+                }
+                clean::WherePredicate::RegionPredicate { lifetime, bounds } => {
+                    write!(f, "{}: {}", lifetime.print(), print_generic_bounds(bounds, tcx))
+                }
+                clean::WherePredicate::EqPredicate { lhs, rhs } => {
+                    write!(f, "{} == {}", print_type(lhs, tcx), print_type(rhs, tcx))
+                }
+            })
+        });
+        write!(f, " where {}", comma_sep(predicates))
+    })
+}
+
+crate fn print_fn_decl<'a, 'tcx: 'a>(
+    decl: &'a clean::FnDecl,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    print_fn_decl_with_output(decl, &decl.output, tcx)
+}
+
+fn print_fn_decl_with_output<'a, 'tcx: 'a>(
+    decl: &'a clean::FnDecl,
+    output: &'a clean::FnRetTy,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        let args = decl.inputs.values.iter().map(|input| {
+            display_fn(move |f| match input.to_self() {
+                Some(clean::SelfValue) => f.write_str("self"),
+                Some(clean::SelfBorrowed(Some(ref lt), mtbl)) => {
+                    write!(f, "&{} {}self", lt.print(), mtbl.print_with_space())
+                }
+                Some(clean::SelfBorrowed(None, mtbl)) => {
+                    write!(f, "&{}self", mtbl.print_with_space())
+                }
+                Some(clean::SelfExplicit(ref typ)) => write!(f, "self: {}", print_type(typ, tcx)),
+                None => {
+                    if !input.name.is_empty() {
+                        write!(f, "{}: ", input.name)?;
+                    }
+                    write!(f, "{}", print_type(&input.type_, tcx))
+                }
+            })
+        });
+        write!(f, "({}", comma_sep(args))?;
+        if decl.c_variadic {
+            f.write_str(", ...")?;
+        }
+        f.write_str(")")?;
+        match output {
+            clean::Return(clean::Tuple(tys)) if tys.is_empty() => Ok(()),
+            clean::Return(ty) => write!(f, " -> {}", print_type(ty, tcx)),
+            clean::DefaultReturn => Ok(()),
+        }
+    })
+}
+
+crate fn print_visibility<'a, 'tcx: 'a>(
+    vis: clean::Visibility,
+    item_did: FakeDefId,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| match vis {
+        clean::Public => f.write_str("pub "),
+        clean::Inherited => Ok(()),
+        clean::Visibility::Restricted(vis_did) => {
+            let parent_module = find_nearest_parent_module(tcx, item_did.expect_real());
+            if vis_did.index == CRATE_DEF_INDEX {
+                f.write_str("pub(crate) ")
+            } else if parent_module == Some(vis_did) {
+                // `pub(in foo)` where `foo` is the parent module
+                // is the same as no visibility modifier
+                Ok(())
+            } else if parent_module.and_then(|parent| find_nearest_parent_module(tcx, parent))
+                == Some(vis_did)
+            {
+                f.write_str("pub(super) ")
+            } else {
+                write!(f, "pub(in {}) ", tcx.def_path_str(vis_did))
+            }
+        }
+    })
+}
+
+crate fn print_import<'a>(import: &'a clean::Import) -> impl fmt::Display + 'a {
+    display_fn(move |f| {
+        let source = import.source.path.whole_name();
+        match import.kind {
+            clean::ImportKind::Simple(name) if name == import.source.path.last() => {
+                write!(f, "use {};", source)
+            }
+            clean::ImportKind::Simple(name) => write!(f, "use {} as {};", source, name),
+            clean::ImportKind::Glob if import.source.path.segments.is_empty() => {
+                f.write_str("use *;")
+            }
+            clean::ImportKind::Glob => write!(f, "use {}::*;", source),
+        }
+    })
+}
+
+crate fn print_impl<'a, 'tcx: 'a>(
+    impl_: &'a clean::Impl,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        write!(
+            f,
+            "{}impl{} ",
+            impl_.unsafety.print_with_space(),
+            print_generics(&impl_.generics, tcx)
+        )?;
+        if let Some(ref ty) = impl_.trait_ {
+            if impl_.negative_polarity {
+                f.write_str("!")?;
+            }
+            write!(f, "{} for ", print_type(ty, tcx))?;
+        }
+        let for_ = impl_.blanket_impl.as_deref().unwrap_or(&impl_.for_);
+        write!(f, "{}{}", print_type(for_, tcx), print_where_clause(&impl_.generics, tcx))
+    })
+}
+
+fn print_function<'a, 'tcx: 'a>(
+    item: &'a clean::Item,
+    func: &'a clean::Function,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        let header = &func.header;
+        let output = match header.asyncness {
+            hir::IsAsync::Async => func.decl.sugared_async_return_type(),
+            hir::IsAsync::NotAsync => func.decl.output.clone(),
+        };
+        write!(
+            f,
+            "{}{}{}{}{}{:#}fn {}{}{}{}",
+            print_default_space(item.is_default()),
+            print_visibility(item.visibility, item.def_id, tcx),
+            header.constness.print_with_space(),
+            header.asyncness.print_with_space(),
+            header.unsafety.print_with_space(),
+            print_abi_with_space(header.abi),
+            item.name.unwrap(),
+            print_generics(&func.generics, tcx),
+            print_fn_decl_with_output(&func.decl, &output, tcx),
+            print_where_clause(&func.generics, tcx),
+        )
+    })
+}
+
+/// Prints the fields of a struct, union or struct variant, one per line and indented by `indent`
+/// spaces, followed by the closing brace.
+fn print_fields(
+    f: &mut fmt::Formatter<'_>,
+    fields: &[clean::Item],
+    fields_stripped: bool,
+    indent: usize,
+    tcx: TyCtxt<'_>,
+) -> fmt::Result {
+    let pad = " ".repeat(indent);
+    let mut has_private_fields = fields_stripped;
+    for field in fields {
+        match *field.kind {
+            clean::StructFieldItem(ref ty) => writeln!(
+                f,
+                "{}    {}{}: {},",
+                pad,
+                print_visibility(field.visibility, field.def_id, tcx),
+                field.name.unwrap(),
+                print_type(ty, tcx),
+            )?,
+            _ => has_private_fields = true,
+        }
+    }
+    if has_private_fields {
+        writeln!(f, "{}    /* private fields */", pad)?;
+    }
+    write!(f, "{}}}", pad)
+}
+
+fn print_tuple_fields<'a, 'tcx: 'a>(
+    fields: &'a [clean::Item],
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    let fields = fields.iter().map(move |field| {
+        display_fn(move |f| match *field.kind {
+            clean::StructFieldItem(ref ty) => write!(
+                f,
+                "{}{}",
+                print_visibility(field.visibility, field.def_id, tcx),
+                print_type(ty, tcx)
+            ),
+            _ => f.write_str("_"),
+        })
+    });
+    display_fn(move |f| write!(f, "({})", comma_sep(fields)))
+}
+
+fn print_variant<'a, 'tcx: 'a>(
+    name: Symbol,
+    variant: &'a clean::Variant,
+    indent: usize,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        write!(f, "{}", name)?;
+        match variant {
+            clean::Variant::CLike => Ok(()),
+            clean::Variant::Tuple(tys) => {
+                write!(f, "({})", comma_sep(tys.iter().map(|ty| print_type(ty, tcx))))
+            }
+            clean::Variant::Struct(s) => {
+                f.write_str(" {\n")?;
+                print_fields(f, &s.fields, s.fields_stripped, indent, tcx)
+            }
+        }
+    })
+}
+
+/// Prints the declaration of `item`, or nothing for items without one, like modules and
+/// primitive types.
+crate fn print_item<'a, 'tcx: 'a>(
+    item: &'a clean::Item,
+    tcx: TyCtxt<'tcx>,
+) -> impl fmt::Display + 'a + Captures<'tcx> {
+    display_fn(move |f| {
+        let vis = print_visibility(item.visibility, item.def_id, tcx);
+        let name = item.name.unwrap_or(kw::Empty);
+        match *item.kind {
+            clean::FunctionItem(ref func)
+            | clean::ForeignFunctionItem(ref func)
+            | clean::MethodItem(ref func, _) => write!(f, "{}", print_function(item, func, tcx)),
+            clean::TyMethodItem(ref func) => write!(f, "{};", print_function(item, func, tcx)),
+            clean::StructItem(ref s) => {
+                write!(f, "{}struct {}{}", vis, name, print_generics(&s.generics, tcx))?;
+                match s.struct_type {
+                    CtorKind::Fictive => {
+                        writeln!(f, "{} {{", print_where_clause(&s.generics, tcx))?;
+                        print_fields(f, &s.fields, s.fields_stripped, 0, tcx)
+                    }
+                    CtorKind::Fn => write!(
+                        f,
+                        "{}{};",
+                        print_tuple_fields(&s.fields, tcx),
+                        print_where_clause(&s.generics, tcx)
+                    ),
+                    CtorKind::Const => write!(f, "{};", print_where_clause(&s.generics, tcx)),
+                }
+            }
+            clean::UnionItem(ref u) => {
+                writeln!(
+                    f,
+                    "{}union {}{}{} {{",
+                    vis,
+                    name,
+                    print_generics(&u.generics, tcx),
+                    print_where_clause(&u.generics, tcx)
+                )?;
+                print_fields(f, &u.fields, u.fields_stripped, 0, tcx)
+            }
+            clean::EnumItem(ref e) => {
+                writeln!(
+                    f,
+                    "{}enum {}{}{} {{",
+                    vis,
+                    name,
+                    print_generics(&e.generics, tcx),
+                    print_where_clause(&e.generics, tcx)
+                )?;
+                for variant in &e.variants {
+                    if let clean::VariantItem(ref v) = *variant.kind {
+                        writeln!(f, "    {},", print_variant(variant.name.unwrap(), v, 4, tcx))?;
+                    }
+                }
+                if e.variants_stripped {
+                    f.write_str("    // some variants omitted\n")?;
+                }
+                f.write_str("}")
+            }
+            clean::VariantItem(ref v) => write!(f, "{}", print_variant(name, v, 0, tcx)),
+            clean::StructFieldItem(ref ty) => write!(f, "{}{}: {}", vis, name, print_type(ty, tcx)),
+            clean::TraitItem(ref t) => {
+                write!(
+                    f,
+                    "{}{}{}trait {}{}",
+                    vis,
+                    t.unsafety.print_with_space(),
+                    if t.is_auto { "auto " } else { "" },
+                    name,
+                    print_generics(&t.generics, tcx),
+                )?;
+                if !t.bounds.is_empty() {
+                    write!(f, ": {}", print_generic_bounds(&t.bounds, tcx))?;
+                }
+                write!(f, "{} {{", print_where_clause(&t.generics, tcx))?;
+                if t.items.is_empty() {
+                    return f.write_str("}");
+                }
+                f.write_str("\n")?;
+                for trait_item in &t.items {
+                    match *trait_item.kind {
+                        clean::MethodItem(..) => {
+                            writeln!(f, "    {} {{ ... }}", print_item(trait_item, tcx))?
+                        }
+                        _ => writeln!(f, "    {}", print_item(trait_item, tcx))?,
+                    }
+                }
+                f.write_str("}")
+            }
+            clean::TraitAliasItem(ref t) => write!(
+                f,
+                "trait {}{} = {}{};",
+                name,
+                print_generics(&t.generics, tcx),
+                print_generic_bounds(&t.bounds, tcx),
+                print_where_clause(&t.generics, tcx),
+            ),
+            clean::TypedefItem(ref t, _) => write!(
+                f,
+                "{}type {}{}{} = {};",
+                vis,
+                name,
+                print_generics(&t.generics, tcx),
+                print_where_clause(&t.generics, tcx),
+                print_type(&t.type_, tcx),
+            ),
+            clean::OpaqueTyItem(ref t) => write!(
+                f,
+                "type {}{}{} = impl {};",
+                name,
+                print_generics(&t.generics, tcx),
+                print_where_clause(&t.generics, tcx),
+                print_generic_bounds(&t.bounds, tcx),
+            ),
+            clean::ConstantItem(ref c) => write!(
+                f,
+                "{}const {}: {} = {:#};",
+                vis,
+                name,
+                print_type(&c.type_, tcx),
+                c.print(tcx),
+            ),
+            clean::StaticItem(ref s) | clean::ForeignStaticItem(ref s) => write!(
+                f,
+                "{}static {}{}: {};",
+                vis,
+                s.mutability.print_with_space(),
+                name,
+                print_type(&s.type_, tcx),
+            ),
+            clean::ForeignTypeItem => write!(f, "{}type {};", vis, name),
+            clean::AssocConstItem(ref ty, ref default) => {
+                write!(f, "{}const {}: {}", vis, name, print_type(ty, tcx))?;
+                if let Some(default) = default {
+                    write!(f, " = {}", default)?;
+                }
+                f.write_str(";")
+            }
+            clean::AssocTypeItem(ref bounds, ref default) => {
+                write!(f, "type {}", name)?;
+                if !bounds.is_empty() {
+                    write!(f, ": {}", print_generic_bounds(bounds, tcx))?;
+                }
+                if let Some(ref default) = default {
+                    write!(f, " = {}", print_type(default, tcx))?;
+                }
+                f.write_str(";")
+            }
+            clean::MacroItem(ref m) => f.write_str(&m.source),
+            clean::ProcMacroItem(ref m) => match m.kind {
+                MacroKind::Bang => write!(f, "{}!() {{ /* proc-macro */ }}", name),
+                MacroKind::Attr => write!(f, "#[{}]", name),
+                MacroKind::Derive => {
+                    write!(f, "#[derive({})]", name)?;
+                    if !m.helpers.is_empty() {
+                        f.write_str("\n\n// Attributes available to this derive:")?;
+                        for attr in &m.helpers {
+                            write!(f, "\n#[{}]", attr)?;
+                        }
+                    }
+                    Ok(())
+                }
+            },
+            clean::ExternCrateItem { src: Some(src) } => {
+                write!(f, "{}extern crate {} as {};", vis, src, name)
+            }
+            clean::ExternCrateItem { src: None } => write!(f, "{}extern crate {};", vis, name),
+            clean::ImportItem(ref import) => write!(f, "{}{}", vis, print_import(import)),
+            clean::ImplItem(ref impl_) => write!(f, "{}", print_impl(impl_, tcx)),
+            clean::ModuleItem(_)
+            | clean::PrimitiveItem(_)
+            | clean::KeywordItem(_)
+            | clean::StrippedItem(_) => Ok(()),
+        }
+    })
+}
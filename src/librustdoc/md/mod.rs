@@ -0,0 +1,684 @@
+//! Rustdoc's Markdown backend
+//!
+//! This module contains the logic for rendering a crate as a tree of Markdown files rather than the
+//! normal static HTML output, for use with tools that consume Markdown, like static site
+//! generators, or `pandoc` to produce man pages. The files are laid out like the HTML pages: each
+//! module gets a directory with an `index.md`, and each other item a `<type>.<name>.md` file in the
+//! directory of its module. Associated items, fields and variants are documented on the page of
+//! their parent item.
+
+mod format;
+#[cfg(test)]
+mod tests;
+
+use std::cmp;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::symbol::kw;
+
+use crate::clean::{self, utils::DOC_RUST_LANG_ORG_CHANNEL, GetDefId, ItemLink, RenderedLink};
+use crate::config::RenderOptions;
+use crate::error::Error;
+use crate::formats::cache::Cache;
+use crate::formats::item_type::ItemType;
+use crate::formats::{FormatRenderer, Impl};
+use crate::html::format::href_relative_parts;
+use crate::html::markdown::{map_line, opts, plain_text_summary, ErrorCodes, LangString};
+use crate::html::render::cache::ExternalLocation;
+use crate::html::render::item_ty_to_strs;
+use crate::md::format::{print_impl, print_item};
+
+#[derive(Clone)]
+crate struct MarkdownRenderer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// The directory the current module is rendered into.
+    dst: PathBuf,
+    /// The path of the current module, starting with the crate name.
+    current: Vec<String>,
+    /// Whether the current module is stripped. Stripped modules are recursed into because they
+    /// can contain impls for public types, but nothing in them gets a page.
+    in_stripped_module: bool,
+    sort_modules_alphabetically: bool,
+    cache: Rc<Cache>,
+}
+
+impl MarkdownRenderer<'tcx> {
+    /// Returns the URL of the page documenting `did`, relative to the current module.
+    ///
+    /// Pages of the local crate and of crates documented into the same output directory are
+    /// linked relatively, so the links work wherever the output directory is moved to. Crates
+    /// with a remote location are linked to their HTML documentation.
+    fn href(&self, did: DefId) -> Option<String> {
+        let cache = &self.cache;
+        if !did.is_local() && !cache.access_levels.is_public(did) && !cache.document_private {
+            return None;
+        }
+
+        fn to_module_fqp(shortty: ItemType, fqp: &[String]) -> &[String] {
+            if shortty == ItemType::Module { &fqp[..] } else { &fqp[..fqp.len() - 1] }
+        }
+
+        let (fqp, shortty, url_parts, extension) = match cache.paths.get(&did) {
+            Some(&(ref fqp, shortty)) => {
+                let module_fqp = to_module_fqp(shortty, fqp);
+                (fqp, shortty, href_relative_parts(module_fqp, &self.current), "md")
+            }
+            None => {
+                let &(ref fqp, shortty) = cache.external_paths.get(&did)?;
+                let module_fqp = to_module_fqp(shortty, fqp);
+                match cache.extern_locations[&did.krate] {
+                    ExternalLocation::Remote(ref s) => {
+                        let mut parts = vec![s.trim_end_matches('/')];
+                        parts.extend(module_fqp.iter().map(String::as_str));
+                        (fqp, shortty, parts, "html")
+                    }
+                    ExternalLocation::Local => {
+                        (fqp, shortty, href_relative_parts(module_fqp, &self.current), "md")
+                    }
+                    ExternalLocation::Unknown => return None,
+                }
+            }
+        };
+
+        let mut url = url_parts.join("/");
+        if !url.is_empty() {
+            url.push('/');
+        }
+        match shortty {
+            ItemType::Module => write!(url, "index.{}", extension).unwrap(),
+            _ => write!(url, "{}.{}.{}", shortty, fqp.last().unwrap(), extension).unwrap(),
+        }
+        Some(url)
+    }
+
+    /// Resolves the intra-doc links of `item` to URLs, like `clean::Item::links` does for the HTML
+    /// output.
+    fn links(&self, item: &clean::Item) -> Vec<RenderedLink> {
+        self.cache
+            .intra_doc_links
+            .get(&item.def_id)
+            .map_or(&[][..], |v| v.as_slice())
+            .iter()
+            .filter_map(|ItemLink { link: s, link_text, did, ref fragment }| {
+                let href = match did {
+                    Some(did) => {
+                        let mut href = self.href(*did)?;
+                        if let Some(ref fragment) = *fragment {
+                            href.push('#');
+                            href.push_str(fragment);
+                        }
+                        href
+                    }
+                    // Links to primitives don't have a `DefId`, the primitive is in the fragment.
+                    None => {
+                        let fragment = fragment.as_ref()?;
+                        let (url, extension) =
+                            match self.cache.extern_locations.get(&item.def_id.krate()) {
+                                Some(&ExternalLocation::Local) => {
+                                    if self.current[0] == "std" {
+                                        ("../".repeat(self.current.len() - 1), "md")
+                                    } else {
+                                        (format!("{}std/", "../".repeat(self.current.len())), "md")
+                                    }
+                                }
+                                Some(ExternalLocation::Remote(ref s)) => {
+                                    (format!("{}/std/", s.trim_end_matches('/')), "html")
+                                }
+                                Some(ExternalLocation::Unknown) | None => {
+                                    (format!("{}/std/", DOC_RUST_LANG_ORG_CHANNEL), "html")
+                                }
+                            };
+                        let tail = fragment.find('#').unwrap_or_else(|| fragment.len());
+                        format!(
+                            "{}primitive.{}.{}{}",
+                            url,
+                            &fragment[..tail],
+                            extension,
+                            &fragment[tail..]
+                        )
+                    }
+                };
+                Some(RenderedLink { original_text: s.clone(), new_text: link_text.clone(), href })
+            })
+            .collect()
+    }
+
+    /// Returns the documentation of `item` with its intra-doc links resolved, followed by a blank
+    /// line, or nothing if it is undocumented. The documentation is nested under a heading of
+    /// `level`.
+    fn docs(&self, item: &clean::Item, level: u32) -> String {
+        match item.collapsed_doc_value() {
+            Some(doc) if !doc.trim().is_empty() => {
+                let doc = rewrite_docs(doc.trim(), level);
+                format!("{}\n\n", resolve_links(&doc, &self.links(item)))
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Formats `code` as inline code, linked to the page of `did` if there is one.
+    fn code_link(&self, code: &str, did: Option<DefId>) -> String {
+        match did.and_then(|did| self.href(did)) {
+            Some(href) => format!("[`{}`]({})", code, href),
+            None => format!("`{}`", code),
+        }
+    }
+
+    fn render_title(&self, page: &mut String, item: &clean::Item) {
+        let kind = match *item.kind {
+            clean::ModuleItem(_) => {
+                if item.is_crate() {
+                    "Crate"
+                } else {
+                    "Module"
+                }
+            }
+            clean::FunctionItem(..) | clean::ForeignFunctionItem(..) => "Function",
+            clean::TraitItem(..) => "Trait",
+            clean::StructItem(..) => "Struct",
+            clean::UnionItem(..) => "Union",
+            clean::EnumItem(..) => "Enum",
+            clean::TypedefItem(..) => "Type Definition",
+            clean::MacroItem(..) => "Macro",
+            clean::ProcMacroItem(ref mac) => match mac.kind {
+                rustc_span::MacroKind::Bang => "Macro",
+                rustc_span::MacroKind::Attr => "Attribute Macro",
+                rustc_span::MacroKind::Derive => "Derive Macro",
+            },
+            clean::PrimitiveItem(..) => "Primitive Type",
+            clean::StaticItem(..) | clean::ForeignStaticItem(..) => "Static",
+            clean::ConstantItem(..) => "Constant",
+            clean::ForeignTypeItem => "Foreign Type",
+            clean::KeywordItem(..) => "Keyword",
+            clean::OpaqueTyItem(..) => "Opaque Type",
+            clean::TraitAliasItem(..) => "Trait Alias",
+            _ => {
+                // We don't generate pages for any other type.
+                unreachable!();
+            }
+        };
+        let name = item.name.unwrap();
+        if item.is_mod() {
+            writeln!(page, "# {} `{}`\n", kind, self.current.join("::")).unwrap();
+        } else if item.is_primitive() || item.is_keyword() {
+            writeln!(page, "# {} `{}`\n", kind, name).unwrap();
+        } else {
+            writeln!(page, "# {} `{}::{}`\n", kind, self.current.join("::"), name).unwrap();
+        }
+    }
+
+    fn render_module(&self, item: &clean::Item, module: &clean::Module) -> String {
+        let mut page = String::new();
+        self.render_title(&mut page, item);
+        page.push_str(&self.docs(item, 1));
+
+        // The sections are ordered like in the HTML output.
+        fn reorder(ty: ItemType) -> u8 {
+            match ty {
+                ItemType::ExternCrate | ItemType::Import => 0,
+                ItemType::Primitive => 2,
+                ItemType::Module => 3,
+                ItemType::Macro => 4,
+                ItemType::Struct => 5,
+                ItemType::Enum => 6,
+                ItemType::Constant => 7,
+                ItemType::Static => 8,
+                ItemType::Trait => 9,
+                ItemType::Function => 10,
+                ItemType::Typedef => 12,
+                ItemType::Union => 13,
+                _ => 14 + ty as u8,
+            }
+        }
+
+        let mut items: Vec<_> = module
+            .items
+            .iter()
+            .filter(|it| !it.is_stripped() && (it.name.is_some() || it.is_import()))
+            .collect();
+        if self.sort_modules_alphabetically {
+            items.sort_by_key(|it| it.name.unwrap_or(kw::Empty).as_str());
+        }
+        items.sort_by_key(|it| reorder(it.type_()));
+
+        let mut current_section = None;
+        for it in items {
+            let (_, section) = item_ty_to_strs(it.type_());
+            if current_section != Some(section) {
+                current_section = Some(section);
+                writeln!(page, "\n## {}\n", section).unwrap();
+            }
+            match *it.kind {
+                clean::ImportItem(ref import) => {
+                    let code = print_item(it, self.tcx).to_string();
+                    writeln!(page, "- {}", self.code_link(&code, import.source.did)).unwrap();
+                }
+                clean::ExternCrateItem { .. } => {
+                    let code = print_item(it, self.tcx).to_string();
+                    writeln!(page, "- {}", self.code_link(&code, it.def_id.as_real())).unwrap();
+                }
+                _ => {
+                    let name = it.name.unwrap();
+                    let file = match it.type_() {
+                        ItemType::Module => format!("{}/index.md", name),
+                        ty => format!("{}.{}.md", ty, name),
+                    };
+                    let summary = it
+                        .doc_value()
+                        .map(|doc| plain_text_summary(&doc))
+                        .filter(|summary| !summary.is_empty());
+                    match summary {
+                        Some(summary) => {
+                            writeln!(page, "- [`{}`]({}): {}", name, file, summary).unwrap()
+                        }
+                        None => writeln!(page, "- [`{}`]({})", name, file).unwrap(),
+                    }
+                }
+            }
+        }
+        page
+    }
+
+    fn render_item(&self, item: &clean::Item) -> String {
+        let mut page = String::new();
+        self.render_title(&mut page, item);
+
+        let signature = print_item(item, self.tcx).to_string();
+        if !signature.is_empty() {
+            writeln!(page, "```rust\n{}\n```\n", signature).unwrap();
+        }
+        page.push_str(&self.docs(item, 1));
+
+        match *item.kind {
+            clean::StructItem(clean::Struct { ref fields, .. })
+            | clean::UnionItem(clean::Union { ref fields, .. }) => {
+                self.render_members(&mut page, "Fields", fields);
+            }
+            clean::EnumItem(ref e) => {
+                self.render_members(&mut page, "Variants", &e.variants);
+            }
+            clean::TraitItem(ref t) => {
+                let sections = [
+                    (ItemType::AssocType, "Associated Types"),
+                    (ItemType::AssocConst, "Associated Constants"),
+                    (ItemType::TyMethod, "Required Methods"),
+                    (ItemType::Method, "Provided Methods"),
+                ];
+                for &(ty, title) in &sections {
+                    let items = t.items.iter().filter(|it| it.type_() == ty);
+                    self.render_members(&mut page, title, items);
+                }
+                self.render_implementors(&mut page, item.def_id.expect_real());
+            }
+            _ => {}
+        }
+
+        if let Some(did) = item.def_id.as_real() {
+            self.render_impls(&mut page, did);
+        }
+        page
+    }
+
+    /// Renders the documentation of the fields, variants or associated items of an item, each
+    /// with an anchor that intra-doc links can point to.
+    fn render_members<'a>(
+        &self,
+        page: &mut String,
+        title: &str,
+        members: impl IntoIterator<Item = &'a clean::Item>,
+    ) {
+        let mut members = members.into_iter().filter(|it| !it.is_stripped()).peekable();
+        if members.peek().is_none() {
+            return;
+        }
+        writeln!(page, "## {}\n", title).unwrap();
+        for member in members {
+            writeln!(
+                page,
+                "### <a id=\"{}.{}\"></a>`{}`\n",
+                member.type_(),
+                member.name.unwrap(),
+                // Struct variants are printed on several lines.
+                print_item(member, self.tcx)
+                    .to_string()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )
+            .unwrap();
+            page.push_str(&self.docs(member, 3));
+        }
+    }
+
+    fn render_impls(&self, page: &mut String, did: DefId) {
+        let impls = match self.cache.impls.get(&did) {
+            Some(impls) => impls,
+            None => return,
+        };
+        let (inherent, traits): (Vec<&Impl>, Vec<&Impl>) =
+            impls.iter().partition(|i| i.inner_impl().trait_.is_none());
+        let (synthetic, concrete): (Vec<&Impl>, Vec<&Impl>) =
+            traits.into_iter().partition(|i| i.inner_impl().synthetic);
+        let (blanket, concrete): (Vec<&Impl>, Vec<&Impl>) =
+            concrete.into_iter().partition(|i| i.inner_impl().blanket_impl.is_some());
+
+        if !inherent.is_empty() {
+            page.push_str("## Implementations\n\n");
+            for i in inherent {
+                writeln!(page, "### `{}`\n", print_impl(i.inner_impl(), self.tcx)).unwrap();
+                let items = i.inner_impl().items.iter().filter(|it| !it.is_stripped());
+                for it in items {
+                    writeln!(
+                        page,
+                        "#### <a id=\"{}.{}\"></a>`{}`\n",
+                        it.type_(),
+                        it.name.unwrap(),
+                        print_item(it, self.tcx),
+                    )
+                    .unwrap();
+                    page.push_str(&self.docs(it, 4));
+                }
+            }
+        }
+
+        if !concrete.is_empty() {
+            page.push_str("## Trait Implementations\n\n");
+            for i in concrete {
+                let impl_ = i.inner_impl();
+                let code = print_impl(impl_, self.tcx).to_string();
+                writeln!(page, "### {}\n", self.code_link(&code, impl_.trait_.def_id())).unwrap();
+
+                // The documentation of the items is on the page of the trait.
+                let provided = impl_.provided_trait_methods(self.tcx);
+                let trait_href = impl_.trait_.def_id().and_then(|did| self.href(did));
+                let items: Vec<_> = impl_.items.iter().filter(|it| !it.is_stripped()).collect();
+                for it in &items {
+                    let code = print_item(it, self.tcx).to_string();
+                    let anchor_ty = match it.type_() {
+                        ItemType::Method if !provided.contains(&it.name.unwrap()) => {
+                            ItemType::TyMethod
+                        }
+                        ty => ty,
+                    };
+                    match trait_href {
+                        Some(ref href) => writeln!(
+                            page,
+                            "- [`{}`]({}#{}.{})",
+                            code,
+                            href,
+                            anchor_ty,
+                            it.name.unwrap()
+                        )
+                        .unwrap(),
+                        None => writeln!(page, "- `{}`", code).unwrap(),
+                    }
+                }
+                if !items.is_empty() {
+                    page.push('\n');
+                }
+            }
+        }
+
+        let other_impls =
+            [("Auto Trait Implementations", &synthetic), ("Blanket Implementations", &blanket)];
+        for &(title, impls) in &other_impls {
+            if impls.is_empty() {
+                continue;
+            }
+            writeln!(page, "## {}\n", title).unwrap();
+            for i in impls {
+                let impl_ = i.inner_impl();
+                let code = print_impl(impl_, self.tcx).to_string();
+                writeln!(page, "- {}", self.code_link(&code, impl_.trait_.def_id())).unwrap();
+            }
+            page.push('\n');
+        }
+    }
+
+    fn render_implementors(&self, page: &mut String, did: DefId) {
+        let implementors: Vec<_> = self
+            .cache
+            .implementors
+            .get(&did)
+            .map_or(&[][..], |v| v.as_slice())
+            .iter()
+            .filter(|i| !i.inner_impl().synthetic)
+            .collect();
+        if implementors.is_empty() {
+            return;
+        }
+        page.push_str("## Implementors\n\n");
+        for i in implementors {
+            let impl_ = i.inner_impl();
+            let code = print_impl(impl_, self.tcx).to_string();
+            writeln!(page, "- {}", self.code_link(&code, impl_.for_.def_id())).unwrap();
+        }
+        page.push('\n');
+    }
+
+    fn write_page(&self, file_name: &str, page: &str) -> Result<(), Error> {
+        try_err!(fs::create_dir_all(&self.dst), &self.dst);
+        let path = self.dst.join(file_name);
+        try_err!(fs::write(&path, page.trim_end().to_owned() + "\n"), &path);
+        Ok(())
+    }
+}
+
+/// Adapts `doc` to the page it is rendered on, like the HTML renderer does: Rust code blocks are
+/// fenced and tagged as `rust`, without the lines that are hidden in the HTML output, and
+/// headings are moved below the heading of `level` the documentation is nested under.
+fn rewrite_docs(doc: &str, level: u32) -> String {
+    let mut edits = Vec::new();
+    let mut events = Parser::new_ext(doc, opts()).into_offset_iter();
+    while let Some((event, range)) = events.next() {
+        // Leave the trailing newline of blocks alone.
+        let end = range.start + doc[range.clone()].trim_end().len();
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let indented = match kind {
+                    CodeBlockKind::Fenced(ref lang) => {
+                        if !LangString::parse_without_check(lang, ErrorCodes::No, false).rust {
+                            continue;
+                        }
+                        false
+                    }
+                    CodeBlockKind::Indented => true,
+                };
+                let mut code = String::new();
+                for (event, _) in &mut events {
+                    match event {
+                        Event::End(Tag::CodeBlock(_)) => break,
+                        Event::Text(text) => code.push_str(&text),
+                        _ => {}
+                    }
+                }
+
+                // The fence replaces the indentation of indented code blocks.
+                let mut start = range.start;
+                if indented && doc[..start].ends_with("    ") {
+                    start -= 4;
+                }
+                edits.push((start, end, rust_code_block(doc, start, &code)));
+            }
+            Event::Start(Tag::Heading(heading_level)) => {
+                let hashes = "#".repeat(cmp::min(heading_level + level, 6) as usize);
+                let text = &doc[range.start..end];
+                let indentation = text.len() - text.trim_start().len();
+                let is_atx = text[indentation..]
+                    .strip_prefix(&"#".repeat(heading_level as usize))
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+                if is_atx {
+                    let start = range.start + indentation;
+                    edits.push((start, start + heading_level as usize, hashes));
+                } else {
+                    // Setext headings are underlined, which only works for the first two levels.
+                    let content = &text[..text.rfind('\n').unwrap_or(0)];
+                    let content = content.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+                    edits.push((range.start, end, format!("{} {}", hashes, content)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut rewritten = String::with_capacity(doc.len());
+    let mut copied = 0;
+    for (start, end, replacement) in edits {
+        rewritten.push_str(&doc[copied..start]);
+        rewritten.push_str(&replacement);
+        copied = end;
+    }
+    rewritten.push_str(&doc[copied..]);
+    rewritten
+}
+
+/// Formats the lines of `code` that are shown in the HTML output as a fenced Rust code block that
+/// starts at `start` in `doc`.
+fn rust_code_block(doc: &str, start: usize, code: &str) -> String {
+    // The following lines are indented like the first one, and stay in the same block quotes.
+    let line_start = doc[..start].rfind('\n').map_or(0, |i| i + 1);
+    let prefix: String =
+        doc[line_start..start].chars().map(|c| if c == '>' { '>' } else { ' ' }).collect();
+
+    let lines: Vec<_> = code.lines().filter_map(|line| map_line(line).for_html()).collect();
+    let longest_backticks = lines
+        .iter()
+        .flat_map(|line| line.split(|c| c != '`').map(str::len))
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(cmp::max(3, longest_backticks + 1));
+
+    let mut block = format!("{}rust", fence);
+    for line in lines.iter().map(|line| &**line).chain(Some(&*fence)) {
+        block.push('\n');
+        block.push_str(format!("{}{}", prefix, line).trim_end());
+    }
+    block
+}
+
+/// Points the intra-doc links in `doc` at the URLs they were resolved to.
+///
+/// Links written inline (`[text](path)`) or with a reference definition (`[text]: path`) have
+/// their destination replaced. For all other links, like `[path]`, a reference definition is
+/// added at the end of the documentation. Code is left as is.
+fn resolve_links(doc: &str, links: &[RenderedLink]) -> String {
+    // The pieces of `doc`, and whether they are code.
+    let mut pieces = Vec::new();
+    let mut copied = 0;
+    for (event, range) in Parser::new_ext(doc, opts()).into_offset_iter() {
+        if let Event::Start(Tag::CodeBlock(_)) | Event::Code(_) = event {
+            pieces.push((doc[copied..range.start].to_owned(), false));
+            pieces.push((doc[range.clone()].to_owned(), true));
+            copied = range.end;
+        }
+    }
+    pieces.push((doc[copied..].to_owned(), false));
+
+    let mut definitions = String::new();
+    let mut seen = FxHashSet::default();
+    for link in links {
+        if !seen.insert(&link.original_text) {
+            continue;
+        }
+        let inline = format!("]({})", link.original_text);
+        let definition = format!("]: {}", link.original_text);
+        let text = pieces.iter_mut().filter(|(_, is_code)| !is_code).map(|(text, _)| text);
+        let mut found = false;
+        for text in text {
+            if text.contains(&inline) || text.contains(&definition) {
+                found = true;
+                *text = text
+                    .replace(&inline, &format!("]({})", link.href))
+                    .replace(&definition, &format!("]: {}", link.href));
+            }
+        }
+        if !found {
+            writeln!(definitions, "[{}]: {}", link.original_text, link.href).unwrap();
+        }
+    }
+
+    let mut doc: String = pieces.into_iter().map(|(text, _)| text).collect();
+    if !definitions.is_empty() {
+        doc.push_str("\n\n");
+        doc.push_str(definitions.trim_end());
+    }
+    doc
+}
+
+impl<'tcx> FormatRenderer<'tcx> for MarkdownRenderer<'tcx> {
+    fn descr() -> &'static str {
+        "markdown"
+    }
+
+    const RUN_ON_MODULE: bool = true;
+
+    fn init(
+        krate: clean::Crate,
+        options: RenderOptions,
+        cache: Cache,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<(Self, clean::Crate), Error> {
+        debug!("Initializing markdown renderer");
+        Ok((
+            MarkdownRenderer {
+                tcx,
+                dst: options.output,
+                current: Vec::new(),
+                in_stripped_module: false,
+                sort_modules_alphabetically: options.sort_modules_alphabetically,
+                cache: Rc::new(cache),
+            },
+            krate,
+        ))
+    }
+
+    fn make_child_renderer(&self) -> Self {
+        self.clone()
+    }
+
+    fn item(&mut self, item: clean::Item) -> Result<(), Error> {
+        if self.in_stripped_module || item.is_stripped() {
+            return Ok(());
+        }
+        let page = self.render_item(&item);
+        self.write_page(&format!("{}.{}.md", item.type_(), item.name.unwrap()), &page)
+    }
+
+    fn mod_item_in(&mut self, item: &clean::Item) -> Result<(), Error> {
+        if !self.in_stripped_module {
+            self.in_stripped_module = item.is_stripped();
+        }
+        let item_name = item.name.unwrap().to_string();
+        self.dst.push(&item_name);
+        self.current.push(item_name);
+
+        if let clean::ModuleItem(ref module) = *item.kind {
+            if !self.in_stripped_module {
+                let page = self.render_module(item, module);
+                self.write_page("index.md", &page)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn mod_item_out(&mut self) -> Result<(), Error> {
+        self.dst.pop();
+        self.current.pop();
+        Ok(())
+    }
+
+    fn after_krate(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn cache(&self) -> &Cache {
+        &self.cache
+    }
+}
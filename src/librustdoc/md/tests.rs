@@ -0,0 +1,63 @@
+use super::{resolve_links, rewrite_docs};
+use crate::clean::RenderedLink;
+
+fn link(original_text: &str, href: &str) -> RenderedLink {
+    RenderedLink {
+        original_text: original_text.to_owned(),
+        new_text: original_text.to_owned(),
+        href: href.to_owned(),
+    }
+}
+
+#[test]
+fn test_resolve_links_shortcut() {
+    let links = [link("Foo", "struct.Foo.md"), link("`bar`", "../fn.bar.md#method.baz")];
+    assert_eq!(
+        resolve_links("See [Foo] and [`bar`].", &links),
+        "See [Foo] and [`bar`].\n\n[Foo]: struct.Foo.md\n[`bar`]: ../fn.bar.md#method.baz",
+    );
+}
+
+#[test]
+fn test_resolve_links_inline_and_definition() {
+    let links = [link("crate::Foo", "struct.Foo.md"), link("Bar", "https://example.com/Bar.html")];
+    assert_eq!(
+        resolve_links("A [foo](crate::Foo) and a [bar].\n\n[bar]: Bar", &links),
+        "A [foo](struct.Foo.md) and a [bar].\n\n[bar]: https://example.com/Bar.html",
+    );
+}
+
+#[test]
+fn test_resolve_links_duplicates() {
+    let links = [link("Foo", "struct.Foo.md"), link("Foo", "struct.Foo.md")];
+    assert_eq!(resolve_links("[Foo], [Foo]", &links), "[Foo], [Foo]\n\n[Foo]: struct.Foo.md");
+}
+
+#[test]
+fn test_resolve_links_skips_code() {
+    let links = [link("Foo", "struct.Foo.md")];
+    assert_eq!(
+        resolve_links("A [foo](Foo).\n\n```\nlet a = [b](Foo);\n```\n\nAnd `[c](Foo)`.", &links),
+        "A [foo](struct.Foo.md).\n\n```\nlet a = [b](Foo);\n```\n\nAnd `[c](Foo)`.",
+    );
+}
+
+#[test]
+fn test_rewrite_docs_code_blocks() {
+    assert_eq!(
+        rewrite_docs("```\n# use foo::Bar;\nlet bar = Bar;\n```\n\n```text\n# shown\n```", 1),
+        "```rust\nlet bar = Bar;\n```\n\n```text\n# shown\n```",
+    );
+    assert_eq!(
+        rewrite_docs("- ```should_panic\n  # fn main() {\n  panic!();\n  # }\n  ```", 1),
+        "- ```rust\n  panic!();\n  ```",
+    );
+}
+
+#[test]
+fn test_rewrite_docs_headings() {
+    assert_eq!(
+        rewrite_docs("# Examples\n\nText\n\nPanics\n------\n\n##### Deep", 3),
+        "#### Examples\n\nText\n\n##### Panics\n\n###### Deep",
+    );
+}
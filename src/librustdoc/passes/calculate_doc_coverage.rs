@@ -20,10 +20,11 @@
 };
 
 fn calculate_doc_coverage(krate: clean::Crate, ctx: &mut DocContext<'_>) -> clean::Crate {
-    let mut calc = CoverageCalculator { items: Default::default(), ctx };
+    let mut calc = CoverageCalculator { items: Default::default(), path: Vec::new(), ctx };
     let krate = calc.fold_crate(krate);
 
     calc.print_results();
+    calc.check_threshold();
 
     krate
 }
@@ -89,6 +90,29 @@ fn sub(self, rhs: Self) -> Self {
     }
 }
 
+/// The position of an item in its file, as `(line, column)` pairs.
+#[derive(Serialize, Debug)]
+struct ItemSpan {
+    begin: (usize, usize),
+    end: (usize, usize),
+}
+
+/// An item listed in the JSON output because it's missing documentation or an example.
+#[derive(Serialize, Debug)]
+struct ItemEntry {
+    /// The path of the item, based on the modules and types it's nested in.
+    path: String,
+    span: ItemSpan,
+}
+
+#[derive(Default, Serialize, Debug)]
+struct FileCoverage {
+    #[serde(flatten)]
+    count: ItemCount,
+    undocumented: Vec<ItemEntry>,
+    missing_examples: Vec<ItemEntry>,
+}
+
 impl ops::AddAssign for ItemCount {
     fn add_assign(&mut self, rhs: Self) {
         self.total += rhs.total;
@@ -99,7 +123,9 @@ fn add_assign(&mut self, rhs: Self) {
 }
 
 struct CoverageCalculator<'a, 'b> {
-    items: BTreeMap<FileName, ItemCount>,
+    items: BTreeMap<FileName, FileCoverage>,
+    /// The path of the item currently being folded.
+    path: Vec<String>,
     ctx: &'a mut DocContext<'b>,
 }
 
@@ -120,7 +146,7 @@ fn to_json(&self) -> String {
                 .items
                 .iter()
                 .map(|(k, v)| (k.prefer_local().to_string(), v))
-                .collect::<BTreeMap<String, &ItemCount>>(),
+                .collect::<BTreeMap<String, &FileCoverage>>(),
         )
         .expect("failed to convert JSON data to string")
     }
@@ -156,7 +182,7 @@ fn print_table_record(
         );
         print_table_line();
 
-        for (file, &count) in &self.items {
+        for (file, &FileCoverage { count, .. }) in &self.items {
             if let Some(percentage) = count.percentage() {
                 print_table_record(
                     &limit_filename_len(file.prefer_local().to_string_lossy().into()),
@@ -178,6 +204,46 @@ fn print_table_record(
         );
         print_table_line();
     }
+
+    /// Emits an error if the documentation coverage of the whole crate is below the threshold
+    /// given with `--coverage-threshold`.
+    fn check_threshold(&self) {
+        let threshold = match self.ctx.coverage_threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        let mut total = ItemCount::default();
+        for file in self.items.values() {
+            total += file.count;
+        }
+        // A crate without any item to document can't be missing documentation.
+        let percentage = total.percentage().unwrap_or(100.0);
+        if percentage < threshold {
+            self.ctx
+                .sess()
+                .struct_err(&format!(
+                    "documentation coverage of {:.1}% is below the threshold of {}%",
+                    percentage, threshold
+                ))
+                .emit();
+        }
+    }
+
+    fn item_entry(&self, i: &clean::Item) -> ItemEntry {
+        let mut path = self.path.clone();
+        if let Some(name) = i.name {
+            path.push(name.to_string());
+        }
+        let span = i.span(self.ctx.tcx);
+        let (lo, hi) = (span.lo(self.ctx.sess()), span.hi(self.ctx.sess()));
+        ItemEntry {
+            path: path.join("::"),
+            span: ItemSpan {
+                begin: (lo.line, lo.col.to_usize()),
+                end: (hi.line, hi.col.to_usize()),
+            },
+        }
+    }
 }
 
 impl<'a, 'b> fold::DocFolder for CoverageCalculator<'a, 'b> {
@@ -225,16 +291,39 @@ fn fold_item(&mut self, i: clean::Item) -> Option<clean::Item> {
                 // unless the user had an explicit `allow`
                 let should_have_docs =
                     level != lint::Level::Allow || matches!(source, LintLevelSource::Default);
+                let should_have_doc_examples = should_have_doc_example(self.ctx, &i);
                 debug!("counting {:?} {:?} in {:?}", i.type_(), i.name, filename);
-                self.items.entry(filename).or_default().count_item(
+                let undocumented = (should_have_docs && !has_docs).then(|| self.item_entry(&i));
+                let missing_example =
+                    (should_have_doc_examples && !has_doc_example).then(|| self.item_entry(&i));
+                let file = self.items.entry(filename).or_default();
+                file.count.count_item(
                     has_docs,
                     has_doc_example,
-                    should_have_doc_example(self.ctx, &i),
+                    should_have_doc_examples,
                     should_have_docs,
                 );
+                file.undocumented.extend(undocumented);
+                file.missing_examples.extend(missing_example);
             }
         }
 
-        Some(self.fold_item_recur(i))
+        // Items nested in this one have their path start with its name, or with the name of the
+        // type for impls.
+        let segment = match *i.kind {
+            clean::ImplItem(ref impl_) => match impl_.for_ {
+                clean::ResolvedPath { ref path, .. } => Some(path.last().to_string()),
+                _ => None,
+            },
+            _ => i.name.map(|name| name.to_string()),
+        };
+        let has_segment = segment.is_some();
+        self.path.extend(segment);
+        let i = self.fold_item_recur(i);
+        if has_segment {
+            self.path.pop();
+        }
+
+        Some(i)
     }
 }
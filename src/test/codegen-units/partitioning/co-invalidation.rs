@@ -0,0 +1,41 @@
+// Without a previous incremental session there is nothing to learn from, so
+// the co-invalidation partitioning places items like the default one.
+//
+// We specify -C incremental here because we want to test the partitioning for
+// incremental compilation
+// compile-flags:-Zprint-mono-items=lazy -Cincremental=tmp/partitioning-tests/co-invalidation
+// compile-flags:-Zcgu-partitioning-strategy=co-invalidation -Zinline-in-all-cgus
+
+#![allow(dead_code)]
+#![crate_type="lib"]
+
+mod inline {
+
+    //~ MONO_ITEM fn inline::inlined_function @@ co_invalidation-user1[Internal] co_invalidation-user2[Internal]
+    #[inline(always)]
+    pub fn inlined_function()
+    {
+
+    }
+}
+
+pub mod user1 {
+    use super::inline;
+
+    //~ MONO_ITEM fn user1::foo @@ co_invalidation-user1[External]
+    pub fn foo() {
+        inline::inlined_function();
+    }
+
+    //~ MONO_ITEM static user1::BAZ @@ co_invalidation-user1[External]
+    pub static BAZ: u64 = 0;
+}
+
+pub mod user2 {
+    use super::inline;
+
+    //~ MONO_ITEM fn user2::bar @@ co_invalidation-user2[External]
+    pub fn bar() {
+        inline::inlined_function();
+    }
+}
@@ -0,0 +1,38 @@
+// Checks that the co-invalidation partitioning moves functions out of the codegen unit of their
+// module once their inputs are known from the previous session, so that editing a function no
+// longer invalidates the statics of its module.
+
+// revisions: cfail1 cfail2 cfail3
+// compile-flags: -Z query-dep-graph -Z cgu-partitioning-strategy=co-invalidation
+// build-pass
+
+#![feature(rustc_attrs)]
+#![crate_type = "rlib"]
+
+// The first session has no previous dep-graph, so the functions only move out in the second one.
+#![rustc_partition_codegened(module="co_invalidation_partitioning-edited", cfg="cfail2")]
+#![rustc_partition_codegened(module="co_invalidation_partitioning-unrelated", cfg="cfail2")]
+#![rustc_partition_reused(module="co_invalidation_partitioning-edited", cfg="cfail3")]
+#![rustc_partition_reused(module="co_invalidation_partitioning-unrelated", cfg="cfail3")]
+
+pub mod edited {
+    pub static EDITED: u32 = 1;
+
+    #[cfg(any(cfail1, cfail2))]
+    pub fn edited() -> u32 {
+        1
+    }
+
+    #[cfg(cfail3)]
+    pub fn edited() -> u32 {
+        2
+    }
+}
+
+pub mod unrelated {
+    pub static UNRELATED: u32 = 2;
+
+    pub fn unrelated() -> u32 {
+        42
+    }
+}
@@ -0,0 +1,39 @@
+// Checks that tiny leaf functions that are not `#[inline]` are MIR-inlined in incremental builds,
+// and that their callers are optimized and codegened again when the body of the callee changes.
+
+// revisions: cfail1 cfail2
+// compile-flags: -Z query-dep-graph -Z inline-mir
+// build-pass
+
+#![feature(rustc_attrs)]
+#![crate_type = "rlib"]
+
+#![rustc_partition_codegened(module="inlined_leaf_fn-callee", cfg="cfail2")]
+#![rustc_partition_codegened(module="inlined_leaf_fn-caller", cfg="cfail2")]
+#![rustc_partition_reused(module="inlined_leaf_fn-unrelated", cfg="cfail2")]
+
+pub mod callee {
+    #[cfg(cfail1)]
+    pub fn leaf() -> u32 {
+        1
+    }
+
+    #[cfg(cfail2)]
+    pub fn leaf() -> u32 {
+        2
+    }
+}
+
+pub mod caller {
+    #[rustc_clean(except="optimized_mir", cfg="cfail2")]
+    pub fn call_leaf() -> u32 {
+        crate::callee::leaf()
+    }
+}
+
+pub mod unrelated {
+    #[rustc_clean(cfg="cfail2")]
+    pub fn answer() -> u32 {
+        42
+    }
+}
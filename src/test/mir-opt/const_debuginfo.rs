@@ -1,4 +1,4 @@
-// compile-flags: -C overflow-checks=no -Zunsound-mir-opts
+// compile-flags: -C overflow-checks=no -Zunsound-mir-opts -Zmir-sroa=no
 
 struct Point {
     x: u32,
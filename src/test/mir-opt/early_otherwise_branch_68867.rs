@@ -1,4 +1,4 @@
-// compile-flags: -Z mir-opt-level=4 -Zunsound-mir-opts
+// compile-flags: -Z mir-opt-level=4 -Zunsound-mir-opts -Zmir-sroa=no
 
 // example from #68867
 type CSSFloat = f32;
@@ -0,0 +1,22 @@
+// compile-flags: -C overflow-checks=off
+
+// EMIT_MIR gvn.arithmetic.GlobalValueNumbering.diff
+fn arithmetic(x: u32, y: u32) -> u32 {
+    let a = x * y;
+    let b = x * y;
+    a + b
+}
+
+// `x` is modified between the two products, so the second one has to be computed again.
+// EMIT_MIR gvn.reassigned.GlobalValueNumbering.diff
+fn reassigned(mut x: u32, y: u32) -> u32 {
+    let a = x * y;
+    x = y;
+    let b = x * y;
+    a + b
+}
+
+fn main() {
+    arithmetic(2, 3);
+    reassigned(2, 3);
+}
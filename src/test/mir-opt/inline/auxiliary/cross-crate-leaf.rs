@@ -0,0 +1,13 @@
+pub fn answer() -> u32 {
+    42
+}
+
+// Calls to `#[inline(never)]` functions are kept, so this is not a leaf function.
+pub fn not_leaf() -> u32 {
+    opaque()
+}
+
+#[inline(never)]
+fn opaque() -> u32 {
+    7
+}
@@ -0,0 +1,10 @@
+// Checks that tiny leaf functions of other crates are inlined even if they are not `#[inline]`.
+// aux-build:cross-crate-leaf.rs
+
+extern crate cross_crate_leaf;
+
+// EMIT_MIR inline_cross_crate_leaf.main.Inline.diff
+fn main() {
+    let a = cross_crate_leaf::answer();
+    let b = cross_crate_leaf::not_leaf();
+}
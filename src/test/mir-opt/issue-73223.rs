@@ -1,3 +1,5 @@
+// compile-flags: -Zmir-sroa=no
+
 fn main() {
     let split = match Some(1) {
         Some(v) => v,
@@ -0,0 +1,16 @@
+// EMIT_MIR jump_threading.rebuilt.JumpThreading.diff
+fn rebuilt(x: Result<u32, u32>) -> u32 {
+    let y = match x {
+        Ok(v) => Some(v),
+        Err(_) => None,
+    };
+    match y {
+        Some(v) => v,
+        None => 0,
+    }
+}
+
+fn main() {
+    rebuilt(Ok(1));
+    rebuilt(Err(2));
+}
@@ -1,4 +1,4 @@
-// compile-flags: -C overflow-checks=no
+// compile-flags: -C overflow-checks=no -Zmir-sroa=no
 
 fn use_zst(_: ((), ())) {}
 
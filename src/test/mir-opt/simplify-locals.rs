@@ -1,4 +1,4 @@
-// compile-flags: -C overflow-checks=off
+// compile-flags: -C overflow-checks=off -Zmir-sroa=no
 
 #![feature(box_syntax)]
 #![feature(thread_local)]
@@ -0,0 +1,25 @@
+// compile-flags: -C overflow-checks=off
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+// EMIT_MIR sroa.tuple.ScalarReplacementOfAggregates.diff
+fn tuple(a: u32, b: u32) -> u32 {
+    let p = (a, b);
+    p.0 + p.1
+}
+
+// A field of `p` is borrowed, so it has to stay in one piece.
+// EMIT_MIR sroa.borrowed.ScalarReplacementOfAggregates.diff
+fn borrowed(a: u32) -> u32 {
+    let p = Point { x: a, y: a };
+    let r = &p.y;
+    p.x + *r
+}
+
+fn main() {
+    tuple(1, 2);
+    borrowed(3);
+}
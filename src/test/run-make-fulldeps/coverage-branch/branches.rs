@@ -0,0 +1,42 @@
+fn parity(n: u32) -> &'static str {
+    if n % 2 == 0 { "even" } else { "odd" }
+}
+
+fn both(a: bool, b: bool) -> bool {
+    if a && b { true } else { false }
+}
+
+enum Light {
+    Red,
+    Yellow,
+    Green,
+}
+
+fn wait(light: Light) -> u32 {
+    match light {
+        Light::Red => 30,
+        Light::Yellow => 5,
+        Light::Green => 0,
+    }
+}
+
+fn main() {
+    for n in 0..3 {
+        println!("{} is {}", n, parity(n));
+    }
+
+    println!("{}", both(true, true));
+    println!("{}", both(true, false));
+    println!("{}", both(false, true));
+
+    let lights = vec![
+        Light::Red,
+        Light::Red,
+        Light::Red,
+        Light::Yellow,
+        Light::Yellow,
+        Light::Green,
+    ];
+    let total: u32 = lights.into_iter().map(wait).sum();
+    println!("{}", total);
+}
@@ -0,0 +1,11 @@
+fn main() {
+    println!("{}", edited() + untouched());
+}
+
+fn edited() -> u32 {
+    1
+}
+
+fn untouched() -> u32 {
+    2
+}
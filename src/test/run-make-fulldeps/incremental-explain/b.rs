@@ -0,0 +1,11 @@
+fn main() {
+    println!("{}", edited() + untouched());
+}
+
+fn edited() -> u32 {
+    3
+}
+
+fn untouched() -> u32 {
+    2
+}
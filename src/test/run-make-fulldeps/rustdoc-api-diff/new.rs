@@ -0,0 +1,52 @@
+use std::rc::Rc;
+
+pub fn unchanged(x: u32) -> u32 {
+    x
+}
+
+pub fn changed(x: u64) -> u64 {
+    x
+}
+
+pub fn added() {}
+
+pub enum Exhaustive {
+    A,
+    B,
+    C,
+}
+
+#[non_exhaustive]
+pub enum NonExhaustive {
+    A,
+    B,
+    C,
+}
+
+pub trait Trait {
+    fn provided(&self) {}
+
+    fn required(&self);
+}
+
+pub struct NoLongerSend(pub Rc<u32>);
+
+pub fn unchanged_generic<U: Clone>(renamed: &U) -> U {
+    renamed.clone()
+}
+
+pub struct UnchangedGeneric<'b, U>(pub &'b U);
+
+impl<'c, V> UnchangedGeneric<'c, V> {
+    pub fn get(&self) -> &'c V {
+        self.0
+    }
+}
+
+pub struct Converted(pub u64);
+
+impl From<u32> for Converted {
+    fn from(value: u32) -> Self {
+        Converted(value.into())
+    }
+}
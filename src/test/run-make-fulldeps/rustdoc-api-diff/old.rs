@@ -0,0 +1,52 @@
+pub fn unchanged(x: u32) -> u32 {
+    x
+}
+
+pub fn changed(x: u32) -> u32 {
+    x
+}
+
+pub fn removed() {}
+
+pub enum Exhaustive {
+    A,
+    B,
+}
+
+#[non_exhaustive]
+pub enum NonExhaustive {
+    A,
+    B,
+}
+
+pub trait Trait {
+    fn provided(&self) {}
+}
+
+pub struct NoLongerSend(pub u32);
+
+pub fn unchanged_generic<T: Clone>(value: &T) -> T {
+    value.clone()
+}
+
+pub struct UnchangedGeneric<'a, T>(pub &'a T);
+
+impl<'a, T> UnchangedGeneric<'a, T> {
+    pub fn get(&self) -> &'a T {
+        self.0
+    }
+}
+
+pub struct Converted(pub u64);
+
+impl From<u32> for Converted {
+    fn from(value: u32) -> Self {
+        Converted(value.into())
+    }
+}
+
+impl From<u64> for Converted {
+    fn from(value: u64) -> Self {
+        Converted(value)
+    }
+}
@@ -0,0 +1,18 @@
+//! ```
+//! assert_eq!(covered::double(2), 4);
+//! ```
+
+pub fn double(x: u32) -> u32 {
+    x * 2
+}
+
+/// ```
+/// assert_eq!(covered::triple_if_odd(3), 9);
+/// ```
+pub fn triple_if_odd(x: u32) -> u32 {
+    if x % 2 == 1 {
+        x * 3
+    } else {
+        x
+    }
+}
@@ -0,0 +1,13 @@
+#![crate_name = "dep"]
+
+/// A type from another crate.
+pub struct Remote;
+
+/// Things that can greet.
+pub trait Greet {
+    /// Returns a greeting.
+    fn greet(&self) -> String;
+
+    /// Waves, which does nothing by default.
+    fn wave(&self) {}
+}
@@ -0,0 +1,65 @@
+#![crate_name = "foo"]
+
+//! The crate documentation, see [`Widget`].
+
+extern crate dep;
+
+pub mod shapes {
+    /// A circle.
+    pub struct Circle {
+        /// The radius of the circle.
+        pub radius: f64,
+    }
+
+    impl Circle {
+        /// Makes a circle.
+        pub fn new(radius: f64) -> Self {
+            Circle { radius }
+        }
+    }
+}
+
+/// A widget, built from a [`shapes::Circle`] and a [`dep::Remote`].
+///
+/// Use [its getter](Widget::get) to get the inner value.
+pub struct Widget<T: Clone> {
+    /// The inner value.
+    pub inner: T,
+    secret: u8,
+}
+
+impl<T: Clone> Widget<T> {
+    /// Returns the inner value.
+    pub fn get(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone> dep::Greet for Widget<T> {
+    fn greet(&self) -> String {
+        String::from("hello")
+    }
+}
+
+/// The shapes a widget can have.
+pub enum Shape {
+    /// A round shape.
+    Circle(shapes::Circle),
+    /// A square shape.
+    Square { side: f64 },
+}
+
+/// Computes the area of a shape.
+///
+/// # Examples
+///
+/// ```
+/// # use foo::{area, Shape};
+/// assert_eq!(area(&Shape::Square { side: 2.0 }), 4.0);
+/// ```
+pub fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle(c) => c.radius * c.radius * 3.14,
+        Shape::Square { side } => side * side,
+    }
+}
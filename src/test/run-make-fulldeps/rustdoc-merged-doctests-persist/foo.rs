@@ -0,0 +1,17 @@
+//! ```
+//! assert_eq!(1 + 1, 2);
+//! ```
+
+/// ```
+/// let v = vec![1, 2];
+/// assert_eq!(v.len(), 2);
+/// ```
+pub fn merged() {}
+
+/// Crate attributes keep a doctest from being merged.
+///
+/// ```
+/// #![allow(unused)]
+/// let x = 1;
+/// ```
+pub fn separate() {}
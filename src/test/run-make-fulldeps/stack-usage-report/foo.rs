@@ -0,0 +1,37 @@
+#[inline(never)]
+fn big_frame() -> u8 {
+    let buf = [1u8; 1024];
+    buf[7]
+}
+
+#[inline(never)]
+fn recurse(n: u32) -> u32 {
+    if n == 0 { 0 } else { recurse(n - 1) + 1 }
+}
+
+// Only called by themselves, so they are entry points as well.
+#[no_mangle]
+pub extern "C" fn fib(n: u64) -> u64 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+#[no_mangle]
+pub extern "C" fn ping(n: u32) -> u32 {
+    if n == 0 { 0 } else { pong(n - 1) }
+}
+
+#[no_mangle]
+pub extern "C" fn pong(n: u32) -> u32 {
+    if n == 0 { 1 } else { ping(n - 1) }
+}
+
+#[inline(never)]
+fn call_dynamic(f: fn() -> u8) -> u8 {
+    f()
+}
+
+fn main() {
+    big_frame();
+    recurse(3);
+    call_dynamic(big_frame);
+}
@@ -0,0 +1,9 @@
+// compile-flags:-Z unstable-options --show-coverage --coverage-threshold 50
+// check-pass
+
+//! Make sure to have some docs on your crate root
+
+/// This struct is documented
+pub struct Documented;
+
+pub struct Undocumented;
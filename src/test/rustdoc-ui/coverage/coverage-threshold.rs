@@ -0,0 +1,8 @@
+// compile-flags:-Z unstable-options --show-coverage --coverage-threshold 75
+
+//! Make sure to have some docs on your crate root
+
+/// This struct is documented
+pub struct Documented;
+
+pub struct Undocumented;
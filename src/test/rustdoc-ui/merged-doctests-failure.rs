@@ -0,0 +1,13 @@
+// Checks that the locations reported by a failing merged doctest point into the file it comes from.
+
+// compile-flags:--test --test-args=--test-threads=1 -Z unstable-options --merge-doctests
+// rustc-env:RUST_BACKTRACE=0
+// normalize-stdout-test: "src/test/rustdoc-ui" -> "$$DIR"
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+// failure-status: 101
+
+/// ```
+/// let answer = 6 * 7;
+/// assert_eq!(answer, 43);
+/// ```
+pub fn fails() {}
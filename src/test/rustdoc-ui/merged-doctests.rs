@@ -0,0 +1,51 @@
+// Checks that `--merge-doctests` runs the doctests that can be merged from a single binary, and
+// the other ones separately.
+
+// compile-flags:--test --test-args=--test-threads=1 -Z unstable-options --merge-doctests
+// normalize-stdout-test: "src/test/rustdoc-ui" -> "$$DIR"
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+// check-pass
+
+//! ```
+//! assert_eq!(merged_doctests::answer(), 42);
+//! ```
+
+pub fn answer() -> u8 {
+    42
+}
+
+/// ```
+/// let x: u8 = "42".parse()?;
+/// assert_eq!(x, 42);
+/// Ok::<(), std::num::ParseIntError>(())
+/// ```
+pub fn parse() {}
+
+/// ```should_panic
+/// panic!("each merged doctest runs in its own process");
+/// ```
+pub fn panics() {}
+
+/// ```
+/// // Only the merged binary is given the doctest to run as its first argument.
+/// assert!(std::env::args().nth(1).is_some());
+/// ```
+pub fn merged() {}
+
+/// ```
+/// fn main() {
+///     assert!(true);
+/// }
+/// ```
+pub fn with_main() {}
+
+/// ```compile_fail
+/// let x: u8 = "";
+/// ```
+pub fn compile_fail() {}
+
+/// ```
+/// #![allow(dead_code)]
+/// struct Unused;
+/// ```
+pub fn crate_attrs() {}
@@ -0,0 +1,27 @@
+// Tiny leaf functions that are neither generic nor `#[inline]`, but whose MIR is still encoded for
+// cross-crate inlining.
+
+static BASE: u32 = 40;
+
+pub struct Point {
+    x: u32,
+    y: u32,
+}
+
+impl Point {
+    pub fn new(x: u32, y: u32) -> Point {
+        Point { x, y }
+    }
+
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    pub fn offset_sum(&self) -> u32 {
+        self.x + self.y + BASE
+    }
+}
+
+pub fn base() -> u32 {
+    BASE + 2
+}
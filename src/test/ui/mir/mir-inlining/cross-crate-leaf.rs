@@ -0,0 +1,16 @@
+// Checks that tiny leaf functions without `#[inline]` can be inlined across crates, even if they
+// use a private static of their crate.
+// run-pass
+// aux-build:cross-crate-leaf.rs
+// compile-flags: -Zinline-mir
+
+extern crate cross_crate_leaf;
+
+use cross_crate_leaf::{base, Point};
+
+fn main() {
+    let p = Point::new(1, 2);
+    assert_eq!(p.x(), 1);
+    assert_eq!(p.offset_sum(), 43);
+    assert_eq!(base(), 42);
+}
@@ -0,0 +1,78 @@
+// Checks that scalar replacement of aggregates keeps the behavior of locals that are split into
+// their fields, and of the ones that have to be kept intact.
+// run-pass
+// compile-flags: -Zmir-opt-level=1
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+struct Pair {
+    a: u32,
+    b: (u32, u32),
+}
+
+struct Noisy(u32);
+
+impl Drop for Noisy {
+    fn drop(&mut self) {
+        assert_eq!(self.0, 3);
+    }
+}
+
+#[inline(never)]
+fn dot(a: Point, b: Point) -> f64 {
+    let p = (a.x * b.x, a.y * b.y);
+    p.0 + p.1
+}
+
+#[inline(never)]
+fn nested(n: u32) -> u32 {
+    let mut pair = Pair { a: n, b: (n + 1, n + 2) };
+    pair.b.0 += pair.a;
+    pair.b.1 *= 2;
+    pair.a + pair.b.0 + pair.b.1
+}
+
+#[inline(never)]
+fn borrowed(n: u32) -> u32 {
+    let mut t = (n, n);
+    let r = &mut t.1;
+    *r += 1;
+    t.0 + t.1
+}
+
+#[inline(never)]
+fn swapped(n: u32) -> (u32, u32) {
+    let mut t = (n, n + 1);
+    let tmp = t.0;
+    t.0 = t.1;
+    t.1 = tmp;
+    t
+}
+
+#[inline(never)]
+fn loops(n: u32) -> u32 {
+    let mut acc = (0, 1);
+    for _ in 0..n {
+        acc = (acc.1, acc.0 + acc.1);
+    }
+    acc.0
+}
+
+#[inline(never)]
+fn with_drop(n: u32) -> u32 {
+    let noisy = Noisy(n);
+    noisy.0
+}
+
+fn main() {
+    assert_eq!(dot(Point { x: 1.0, y: 2.0 }, Point { x: 3.0, y: 4.0 }), 11.0);
+    assert_eq!(nested(1), 10);
+    assert_eq!(borrowed(1), 3);
+    assert_eq!(swapped(1), (2, 1));
+    assert_eq!(loops(10), 55);
+    assert_eq!(with_drop(3), 3);
+}
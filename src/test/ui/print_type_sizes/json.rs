@@ -0,0 +1,35 @@
+// compile-flags: -Z print-type-sizes=json
+// build-pass
+// ignore-pass
+// ^-- needed because `--pass check` does not emit the output needed.
+//     FIXME: consider using an attribute instead of side-effects.
+
+// This file illustrates the JSON format of `-Z print-type-sizes`, in
+// particular the discriminant and niche information it adds to the text
+// format.
+
+#![feature(start)]
+#![allow(dead_code)]
+
+pub struct Flag {
+    on: bool,
+    value: u8,
+}
+
+pub enum Direct {
+    A(u8),
+    B,
+}
+
+pub enum Niched {
+    A(Flag),
+    B,
+    C,
+}
+
+#[start]
+fn start(_: isize, _: *const *const u8) -> isize {
+    let _d: Direct;
+    let _n: Niched;
+    0
+}
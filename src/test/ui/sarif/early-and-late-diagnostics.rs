@@ -0,0 +1,8 @@
+// compile-flags: -Zunstable-options --error-format=pretty-sarif -C remark=all -C debuginfo=0
+
+// The warning about `-C remark` is reported before there is a session, but it still ends up in
+// the same SARIF log as the diagnostics of the compilation itself.
+
+compile_error!("a very descriptive error message");
+
+fn main() {}
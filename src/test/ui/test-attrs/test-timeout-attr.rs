@@ -0,0 +1,16 @@
+// compile-flags: --test
+// check-pass
+#![feature(test_timeout)]
+
+#[test]
+#[test_timeout = "30s"]
+fn test1() {}
+
+#[test]
+#[test_timeout = "500ms"]
+fn test2() {}
+
+// The duration is only parsed by the test harness, see `test-timeout-malformed.rs`.
+#[test]
+#[test_timeout = "soon"]
+fn test3() {}
@@ -0,0 +1,16 @@
+// compile-flags: --test
+// run-flags: --test-threads=1
+// run-fail
+// check-run-results
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+// ignore-emscripten no threads support
+
+#![feature(test_timeout)]
+
+#[test]
+#[test_timeout = "30s"]
+fn test_ok() {}
+
+#[test]
+#[test_timeout = "soon"]
+fn test_malformed() {}
@@ -506,6 +506,12 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         options: test::Options::new(),
         time_options: None,
         force_run_in_process: false,
+        shuffle: false,
+        shuffle_seed: None,
+        test_timeout: None,
+        shard: None,
+        save_baseline: None,
+        baseline: None,
     }
 }
 
@@ -668,6 +674,8 @@ fn make_test(config: &Config, testpaths: &TestPaths, inputs: &Stamp) -> Vec<test
                     compile_fail: false,
                     #[cfg(not(bootstrap))]
                     no_run: false,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
                     test_type: test::TestType::Unknown,
                 },
                 testfn: make_test_closure(config, testpaths, revision),
@@ -3625,6 +3625,10 @@ fn normalize_output(&self, output: &str, custom_rules: &[(String, String)]) -> S
             || cflags.contains("--error-format pretty-json")
             || cflags.contains("--error-format=json")
             || cflags.contains("--error-format=pretty-json")
+            || cflags.contains("--error-format sarif")
+            || cflags.contains("--error-format pretty-sarif")
+            || cflags.contains("--error-format=sarif")
+            || cflags.contains("--error-format=pretty-sarif")
             || cflags.contains("--output-format json")
             || cflags.contains("--output-format=json");
 